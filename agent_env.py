@@ -0,0 +1,155 @@
+#!/usr/bin/env python3
+"""
+Per-Agent Environment & Secret Injection for Leviathan Super-Brain
+==================================================================
+Manifests can declare environment-style variables for their agent:
+
+    "env": {
+        "REGION": "eu-north-1",
+        "GITHUB_TOKEN": {"secret": "github_token"}
+    }
+
+Plain values pass through; {"secret": name} references resolve against
+the secret store at injection time. Secrets are scoped — a secret stored
+for agent A is invisible to agent B; agent scope wins over the shared
+('' scope) fallback — so one agent can never read another's credentials.
+The kernel injects the resolved map into the agent's tool execution
+context and prompt templates.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import os
+import string
+import logging
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+
+log = logging.getLogger("agent_env")
+
+
+class SecretStore:
+    """Scoped secrets: (scope_agent_id, name) → value. '' scope is shared."""
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS agent_secrets (
+                    scope_agent_id TEXT NOT NULL DEFAULT '',
+                    name TEXT NOT NULL,
+                    value TEXT NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    PRIMARY KEY (scope_agent_id, name)
+                )
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    def set_secret(self, name: str, value: str, scope_agent_id: str = "") -> dict:
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT OR REPLACE INTO agent_secrets
+                   (scope_agent_id, name, value, updated_at) VALUES (?, ?, ?, ?)""",
+                (scope_agent_id, name, value,
+                 datetime.now(timezone.utc).isoformat()),
+            )
+            conn.commit()
+            log.info(f"[SECRETS] Set '{name}' "
+                     f"(scope {scope_agent_id or 'shared'})")
+            return {"name": name, "scope": scope_agent_id or "shared"}
+        finally:
+            conn.close()
+
+    def resolve(self, agent_id: str, name: str) -> str:
+        """Agent-scoped value if present, else the shared one, else None.
+        Never crosses into another agent's scope."""
+        conn = self._connect()
+        try:
+            for scope in (agent_id, ""):
+                row = conn.execute(
+                    "SELECT value FROM agent_secrets "
+                    "WHERE scope_agent_id = ? AND name = ?",
+                    (scope, name),
+                ).fetchone()
+                if row:
+                    return row[0]
+            return None
+        finally:
+            conn.close()
+
+    def list_names(self, agent_id: str = None) -> list:
+        """Secret names visible to an agent (values never listed)."""
+        conn = self._connect()
+        try:
+            scopes = (agent_id, "") if agent_id else ("",)
+            placeholders = ",".join("?" * len(scopes))
+            return [
+                {"name": r[0], "scope": r[1] or "shared"}
+                for r in conn.execute(
+                    f"SELECT name, scope_agent_id FROM agent_secrets "
+                    f"WHERE scope_agent_id IN ({placeholders}) ORDER BY name",
+                    scopes,
+                ).fetchall()
+            ]
+        finally:
+            conn.close()
+
+    def delete_secret(self, name: str, scope_agent_id: str = "") -> bool:
+        conn = self._connect()
+        try:
+            removed = conn.execute(
+                "DELETE FROM agent_secrets WHERE scope_agent_id = ? AND name = ?",
+                (scope_agent_id, name),
+            ).rowcount
+            conn.commit()
+            return removed > 0
+        finally:
+            conn.close()
+
+
+def resolve_env(secret_store: SecretStore, agent_id: str, env_spec: dict) -> dict:
+    """
+    Resolve a manifest 'env' block into a flat dict for injection.
+    Unresolvable secret references resolve to None so the agent sees the
+    key exists but has no value (easier to diagnose than a missing key).
+    """
+    resolved = {}
+    for key, spec in (env_spec or {}).items():
+        if isinstance(spec, dict) and "secret" in spec:
+            value = secret_store.resolve(agent_id, spec["secret"])
+            if value is None:
+                log.warning(f"[SECRETS] {agent_id}: env '{key}' references "
+                            f"unknown secret '{spec['secret']}'")
+            resolved[key] = value
+        else:
+            resolved[key] = spec
+    return resolved
+
+
+def render_prompt(template: str, env: dict) -> str:
+    """Substitute ${VAR} references in a prompt template from the agent's
+    resolved env. Unknown references are left in place rather than
+    erased, so a typo is visible in the rendered prompt."""
+    mapping = {k: v for k, v in (env or {}).items() if v is not None}
+    return string.Template(template or "").safe_substitute(mapping)
+
+
+__all__ = ["SecretStore", "resolve_env", "render_prompt"]
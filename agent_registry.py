@@ -0,0 +1,581 @@
+#!/usr/bin/env python3
+"""
+Agent Registry with Skill/Capability Discovery
+==============================================
+Registered agent manifests can advertise skills ("calendar.create_event",
+"pdf.generate", ...) with optional JSON schemas for their inputs. Router
+and coordinator agents query `find_agents(skill)` at runtime instead of
+hardcoding agent names.
+
+Features:
+  1. register(manifest) — upsert an agent manifest with advertised skills
+  2. find_agents(skill) — exact match plus wildcard ("calendar.*") lookup
+  3. Skill schemas stored alongside, returned to callers for validation
+  4. last_seen heartbeat tracking per agent
+  5. Heartbeat history with uptime/availability and MTTR reporting
+  6. Manifest inheritance — templates define provider/quotas/common tools
+     once; concrete manifests extend them with overrides
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import logging
+from datetime import datetime, timedelta, timezone
+
+STALE_AGENT_ARCHIVE_DIR = os.environ.get("STALE_AGENT_ARCHIVE_DIR", "/data/agent-archive")
+STALE_AGENT_DAYS = int(os.environ.get("STALE_AGENT_DAYS", "30"))
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+
+log = logging.getLogger("agent_registry")
+
+# Manifest keys whose list values merge (union, template entries first)
+# instead of being replaced by the extending manifest
+MERGED_LIST_KEYS = ("skills", "tools")
+
+# Guard against template chains that never bottom out
+MAX_TEMPLATE_DEPTH = 5
+
+
+def _deep_merge(base: dict, override: dict) -> dict:
+    """Template-aware merge: dicts merge recursively, the override wins on
+    conflicts, and MERGED_LIST_KEYS lists union rather than replace."""
+    merged = dict(base)
+    for key, value in override.items():
+        if isinstance(value, dict) and isinstance(merged.get(key), dict):
+            merged[key] = _deep_merge(merged[key], value)
+        elif key in MERGED_LIST_KEYS and isinstance(value, list) \
+                and isinstance(merged.get(key), list):
+            combined = list(merged[key])
+            for item in value:
+                if item not in combined:
+                    combined.append(item)
+            merged[key] = combined
+        else:
+            merged[key] = value
+    return merged
+
+
+class AgentRegistry:
+    """
+    SQLite-backed registry of agent manifests and their advertised skills.
+
+    Tables:
+      agent_manifests — one row per registered agent (manifest JSON + timestamps)
+      agent_skills    — one row per (agent, skill) with optional schema
+    """
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        """Create registry tables if they don't exist."""
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS agent_manifests (
+                    agent_id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    manifest TEXT NOT NULL,
+                    registered_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    last_seen_at TEXT
+                )
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS agent_skills (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    agent_id TEXT NOT NULL,
+                    skill TEXT NOT NULL,
+                    description TEXT,
+                    schema_json TEXT,
+                    UNIQUE(agent_id, skill)
+                )
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_agent_skills_skill
+                ON agent_skills(skill)
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS agent_templates (
+                    template_id TEXT PRIMARY KEY,
+                    template TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                )
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS heartbeat_history (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    agent_id TEXT NOT NULL,
+                    healthy INTEGER NOT NULL,
+                    detail TEXT,
+                    recorded_at TEXT NOT NULL
+                )
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_heartbeat_history_agent
+                ON heartbeat_history(agent_id, recorded_at)
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _now() -> str:
+        return datetime.now(timezone.utc).isoformat()
+
+    def _load_template(self, conn, template_id: str) -> dict:
+        row = conn.execute(
+            "SELECT template FROM agent_templates WHERE template_id = ?",
+            (template_id,),
+        ).fetchone()
+        return json.loads(row[0]) if row else None
+
+    def resolve_manifest(self, manifest: dict, conn=None) -> dict:
+        """
+        Resolve a manifest's 'extends' chain against stored templates:
+        the template's fields apply first, the extending manifest's
+        overrides win, skill/tool lists union. Returns the flattened
+        manifest, or {"error": ...} for unknown templates and chains
+        deeper than MAX_TEMPLATE_DEPTH (which also catches cycles).
+        """
+        own_conn = conn is None
+        if own_conn:
+            conn = self._connect()
+        try:
+            resolved = dict(manifest or {})
+            depth = 0
+            while resolved.get("extends"):
+                if depth >= MAX_TEMPLATE_DEPTH:
+                    return {"error": f"Template chain deeper than "
+                                     f"{MAX_TEMPLATE_DEPTH} (cycle?)"}
+                template_id = resolved.pop("extends")
+                template = self._load_template(conn, template_id)
+                if template is None:
+                    return {"error": f"Unknown template: {template_id}"}
+                resolved = _deep_merge(template, resolved)
+                depth += 1
+            return resolved
+        finally:
+            if own_conn:
+                conn.close()
+
+    def register_template(self, template_id: str, template: dict) -> dict:
+        """
+        Upsert a manifest template. The template may itself extend another
+        template (validated here). Agents extending it are re-synced so a
+        fleet-wide edit — say, a new fallback model — lands everywhere
+        from this one place.
+        """
+        conn = self._connect()
+        try:
+            # Validate the template's own chain before anything depends on it
+            probe = self.resolve_manifest(template, conn=conn)
+            if "error" in probe and "error" not in (template or {}):
+                return probe
+            conn.execute(
+                """INSERT OR REPLACE INTO agent_templates
+                   (template_id, template, updated_at) VALUES (?, ?, ?)""",
+                (template_id, json.dumps(template), self._now()),
+            )
+            conn.commit()
+            dependents = [
+                (r[0], r[1], json.loads(r[2])) for r in conn.execute(
+                    "SELECT agent_id, name, manifest FROM agent_manifests"
+                ).fetchall()
+                if json.loads(r[2]).get("extends")
+            ]
+        finally:
+            conn.close()
+        # Re-register extenders so advertised skills pick up the change.
+        # Cheap enough at fleet sizes we run; manifests store the raw
+        # 'extends' form so reads always see the current template.
+        resynced = 0
+        for agent_id, name, manifest in dependents:
+            result = self.register(agent_id, name, manifest)
+            if "error" not in result:
+                resynced += 1
+        log.info(f"[TEMPLATES] '{template_id}' updated, {resynced} agents re-synced")
+        return {"template_id": template_id, "resynced_agents": resynced}
+
+    def list_templates(self) -> list:
+        conn = self._connect()
+        try:
+            return [
+                {"template_id": r[0], "template": json.loads(r[1]), "updated_at": r[2]}
+                for r in conn.execute(
+                    "SELECT template_id, template, updated_at FROM agent_templates "
+                    "ORDER BY template_id"
+                ).fetchall()
+            ]
+        finally:
+            conn.close()
+
+    def register(self, agent_id: str, name: str, manifest: dict) -> dict:
+        """
+        Upsert an agent manifest. Advertised skills come from the manifest's
+        'skills' list: [{"name": "calendar.create_event", "description": ..,
+        "schema": {...}}, ...]. Plain strings are also accepted. Manifests
+        may extend a template; the raw form is stored and the resolved form
+        drives skill extraction (and is what get_agent returns).
+        """
+        now = self._now()
+        resolved = self.resolve_manifest(manifest)
+        if "error" in resolved and "error" not in (manifest or {}):
+            return resolved
+        skills = resolved.get("skills", [])
+        conn = self._connect()
+        try:
+            existing = conn.execute(
+                "SELECT registered_at FROM agent_manifests WHERE agent_id = ?",
+                (agent_id,),
+            ).fetchone()
+            registered_at = existing[0] if existing else now
+
+            conn.execute(
+                """INSERT OR REPLACE INTO agent_manifests
+                   (agent_id, name, manifest, registered_at, updated_at, last_seen_at)
+                   VALUES (?, ?, ?, ?, ?, ?)""",
+                (agent_id, name, json.dumps(manifest), registered_at, now, now),
+            )
+
+            # Replace the skill set wholesale — the manifest is authoritative
+            conn.execute("DELETE FROM agent_skills WHERE agent_id = ?", (agent_id,))
+            normalized = []
+            for skill in skills:
+                if isinstance(skill, str):
+                    skill = {"name": skill}
+                if not skill.get("name"):
+                    continue
+                normalized.append(skill["name"])
+                conn.execute(
+                    """INSERT OR REPLACE INTO agent_skills
+                       (agent_id, skill, description, schema_json)
+                       VALUES (?, ?, ?, ?)""",
+                    (
+                        agent_id,
+                        skill["name"],
+                        skill.get("description"),
+                        json.dumps(skill["schema"]) if skill.get("schema") else None,
+                    ),
+                )
+            conn.commit()
+            log.info(f"[REGISTER] {name} ({agent_id[:8]}) advertising {len(normalized)} skills")
+            return {"agent_id": agent_id, "name": name, "skills": normalized, "registered_at": registered_at}
+        finally:
+            conn.close()
+
+    def heartbeat(self, agent_id: str, healthy: bool = True, detail: str = None) -> bool:
+        """Record a heartbeat outcome and update last_seen on success.
+        Returns False if the agent is unknown."""
+        conn = self._connect()
+        try:
+            known = conn.execute(
+                "SELECT 1 FROM agent_manifests WHERE agent_id = ?", (agent_id,)
+            ).fetchone() is not None
+            if not known:
+                return False
+            now = self._now()
+            if healthy:
+                conn.execute(
+                    "UPDATE agent_manifests SET last_seen_at = ? WHERE agent_id = ?",
+                    (now, agent_id),
+                )
+            conn.execute(
+                """INSERT INTO heartbeat_history (agent_id, healthy, detail, recorded_at)
+                   VALUES (?, ?, ?, ?)""",
+                (agent_id, 1 if healthy else 0, detail, now),
+            )
+            conn.commit()
+            return True
+        finally:
+            conn.close()
+
+    def uptime_report(self, agent_id: str, days: int = 7) -> dict:
+        """
+        Availability from heartbeat history: per-day ok/fail counts and
+        availability %, plus MTTR — the mean time from the first failed
+        heartbeat of an outage to the next healthy one.
+        """
+        since = (datetime.now(timezone.utc) - timedelta(days=days)).isoformat()
+        conn = self._connect()
+        try:
+            rows = conn.execute(
+                """SELECT healthy, recorded_at FROM heartbeat_history
+                   WHERE agent_id = ? AND recorded_at >= ? ORDER BY recorded_at""",
+                (agent_id, since),
+            ).fetchall()
+
+            per_day = {}
+            for healthy, recorded_at in rows:
+                day = recorded_at[:10]
+                bucket = per_day.setdefault(day, {"ok": 0, "fail": 0})
+                bucket["ok" if healthy else "fail"] += 1
+            for day, bucket in per_day.items():
+                total = bucket["ok"] + bucket["fail"]
+                bucket["availability_pct"] = round(100.0 * bucket["ok"] / total, 2)
+
+            # MTTR: outage starts at the first fail after an ok, ends at
+            # the next ok heartbeat.
+            recoveries = []
+            outage_started = None
+            for healthy, recorded_at in rows:
+                at = datetime.fromisoformat(recorded_at)
+                if not healthy and outage_started is None:
+                    outage_started = at
+                elif healthy and outage_started is not None:
+                    recoveries.append((at - outage_started).total_seconds())
+                    outage_started = None
+
+            total_ok = sum(1 for h, _ in rows if h)
+            return {
+                "agent_id": agent_id,
+                "window_days": days,
+                "heartbeats": len(rows),
+                "availability_pct": round(100.0 * total_ok / len(rows), 2) if rows else None,
+                "per_day": per_day,
+                "outages_recovered": len(recoveries),
+                "mttr_seconds": round(sum(recoveries) / len(recoveries), 1) if recoveries else None,
+                "ongoing_outage_since": outage_started.isoformat() if outage_started else None,
+            }
+        finally:
+            conn.close()
+
+    def find_agents(self, skill: str) -> list:
+        """
+        Find registered agents advertising `skill`.
+
+        Supports trailing wildcard: "calendar.*" matches any skill with the
+        "calendar." prefix. Returns agent records with the matching skill's
+        description and schema attached.
+        """
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            if skill.endswith(".*"):
+                prefix = skill[:-1]  # keep the dot
+                rows = conn.execute(
+                    """SELECT m.agent_id, m.name, m.last_seen_at,
+                              s.skill, s.description, s.schema_json
+                       FROM agent_skills s JOIN agent_manifests m ON m.agent_id = s.agent_id
+                       WHERE s.skill LIKE ?""",
+                    (prefix + "%",),
+                ).fetchall()
+            else:
+                rows = conn.execute(
+                    """SELECT m.agent_id, m.name, m.last_seen_at,
+                              s.skill, s.description, s.schema_json
+                       FROM agent_skills s JOIN agent_manifests m ON m.agent_id = s.agent_id
+                       WHERE s.skill = ?""",
+                    (skill,),
+                ).fetchall()
+
+            results = []
+            for row in rows:
+                entry = dict(row)
+                entry["schema"] = json.loads(entry.pop("schema_json")) if entry.get("schema_json") else None
+                results.append(entry)
+            return results
+        finally:
+            conn.close()
+
+    def get_agent(self, agent_id: str) -> dict:
+        """Fetch a single agent's manifest and skill list."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            row = conn.execute(
+                "SELECT * FROM agent_manifests WHERE agent_id = ?", (agent_id,)
+            ).fetchone()
+            if not row:
+                return {"error": f"Unknown agent: {agent_id}"}
+            agent = dict(row)
+            raw = json.loads(agent["manifest"])
+            agent["manifest"] = self.resolve_manifest(raw, conn=conn)
+            if raw.get("extends"):
+                agent["extends"] = raw["extends"]
+            agent["skills"] = [
+                dict(r) for r in conn.execute(
+                    "SELECT skill, description, schema_json FROM agent_skills WHERE agent_id = ?",
+                    (agent_id,),
+                ).fetchall()
+            ]
+            return agent
+        finally:
+            conn.close()
+
+    def find_stale(self, days: int = STALE_AGENT_DAYS) -> list:
+        """
+        Agents that look like zombies: no heartbeat for `days`, no usage
+        records and no scheduled cron entries in the window. Cross-store
+        checks degrade gracefully on data directories without those tables.
+        """
+        cutoff = (datetime.now(timezone.utc) - timedelta(days=days)).isoformat()
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            stale = []
+            for row in conn.execute(
+                """SELECT agent_id, name, registered_at, last_seen_at
+                   FROM agent_manifests
+                   WHERE last_seen_at IS NULL OR last_seen_at < ?""",
+                (cutoff,),
+            ).fetchall():
+                agent = dict(row)
+                agent_id = agent["agent_id"]
+                try:
+                    recent_turns = conn.execute(
+                        "SELECT COUNT(*) FROM usage_records "
+                        "WHERE agent_id = ? AND created_at >= ?",
+                        (agent_id, cutoff),
+                    ).fetchone()[0]
+                except sqlite3.OperationalError:
+                    recent_turns = 0
+                try:
+                    scheduled = conn.execute(
+                        "SELECT COUNT(*) FROM cron_entries "
+                        "WHERE owner = ? AND status = 'scheduled'",
+                        (agent_id,),
+                    ).fetchone()[0]
+                except sqlite3.OperationalError:
+                    scheduled = 0
+                if recent_turns == 0 and scheduled == 0:
+                    agent["stale_days"] = days
+                    agent["owner_label"] = self._owner_label(conn, agent_id)
+                    stale.append(agent)
+            return stale
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _owner_label(conn, agent_id: str) -> str:
+        row = conn.execute(
+            "SELECT manifest FROM agent_manifests WHERE agent_id = ?", (agent_id,)
+        ).fetchone()
+        if not row:
+            return None
+        try:
+            return json.loads(row[0]).get("labels", {}).get("owner")
+        except (json.JSONDecodeError, AttributeError):
+            return None
+
+    def archive_agent(self, agent_id: str, archive_dir: str = STALE_AGENT_ARCHIVE_DIR) -> dict:
+        """Export the agent's manifest + skills as a bundle, then unregister."""
+        agent = self.get_agent(agent_id)
+        if "error" in agent:
+            return agent
+        os.makedirs(archive_dir, exist_ok=True)
+        stamp = datetime.now(timezone.utc).strftime("%Y%m%d%H%M%S")
+        bundle_path = os.path.join(archive_dir, f"{agent_id}-{stamp}.json")
+        with open(bundle_path, "w") as f:
+            json.dump({"archived_at": self._now(), "agent": agent}, f, indent=2)
+
+        conn = self._connect()
+        try:
+            conn.execute("DELETE FROM agent_skills WHERE agent_id = ?", (agent_id,))
+            conn.execute("DELETE FROM agent_manifests WHERE agent_id = ?", (agent_id,))
+            conn.commit()
+        finally:
+            conn.close()
+        log.info(f"[GC] Archived agent {agent_id} to {bundle_path}")
+        return {"agent_id": agent_id, "bundle": bundle_path, "unregistered": True}
+
+    def select_by_labels(self, selector: dict, include_ephemeral: bool = False) -> list:
+        """
+        Agent IDs whose resolved manifest labels match every key/value in
+        the selector (string comparison, kubectl-style). An empty selector
+        matches nothing — bulk operations should never fan out by accident.
+        """
+        if not selector:
+            return []
+        conn = self._connect()
+        try:
+            matched = []
+            for agent_id, manifest_json in conn.execute(
+                "SELECT agent_id, manifest FROM agent_manifests"
+            ).fetchall():
+                try:
+                    raw = json.loads(manifest_json)
+                except json.JSONDecodeError:
+                    continue
+                resolved = self.resolve_manifest(raw, conn=conn)
+                labels = resolved.get("labels", {}) if "error" not in resolved else {}
+                if labels.get("ephemeral") and not include_ephemeral:
+                    continue
+                if all(str(labels.get(k)) == str(v) for k, v in selector.items()):
+                    matched.append(agent_id)
+            return matched
+        finally:
+            conn.close()
+
+    def patch_manifest(self, agent_id: str, patch: dict) -> dict:
+        """
+        Shallow-merge a patch into the agent's RAW manifest and
+        re-register (a None value removes the key). Template links are
+        preserved — the patch lands on top of whatever 'extends' brings in.
+        """
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                "SELECT name, manifest FROM agent_manifests WHERE agent_id = ?",
+                (agent_id,),
+            ).fetchone()
+        finally:
+            conn.close()
+        if not row:
+            return {"error": f"Unknown agent: {agent_id}"}
+        name, manifest_json = row
+        manifest = json.loads(manifest_json)
+        for key, value in (patch or {}).items():
+            if value is None:
+                manifest.pop(key, None)
+            else:
+                manifest[key] = value
+        return self.register(agent_id, name, manifest)
+
+    def list_agents(self, include_ephemeral: bool = False) -> list:
+        """List registered agents with their skill names. Ephemeral agents
+        (manifest label 'ephemeral') are hidden unless asked for — the
+        normal fleet view is about the permanent roster."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            agents = []
+            for row in conn.execute(
+                """SELECT agent_id, name, manifest, registered_at, updated_at, last_seen_at
+                   FROM agent_manifests"""
+            ).fetchall():
+                agent = dict(row)
+                try:
+                    labels = json.loads(agent.pop("manifest")).get("labels", {})
+                except (json.JSONDecodeError, AttributeError):
+                    labels = {}
+                if labels.get("ephemeral") and not include_ephemeral:
+                    continue
+                agents.append(agent)
+            for agent in agents:
+                agent["skills"] = [
+                    r[0] for r in conn.execute(
+                        "SELECT skill FROM agent_skills WHERE agent_id = ?",
+                        (agent["agent_id"],),
+                    ).fetchall()
+                ]
+            return agents
+        finally:
+            conn.close()
+
+
+__all__ = ["AgentRegistry"]
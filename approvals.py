@@ -0,0 +1,224 @@
+#!/usr/bin/env python3
+"""
+Approval Module for Leviathan Super-Brain
+=========================================
+Generic pending-approval queue for actions that need a human (or the CTO
+agent) to sign off before execution: expensive turns, destructive ops,
+spend overrides. Callers create an approval request, poll (or get told
+via Discord), and the gated action proceeds only once the request is
+approved.
+
+First consumer: turn-level cost confirmation — per-agent policies define
+a USD threshold above which an estimated turn must be confirmed first.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import uuid
+import logging
+from datetime import datetime, timedelta, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+APPROVAL_TTL_MINUTES = int(os.environ.get("APPROVAL_TTL_MINUTES", "30"))
+
+# Default threshold when an agent has no explicit policy: never require
+# confirmation (opt-in behaviour).
+DEFAULT_CONFIRM_THRESHOLD_USD = None
+
+log = logging.getLogger("approvals")
+
+
+class ApprovalModule:
+    """
+    SQLite-backed approval queue plus per-agent cost-confirmation policies.
+
+    Tables:
+      approvals           — pending/approved/denied/expired requests
+      agent_cost_policies — per-agent confirm_threshold_usd
+    """
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        """Create approval tables if they don't exist."""
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS approvals (
+                    approval_id TEXT PRIMARY KEY,
+                    kind TEXT NOT NULL,
+                    agent_id TEXT,
+                    payload TEXT,
+                    status TEXT NOT NULL DEFAULT 'pending',
+                    requested_at TEXT NOT NULL,
+                    expires_at TEXT,
+                    resolved_at TEXT,
+                    resolved_by TEXT,
+                    resolution_note TEXT
+                )
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS agent_cost_policies (
+                    agent_id TEXT PRIMARY KEY,
+                    confirm_threshold_usd REAL,
+                    updated_at TEXT NOT NULL
+                )
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_approvals_status
+                ON approvals(status, requested_at)
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _now_dt() -> datetime:
+        return datetime.now(timezone.utc)
+
+    def request_approval(self, kind: str, agent_id: str = None, payload: dict = None,
+                         ttl_minutes: int = APPROVAL_TTL_MINUTES) -> dict:
+        """Create a pending approval request. Returns the stored request."""
+        approval_id = uuid.uuid4().hex[:12]
+        now = self._now_dt()
+        expires = now + timedelta(minutes=ttl_minutes)
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT INTO approvals
+                   (approval_id, kind, agent_id, payload, status, requested_at, expires_at)
+                   VALUES (?, ?, ?, ?, 'pending', ?, ?)""",
+                (approval_id, kind, agent_id, json.dumps(payload or {}),
+                 now.isoformat(), expires.isoformat()),
+            )
+            conn.commit()
+            log.info(f"[APPROVAL] New {kind} request {approval_id} for {agent_id or 'global'}")
+            return self.get(approval_id)
+        finally:
+            conn.close()
+
+    def resolve(self, approval_id: str, decision: str, resolved_by: str, note: str = None) -> dict:
+        """Approve or deny a pending request. decision ∈ {approved, denied}."""
+        if decision not in ("approved", "denied"):
+            return {"error": f"Invalid decision: {decision}"}
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                "SELECT status, expires_at FROM approvals WHERE approval_id = ?",
+                (approval_id,),
+            ).fetchone()
+            if not row:
+                return {"error": f"Unknown approval: {approval_id}"}
+            status, expires_at = row
+            if status != "pending":
+                return {"error": f"Approval already {status}"}
+            if expires_at and expires_at < self._now_dt().isoformat():
+                conn.execute(
+                    "UPDATE approvals SET status = 'expired' WHERE approval_id = ?",
+                    (approval_id,),
+                )
+                conn.commit()
+                return {"error": "Approval request expired"}
+
+            conn.execute(
+                """UPDATE approvals SET status = ?, resolved_at = ?, resolved_by = ?, resolution_note = ?
+                   WHERE approval_id = ?""",
+                (decision, self._now_dt().isoformat(), resolved_by, note, approval_id),
+            )
+            conn.commit()
+            log.info(f"[APPROVAL] {approval_id} {decision} by {resolved_by}")
+            return self.get(approval_id)
+        finally:
+            conn.close()
+
+    def get(self, approval_id: str) -> dict:
+        """Fetch one approval request, auto-expiring if past TTL."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            row = conn.execute(
+                "SELECT * FROM approvals WHERE approval_id = ?", (approval_id,)
+            ).fetchone()
+            if not row:
+                return {"error": f"Unknown approval: {approval_id}"}
+            entry = dict(row)
+            if entry["status"] == "pending" and entry["expires_at"] and \
+                    entry["expires_at"] < self._now_dt().isoformat():
+                conn.execute(
+                    "UPDATE approvals SET status = 'expired' WHERE approval_id = ?",
+                    (approval_id,),
+                )
+                conn.commit()
+                entry["status"] = "expired"
+            entry["payload"] = json.loads(entry["payload"]) if entry["payload"] else {}
+            return entry
+        finally:
+            conn.close()
+
+    def list_requests(self, status: str = "pending", limit: int = 100) -> list:
+        """List approval requests by status."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            rows = conn.execute(
+                "SELECT * FROM approvals WHERE status = ? ORDER BY requested_at DESC LIMIT ?",
+                (status, limit),
+            ).fetchall()
+            results = []
+            for row in rows:
+                entry = dict(row)
+                entry["payload"] = json.loads(entry["payload"]) if entry["payload"] else {}
+                results.append(entry)
+            return results
+        finally:
+            conn.close()
+
+    def is_approved(self, approval_id: str) -> bool:
+        """True only if the request exists and was approved."""
+        return self.get(approval_id).get("status") == "approved"
+
+    # ── Per-agent cost confirmation policies ──
+
+    def set_cost_policy(self, agent_id: str, confirm_threshold_usd: float) -> dict:
+        """Set (or clear, with None) an agent's cost confirmation threshold."""
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT OR REPLACE INTO agent_cost_policies
+                   (agent_id, confirm_threshold_usd, updated_at)
+                   VALUES (?, ?, ?)""",
+                (agent_id, confirm_threshold_usd, self._now_dt().isoformat()),
+            )
+            conn.commit()
+            return {"agent_id": agent_id, "confirm_threshold_usd": confirm_threshold_usd}
+        finally:
+            conn.close()
+
+    def get_cost_policy(self, agent_id: str) -> float:
+        """Return the agent's confirm threshold in USD, or None if unset."""
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                "SELECT confirm_threshold_usd FROM agent_cost_policies WHERE agent_id = ?",
+                (agent_id,),
+            ).fetchone()
+            return row[0] if row else DEFAULT_CONFIRM_THRESHOLD_USD
+        finally:
+            conn.close()
+
+
+__all__ = ["ApprovalModule"]
@@ -0,0 +1,184 @@
+#!/usr/bin/env python3
+"""
+Workflow Artifact Store for Leviathan Super-Brain
+=================================================
+Named outputs of workflow steps (architecture blueprints, generated
+code, verification verdicts, JSON reports) persisted with lineage back
+to the run and step that produced them — so pipeline outputs survive
+past the chat message that carried them. Metadata lives in SQLite,
+content on disk under ARTIFACTS_DIR, keyed so one run's artifacts sit
+together and can be listed, fetched or referenced from digest messages.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import uuid
+import hashlib
+import logging
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+ARTIFACTS_DIR = os.environ.get("ARTIFACTS_DIR", "/data/artifacts")
+
+# Single-artifact ceiling — a step emitting more than this is a bug,
+# not a deliverable
+ARTIFACT_MAX_BYTES = int(os.environ.get("ARTIFACT_MAX_BYTES", str(10 * 1024 * 1024)))
+
+log = logging.getLogger("artifacts")
+
+
+class ArtifactStore:
+    """SQLite metadata + on-disk content for workflow step outputs."""
+
+    def __init__(self, db_path: str = DB_PATH, artifacts_dir: str = ARTIFACTS_DIR):
+        self.db_path = db_path
+        self.artifacts_dir = artifacts_dir
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS workflow_artifacts (
+                    artifact_id TEXT PRIMARY KEY,
+                    run_id TEXT NOT NULL,
+                    step TEXT NOT NULL,
+                    name TEXT NOT NULL,
+                    kind TEXT NOT NULL DEFAULT 'text',
+                    path TEXT NOT NULL,
+                    size_bytes INTEGER NOT NULL,
+                    sha256 TEXT NOT NULL,
+                    created_at TEXT NOT NULL
+                )
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_artifacts_run
+                ON workflow_artifacts(run_id, created_at)
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    def store(self, run_id: str, step: str, name: str, content,
+              kind: str = "text") -> dict:
+        """
+        Persist one step output. kind: 'text' (str), 'json' (any
+        JSON-serializable value) or 'file' (bytes). Content lands under
+        ARTIFACTS_DIR/<run_id>/ and the metadata row carries the lineage.
+        """
+        if kind == "json":
+            data = json.dumps(content, indent=2).encode()
+        elif kind == "file":
+            data = content if isinstance(content, bytes) else str(content).encode()
+        elif kind == "text":
+            data = (content or "").encode()
+        else:
+            return {"error": f"Unknown artifact kind: {kind} (text, json or file)"}
+        if len(data) > ARTIFACT_MAX_BYTES:
+            return {"error": f"Artifact too large: {len(data)} bytes "
+                             f"(limit {ARTIFACT_MAX_BYTES})"}
+
+        artifact_id = f"art-{uuid.uuid4().hex[:12]}"
+        run_dir = os.path.join(self.artifacts_dir, run_id)
+        os.makedirs(run_dir, exist_ok=True)
+        safe_name = "".join(c if c.isalnum() or c in "._-" else "_" for c in name)
+        path = os.path.join(run_dir, f"{artifact_id}-{safe_name}")
+        with open(path, "wb") as f:
+            f.write(data)
+
+        meta = {
+            "artifact_id": artifact_id,
+            "run_id": run_id,
+            "step": step,
+            "name": name,
+            "kind": kind,
+            "size_bytes": len(data),
+            "sha256": hashlib.sha256(data).hexdigest(),
+            "created_at": datetime.now(timezone.utc).isoformat(),
+        }
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT INTO workflow_artifacts
+                   (artifact_id, run_id, step, name, kind, path, size_bytes,
+                    sha256, created_at)
+                   VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)""",
+                (artifact_id, run_id, step, name, kind, path,
+                 meta["size_bytes"], meta["sha256"], meta["created_at"]),
+            )
+            conn.commit()
+        finally:
+            conn.close()
+        log.info(f"[ARTIFACT] {run_id}/{step}: stored '{name}' "
+                 f"({meta['size_bytes']} bytes) as {artifact_id}")
+        return meta
+
+    def get(self, artifact_id: str) -> dict:
+        """Metadata for one artifact; None if unknown."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            row = conn.execute(
+                "SELECT * FROM workflow_artifacts WHERE artifact_id = ?",
+                (artifact_id,),
+            ).fetchone()
+            return dict(row) if row else None
+        finally:
+            conn.close()
+
+    def read(self, artifact_id: str):
+        """Artifact content: str for text, parsed value for json, bytes
+        for file. Returns an error dict if the metadata or the file on
+        disk has gone missing."""
+        meta = self.get(artifact_id)
+        if not meta:
+            return {"error": f"Unknown artifact: {artifact_id}"}
+        try:
+            with open(meta["path"], "rb") as f:
+                data = f.read()
+        except OSError as e:
+            return {"error": f"Artifact content unreadable: {e}"}
+        if meta["kind"] == "json":
+            return json.loads(data)
+        if meta["kind"] == "text":
+            return data.decode(errors="replace")
+        return data
+
+    def list_for_run(self, run_id: str) -> list:
+        """All artifacts a run produced, oldest first, with lineage."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            return [dict(r) for r in conn.execute(
+                """SELECT artifact_id, run_id, step, name, kind, size_bytes,
+                          sha256, created_at
+                   FROM workflow_artifacts WHERE run_id = ?
+                   ORDER BY created_at""",
+                (run_id,),
+            ).fetchall()]
+        finally:
+            conn.close()
+
+    def manifest_lines(self, run_id: str) -> list:
+        """Short per-artifact reference lines for digest/report messages
+        ('architecture (12.3 KB) — art-ab12cd34ef56')."""
+        lines = []
+        for a in self.list_for_run(run_id):
+            size_kb = a["size_bytes"] / 1024
+            lines.append(f"{a['name']} ({size_kb:.1f} KB) — {a['artifact_id']}")
+        return lines
+
+
+__all__ = ["ArtifactStore"]
@@ -0,0 +1,155 @@
+#!/usr/bin/env python3
+"""
+Usage Webhook Sink for Leviathan Super-Brain
+============================================
+Pushes usage events to an external billing endpoint so enterprise
+chargeback systems get spend as it happens instead of polling SQLite.
+Hangs off the UsageStore observer hook like the Prometheus and OTLP
+exporters: events buffer in memory and a background thread POSTs them
+in signed batches (HMAC-SHA256, same X-Leviathan-Signature header the
+webhook notifier uses), with bounded retries per batch. Unlike the OTLP
+exporter, a failed batch goes back to the front of the buffer — billing
+data is too valuable to drop while the buffer has room.
+
+Off unless BILLING_WEBHOOK_URL is set.
+
+Author: Leviathan DevOps
+"""
+
+import json
+import os
+import time
+import hmac
+import hashlib
+import logging
+import threading
+import urllib.request
+import urllib.error
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+BILLING_WEBHOOK_URL = os.environ.get("BILLING_WEBHOOK_URL", "")
+BILLING_WEBHOOK_SECRET = os.environ.get("BILLING_WEBHOOK_SECRET", "")
+
+BILLING_BATCH_SIZE = int(os.environ.get("BILLING_BATCH_SIZE", "100"))
+BILLING_FLUSH_SECONDS = int(os.environ.get("BILLING_FLUSH_SECONDS", "30"))
+BILLING_BUFFER_MAX = int(os.environ.get("BILLING_BUFFER_MAX", "10000"))
+BILLING_TIMEOUT_SECONDS = int(os.environ.get("BILLING_TIMEOUT_SECONDS", "10"))
+BILLING_MAX_ATTEMPTS = int(os.environ.get("BILLING_MAX_ATTEMPTS", "3"))
+BILLING_RETRY_BACKOFF_SECONDS = float(os.environ.get("BILLING_RETRY_BACKOFF_SECONDS", "2"))
+
+log = logging.getLogger("billing_webhook")
+
+
+class BillingWebhookSink:
+    """Usage records → signed, batched POSTs to a billing endpoint."""
+
+    def __init__(self, url: str = BILLING_WEBHOOK_URL,
+                 secret: str = BILLING_WEBHOOK_SECRET):
+        self.url = url
+        self.secret = secret
+        self.enabled = bool(self.url)
+        self._buffer = []
+        self._lock = threading.Lock()
+        self.delivered = 0
+        self.dropped = 0
+        self.last_error = None
+        if self.enabled:
+            worker = threading.Thread(target=self._flush_loop, daemon=True,
+                                      name="BillingWebhookFlush")
+            worker.start()
+            log.info(f"[BILLING] Shipping usage batches to {self.url} "
+                     f"(batch {BILLING_BATCH_SIZE}, flush {BILLING_FLUSH_SECONDS}s, "
+                     f"{'signed' if self.secret else 'UNSIGNED'})")
+        else:
+            log.info("[BILLING] BILLING_WEBHOOK_URL not set — usage webhook disabled")
+
+    def observe(self, record: dict):
+        """UsageStore observer: buffer one usage event for the next batch.
+        At BILLING_BUFFER_MAX the oldest event is dropped (and counted) —
+        better a hole in chargeback than unbounded memory."""
+        if not self.enabled:
+            return
+        with self._lock:
+            if len(self._buffer) >= BILLING_BUFFER_MAX:
+                self._buffer.pop(0)
+                self.dropped += 1
+            self._buffer.append(record)
+
+    def _flush_loop(self):
+        while True:
+            time.sleep(BILLING_FLUSH_SECONDS)
+            try:
+                self.flush()
+            except Exception as e:
+                log.error(f"[BILLING] Flush loop error: {e}")
+
+    def _sign(self, body: bytes) -> str:
+        return hmac.new(self.secret.encode(), body, hashlib.sha256).hexdigest()
+
+    def flush(self) -> int:
+        """Ship buffered events in batches of BILLING_BATCH_SIZE; returns
+        how many were delivered. A batch that fails all its attempts goes
+        back to the front of the buffer for the next flush."""
+        sent = 0
+        while True:
+            with self._lock:
+                if not self._buffer:
+                    return sent
+                batch = self._buffer[:BILLING_BATCH_SIZE]
+                self._buffer = self._buffer[BILLING_BATCH_SIZE:]
+            if not self._post(batch):
+                with self._lock:
+                    self._buffer = batch + self._buffer
+                return sent
+            sent += len(batch)
+
+    def _post(self, batch: list) -> bool:
+        payload = json.dumps({
+            "source": "leviathan-super-brain",
+            "sent_at": datetime.now(timezone.utc).isoformat(),
+            "count": len(batch),
+            "events": batch,
+        }).encode()
+        headers = {"Content-Type": "application/json"}
+        if self.secret:
+            headers["X-Leviathan-Signature"] = self._sign(payload)
+
+        last_error = None
+        for attempt in range(1, BILLING_MAX_ATTEMPTS + 1):
+            request = urllib.request.Request(self.url, data=payload, headers=headers)
+            try:
+                with urllib.request.urlopen(request, timeout=BILLING_TIMEOUT_SECONDS):
+                    pass
+                self.delivered += len(batch)
+                self.last_error = None
+                return True
+            except urllib.error.HTTPError as e:
+                last_error = f"HTTP {e.code}"
+            except (urllib.error.URLError, OSError) as e:
+                last_error = str(e)
+            if attempt < BILLING_MAX_ATTEMPTS:
+                time.sleep(BILLING_RETRY_BACKOFF_SECONDS * attempt)
+
+        self.last_error = last_error
+        log.warning(f"[BILLING] Batch of {len(batch)} events failed after "
+                    f"{BILLING_MAX_ATTEMPTS} attempts: {last_error} — requeued")
+        return False
+
+    def status(self) -> dict:
+        with self._lock:
+            buffered = len(self._buffer)
+        return {
+            "enabled": self.enabled,
+            "url": self.url or None,
+            "signed": bool(self.secret),
+            "buffered": buffered,
+            "delivered": self.delivered,
+            "dropped": self.dropped,
+            "last_error": self.last_error,
+        }
+
+
+__all__ = ["BillingWebhookSink"]
@@ -0,0 +1,248 @@
+#!/usr/bin/env python3
+"""
+Broadcast / Announcement API for Leviathan Super-Brain
+======================================================
+Send one message from an agent to a selected set of paired users or
+channels — maintenance notices, product announcements — selected by
+label or tenant. Features:
+  - Recipient directory with labels, tenant and per-user opt-out
+  - Per-gateway rate limiting so a broadcast can't trip provider limits
+  - Per-recipient delivery report persisted with each broadcast
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import time
+import uuid
+import logging
+import threading
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+BROADCAST_RATE_PER_MINUTE = int(os.environ.get("BROADCAST_RATE_PER_MINUTE", "30"))
+
+log = logging.getLogger("broadcast")
+
+_rate_lock = threading.Lock()
+_rate_windows = {}  # gateway → list of send timestamps (last 60s)
+
+
+def _gateway_slot(gateway: str) -> float:
+    """Blocking rate limiter: returns the seconds waited for a send slot."""
+    waited = 0.0
+    while True:
+        now = time.time()
+        with _rate_lock:
+            window = [t for t in _rate_windows.get(gateway, []) if now - t < 60]
+            if len(window) < BROADCAST_RATE_PER_MINUTE:
+                window.append(now)
+                _rate_windows[gateway] = window
+                return waited
+            sleep_for = 60 - (now - window[0]) + 0.05
+            _rate_windows[gateway] = window
+        time.sleep(min(sleep_for, 2.0))
+        waited += min(sleep_for, 2.0)
+
+
+class BroadcastManager:
+    """Recipient directory + broadcast delivery reports, SQLite-backed."""
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS broadcast_recipients (
+                    user_ref TEXT NOT NULL,
+                    gateway TEXT NOT NULL,
+                    tenant_id TEXT,
+                    labels TEXT,
+                    opted_out INTEGER NOT NULL DEFAULT 0,
+                    registered_at TEXT NOT NULL,
+                    PRIMARY KEY (user_ref, gateway)
+                )
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS broadcasts (
+                    broadcast_id TEXT PRIMARY KEY,
+                    agent_id TEXT,
+                    text TEXT NOT NULL,
+                    selector TEXT,
+                    sent INTEGER NOT NULL DEFAULT 0,
+                    failed INTEGER NOT NULL DEFAULT 0,
+                    skipped_optout INTEGER NOT NULL DEFAULT 0,
+                    created_at TEXT NOT NULL
+                )
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS broadcast_deliveries (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    broadcast_id TEXT NOT NULL,
+                    user_ref TEXT NOT NULL,
+                    gateway TEXT NOT NULL,
+                    status TEXT NOT NULL,
+                    error TEXT,
+                    sent_at TEXT
+                )
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _now() -> str:
+        return datetime.now(timezone.utc).isoformat()
+
+    # ── Recipient directory ──
+
+    def add_recipient(self, user_ref: str, gateway: str, tenant_id: str = None,
+                      labels: list = None) -> dict:
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT OR REPLACE INTO broadcast_recipients
+                   (user_ref, gateway, tenant_id, labels, opted_out, registered_at)
+                   VALUES (?, ?, ?, ?,
+                           COALESCE((SELECT opted_out FROM broadcast_recipients
+                                     WHERE user_ref = ? AND gateway = ?), 0), ?)""",
+                (user_ref, gateway, tenant_id, json.dumps(labels or []),
+                 user_ref, gateway, self._now()),
+            )
+            conn.commit()
+            return {"user_ref": user_ref, "gateway": gateway,
+                    "tenant_id": tenant_id, "labels": labels or []}
+        finally:
+            conn.close()
+
+    def set_opt_out(self, user_ref: str, gateway: str, opted_out: bool) -> dict:
+        conn = self._connect()
+        try:
+            cur = conn.execute(
+                "UPDATE broadcast_recipients SET opted_out = ? "
+                "WHERE user_ref = ? AND gateway = ?",
+                (1 if opted_out else 0, user_ref, gateway),
+            )
+            conn.commit()
+            if cur.rowcount == 0:
+                return {"error": f"Unknown recipient: {user_ref}@{gateway}"}
+            log.info(f"[BROADCAST] {user_ref}@{gateway} opted "
+                     f"{'out' if opted_out else 'in'}")
+            return {"user_ref": user_ref, "gateway": gateway, "opted_out": opted_out}
+        finally:
+            conn.close()
+
+    def select_recipients(self, label: str = None, tenant_id: str = None) -> list:
+        """Recipients matching the selector, opted-out ones flagged."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            query = "SELECT * FROM broadcast_recipients WHERE 1=1"
+            params = []
+            if tenant_id:
+                query += " AND tenant_id = ?"
+                params.append(tenant_id)
+            selected = []
+            for row in conn.execute(query, params).fetchall():
+                record = dict(row)
+                record["labels"] = json.loads(record["labels"] or "[]")
+                if label and label not in record["labels"]:
+                    continue
+                selected.append(record)
+            return selected
+        finally:
+            conn.close()
+
+    # ── Broadcasting ──
+
+    def broadcast(self, gateway_manager, text: str, agent_id: str = None,
+                  label: str = None, tenant_id: str = None) -> dict:
+        """Send `text` to every matching, opted-in recipient through their
+        gateway, rate-limited per gateway. Returns the delivery report."""
+        broadcast_id = f"bcast-{uuid.uuid4().hex[:12]}"
+        recipients = self.select_recipients(label=label, tenant_id=tenant_id)
+        selector = json.dumps({"label": label, "tenant_id": tenant_id})
+
+        sent = failed = skipped = 0
+        deliveries = []
+        for recipient in recipients:
+            if recipient["opted_out"]:
+                skipped += 1
+                deliveries.append((recipient, "skipped_optout", None))
+                continue
+            gw = gateway_manager.get(recipient["gateway"])
+            if not gw:
+                failed += 1
+                deliveries.append((recipient, "failed",
+                                   f"unknown gateway {recipient['gateway']}"))
+                continue
+            _gateway_slot(recipient["gateway"])
+            result = gw.send_message(recipient["user_ref"], text)
+            if "error" in result:
+                failed += 1
+                deliveries.append((recipient, "failed", result["error"]))
+            else:
+                sent += 1
+                deliveries.append((recipient, "sent", None))
+
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT INTO broadcasts
+                   (broadcast_id, agent_id, text, selector, sent, failed,
+                    skipped_optout, created_at)
+                   VALUES (?, ?, ?, ?, ?, ?, ?, ?)""",
+                (broadcast_id, agent_id, text[:2000], selector, sent, failed,
+                 skipped, self._now()),
+            )
+            for recipient, status, error in deliveries:
+                conn.execute(
+                    """INSERT INTO broadcast_deliveries
+                       (broadcast_id, user_ref, gateway, status, error, sent_at)
+                       VALUES (?, ?, ?, ?, ?, ?)""",
+                    (broadcast_id, recipient["user_ref"], recipient["gateway"],
+                     status, error, self._now()),
+                )
+            conn.commit()
+        finally:
+            conn.close()
+
+        log.info(f"[BROADCAST] {broadcast_id}: {sent} sent, {failed} failed, "
+                 f"{skipped} opted out")
+        return {"broadcast_id": broadcast_id, "recipients": len(recipients),
+                "sent": sent, "failed": failed, "skipped_optout": skipped}
+
+    def report(self, broadcast_id: str) -> dict:
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            row = conn.execute(
+                "SELECT * FROM broadcasts WHERE broadcast_id = ?", (broadcast_id,)
+            ).fetchone()
+            if not row:
+                return {"error": f"Unknown broadcast: {broadcast_id}"}
+            deliveries = [dict(r) for r in conn.execute(
+                "SELECT user_ref, gateway, status, error, sent_at "
+                "FROM broadcast_deliveries WHERE broadcast_id = ? ORDER BY id",
+                (broadcast_id,),
+            ).fetchall()]
+            return {**dict(row), "deliveries": deliveries}
+        finally:
+            conn.close()
+
+
+__all__ = ["BroadcastManager"]
@@ -0,0 +1,171 @@
+#!/usr/bin/env python3
+"""
+Token Budget Reservations for Leviathan Super-Brain
+===================================================
+check_budget() answers "is there headroom right now" — but ten
+concurrent calls can all see the same headroom and collectively blow
+through the limit. A reservation holds estimated capacity from the
+moment of the check to the moment the call completes: quota evaluation
+counts outstanding reservations as spend, so the second caller sees the
+first caller's in-flight estimate.
+
+Reservations are in-memory by design — they describe in-flight calls in
+this process, and the durable truth (the usage rollups) takes over the
+moment the call lands in the UsageStore. A reservation that is never
+completed or released expires after RESERVATION_TTL_SECONDS so a
+crashed caller can't pin capacity forever.
+
+Usage:
+    guard = reservations.reserve("agent-a", estimated_tokens=12000,
+                                 estimated_cost_usd=0.04)
+    if not guard.allowed:
+        ...  # guard.reason says which limit would be exceeded
+    with guard:
+        result = call_model(...)
+        guard.complete()   # actuals are in the usage store now
+    # leaving the block without complete() releases the hold
+
+Author: Leviathan DevOps
+"""
+
+import os
+import time
+import logging
+import secrets
+import threading
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+# A reservation never completed or released expires after this long
+RESERVATION_TTL_SECONDS = int(os.environ.get("RESERVATION_TTL_SECONDS", "300"))
+
+log = logging.getLogger("budget_reservations")
+
+
+class ReservationGuard:
+    """One held reservation. Context-manager exit releases the hold if
+    complete() was not called — release-on-drop, no leaked capacity."""
+
+    def __init__(self, manager, reservation_id: str, agent_id: str,
+                 allowed: bool, reason: str = None):
+        self.manager = manager
+        self.reservation_id = reservation_id
+        self.agent_id = agent_id
+        self.allowed = allowed
+        self.reason = reason
+        self._settled = not allowed  # a denied guard holds nothing
+
+    def complete(self):
+        """The call finished and its actual usage is recorded — drop the
+        hold (the rollups carry the truth from here)."""
+        if not self._settled:
+            self._settled = True
+            self.manager.release(self.reservation_id)
+
+    def release(self):
+        """The call never happened — drop the hold without usage."""
+        self.complete()
+
+    def __enter__(self):
+        return self
+
+    def __exit__(self, exc_type, exc, tb):
+        self.release()
+        return False
+
+
+class ReservationManager:
+    """In-flight capacity holds, counted on top of recorded spend."""
+
+    def __init__(self, quota_manager, ttl_seconds: int = RESERVATION_TTL_SECONDS):
+        self.quota_manager = quota_manager
+        self.ttl_seconds = ttl_seconds
+        self._held = {}  # id → {agent_id, tokens, cost_usd, held_at}
+        self._lock = threading.Lock()
+        self.reserved_total = 0
+        self.denied_total = 0
+        self.expired_total = 0
+
+    def _expire_locked(self):
+        cutoff = time.monotonic() - self.ttl_seconds
+        stale = [rid for rid, r in self._held.items() if r["held_at"] < cutoff]
+        for rid in stale:
+            held = self._held.pop(rid)
+            self.expired_total += 1
+            log.warning(f"[RESERVE] Expired stale reservation {rid} for "
+                        f"{held['agent_id']} ({held['tokens']} tokens)")
+
+    def outstanding(self, agent_id: str) -> dict:
+        """Capacity currently held for an agent by in-flight calls."""
+        with self._lock:
+            self._expire_locked()
+            held = [r for r in self._held.values()
+                    if r["agent_id"] == agent_id]
+        return {
+            "agent_id": agent_id,
+            "count": len(held),
+            "tokens": sum(r["tokens"] for r in held),
+            "cost_usd": round(sum(r["cost_usd"] for r in held), 6),
+        }
+
+    def reserve(self, agent_id: str, estimated_tokens: int,
+                estimated_cost_usd: float = 0.0,
+                usage_store=None) -> ReservationGuard:
+        """
+        Hold estimated capacity for one call. The budget check runs with
+        outstanding holds added to recorded spend, so concurrent callers
+        serialize against the limit instead of racing past it. Returns a
+        guard; guard.allowed is False (nothing held) when the estimate
+        would cross a limit.
+        """
+        with self._lock:
+            self._expire_locked()
+            check = self.quota_manager.check_budget(agent_id, usage_store) \
+                if usage_store else {"allowed": True, "checks": []}
+            held = [r for r in self._held.values()
+                    if r["agent_id"] == agent_id]
+            held_tokens = sum(r["tokens"] for r in held)
+            held_cost = sum(r["cost_usd"] for r in held)
+            for c in check.get("checks", []):
+                pending = (held_tokens + estimated_tokens
+                           if c["dimension"] == "tokens"
+                           else held_cost + estimated_cost_usd)
+                if c["spent"] + pending > c["limit"]:
+                    self.denied_total += 1
+                    reason = (f"{c['dimension']}/{c['window']} would exceed "
+                              f"limit: {c['spent']} spent + {pending} "
+                              f"in-flight > {c['limit']}")
+                    log.info(f"[RESERVE] Denied {agent_id}: {reason}")
+                    return ReservationGuard(self, None, agent_id,
+                                            allowed=False, reason=reason)
+            reservation_id = f"rsv-{secrets.token_hex(6)}"
+            self._held[reservation_id] = {
+                "agent_id": agent_id,
+                "tokens": estimated_tokens,
+                "cost_usd": estimated_cost_usd,
+                "held_at": time.monotonic(),
+            }
+            self.reserved_total += 1
+        return ReservationGuard(self, reservation_id, agent_id, allowed=True)
+
+    def release(self, reservation_id: str) -> bool:
+        with self._lock:
+            return self._held.pop(reservation_id, None) is not None
+
+    def status(self) -> dict:
+        with self._lock:
+            self._expire_locked()
+            held = list(self._held.values())
+        return {
+            "held": len(held),
+            "held_tokens": sum(r["tokens"] for r in held),
+            "held_cost_usd": round(sum(r["cost_usd"] for r in held), 6),
+            "reserved_total": self.reserved_total,
+            "denied_total": self.denied_total,
+            "expired_total": self.expired_total,
+            "ttl_seconds": self.ttl_seconds,
+        }
+
+
+__all__ = ["ReservationManager", "ReservationGuard", "RESERVATION_TTL_SECONDS"]
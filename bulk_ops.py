@@ -0,0 +1,108 @@
+#!/usr/bin/env python3
+"""
+Bulk Administrative Operations for Leviathan Super-Brain
+========================================================
+Fleet-wide admin actions driven by label selectors — pause/resume
+schedules, patch quotas, switch the default model, rotate a provider
+key — applied to every matching agent in one call instead of scripting
+dozens of individual requests. Every operation returns a per-agent
+result report; one agent failing never aborts the rest.
+
+Selectors are kubectl-style label matches against the resolved manifest:
+{"team": "growth", "env": "prod"} matches agents carrying both labels.
+
+Author: Leviathan DevOps
+"""
+
+import logging
+
+from quotas import ResourceQuota
+
+log = logging.getLogger("bulk_ops")
+
+# Operations the endpoint may dispatch — anything else is a 400
+BULK_OPERATIONS = ("pause_schedules", "resume_schedules", "quota_patch",
+                   "set_default_model", "rotate_secret")
+
+
+class BulkOperator:
+    """Label-selected fan-out over the kernel's per-agent admin stores."""
+
+    def __init__(self, registry, schedule_pause, quota_manager, secret_store):
+        self.registry = registry
+        self.schedule_pause = schedule_pause
+        self.quota_manager = quota_manager
+        self.secret_store = secret_store
+
+    def _fan_out(self, selector: dict, operation: str, fn) -> dict:
+        """Run fn(agent_id) for every selected agent, collecting per-agent
+        results. Exceptions become error entries, not aborts."""
+        agent_ids = self.registry.select_by_labels(selector)
+        results = {}
+        failed = 0
+        for agent_id in agent_ids:
+            try:
+                results[agent_id] = fn(agent_id)
+            except Exception as e:
+                results[agent_id] = {"error": str(e)}
+            if "error" in results[agent_id]:
+                failed += 1
+        log.info(f"[BULK] {operation} on {len(agent_ids)} agents "
+                 f"({failed} failed) selector={selector}")
+        return {"operation": operation, "selector": selector,
+                "matched": len(agent_ids), "failed": failed,
+                "results": results}
+
+    def pause_schedules(self, selector: dict, by: str = "bulk",
+                        reason: str = None) -> dict:
+        """Pause cron schedules for every matching agent."""
+        return self._fan_out(
+            selector, "pause_schedules",
+            lambda agent_id: self.schedule_pause.pause_schedules(
+                agent_id, paused_by=by, reason=reason))
+
+    def resume_schedules(self, selector: dict, by: str = "bulk") -> dict:
+        """Resume cron schedules for every matching agent."""
+        return self._fan_out(
+            selector, "resume_schedules",
+            lambda agent_id: self.schedule_pause.resume_schedules(
+                agent_id, resumed_by=by))
+
+    def quota_patch(self, selector: dict, patch: dict, by: str = "bulk",
+                    reason: str = None) -> dict:
+        """
+        Merge a partial quota into each matching agent's current quota —
+        only the fields present in the patch change, so bumping daily cost
+        caps fleet-wide doesn't clobber per-agent token limits.
+        """
+        unknown = [f for f in patch
+                   if f not in ResourceQuota.__dataclass_fields__]
+        if unknown:
+            return {"error": f"Unknown quota fields: {', '.join(unknown)}"}
+
+        def apply(agent_id):
+            quota = self.quota_manager.get_quota(agent_id)
+            merged = {**quota.to_dict(), **patch}
+            return self.quota_manager.set_quota(
+                agent_id, ResourceQuota.from_dict(merged),
+                changed_by=by, reason=reason)
+
+        return self._fan_out(selector, "quota_patch", apply)
+
+    def set_default_model(self, selector: dict, model: str) -> dict:
+        """Switch the manifest's default model on every matching agent."""
+        return self._fan_out(
+            selector, "set_default_model",
+            lambda agent_id: self.registry.patch_manifest(
+                agent_id, {"model": model}))
+
+    def rotate_secret(self, selector: dict, name: str, value: str) -> dict:
+        """Set a new value for a named secret in every matching agent's
+        scope (key rotation after a provider credential leak)."""
+        return self._fan_out(
+            selector, "rotate_secret",
+            lambda agent_id: self.secret_store.set_secret(
+                name, value, scope_agent_id=agent_id))
+
+
+__all__ = ["BulkOperator", "BULK_OPERATIONS"]
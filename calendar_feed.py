@@ -0,0 +1,129 @@
+#!/usr/bin/env python3
+"""
+Upcoming Executions Calendar Feed for Leviathan Super-Brain
+===========================================================
+One time-ordered answer to "what will run next?" — scheduled cron
+firings and delegated tasks with due dates, merged into a unified feed
+the dashboard/TUI can render as a calendar. Sources are queried directly
+with OperationalError guards (same pattern as the stale-agent GC), so
+the feed works on data directories that predate any one store.
+
+Each feed entry normalizes to:
+  {"at", "source", "ref_id", "owner", "summary", "status"}
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import logging
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+
+CALENDAR_PAGE_MAX = int(os.environ.get("CALENDAR_PAGE_MAX", "200"))
+
+# Delegated-task statuses still expected to execute
+_PENDING_TASK_STATUSES = ("pending", "accepted", "in_progress")
+
+log = logging.getLogger("calendar_feed")
+
+
+class CalendarFeed:
+    """Merged, paginated view over everything with a future timestamp."""
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    @staticmethod
+    def _now() -> str:
+        return datetime.now(timezone.utc).isoformat()
+
+    def _cron_entries(self, conn, since: str, until: str, owner: str) -> list:
+        try:
+            query = ("SELECT entry_id, owner, fire_at, payload_json, kind "
+                     "FROM cron_entries WHERE status = 'scheduled' AND fire_at >= ?")
+            params = [since]
+            if until:
+                query += " AND fire_at < ?"
+                params.append(until)
+            if owner:
+                query += " AND owner = ?"
+                params.append(owner)
+            rows = conn.execute(query, params).fetchall()
+        except sqlite3.OperationalError:
+            return []
+        entries = []
+        for entry_id, entry_owner, fire_at, payload_json, kind in rows:
+            try:
+                payload = json.loads(payload_json) if payload_json else {}
+            except json.JSONDecodeError:
+                payload = {}
+            summary = payload.get("text") or payload.get("type") or kind
+            entries.append({"at": fire_at, "source": "cron", "ref_id": entry_id,
+                            "owner": entry_owner, "summary": str(summary)[:200],
+                            "status": "scheduled"})
+        return entries
+
+    def _task_deadlines(self, conn, since: str, until: str, owner: str) -> list:
+        try:
+            placeholders = ",".join("?" * len(_PENDING_TASK_STATUSES))
+            query = (f"SELECT task_id, owner, due_at, spec, status "
+                     f"FROM delegated_tasks WHERE due_at IS NOT NULL "
+                     f"AND due_at >= ? AND status IN ({placeholders})")
+            params = [since, *_PENDING_TASK_STATUSES]
+            if until:
+                query += " AND due_at < ?"
+                params.append(until)
+            if owner:
+                query += " AND owner = ?"
+                params.append(owner)
+            rows = conn.execute(query, params).fetchall()
+        except sqlite3.OperationalError:
+            return []
+        return [
+            {"at": due_at, "source": "task", "ref_id": task_id,
+             "owner": task_owner, "summary": (spec or "")[:200], "status": status}
+            for task_id, task_owner, due_at, spec, status in rows
+        ]
+
+    def upcoming(self, since: str = None, until: str = None, owner: str = None,
+                 limit: int = 50, offset: int = 0) -> dict:
+        """
+        The unified feed, soonest first. `since` defaults to now; `until`
+        bounds the window. Pagination via limit/offset — `next_offset`
+        is set while more entries remain.
+        """
+        since = since or self._now()
+        limit = max(1, min(int(limit), CALENDAR_PAGE_MAX))
+        offset = max(0, int(offset))
+        conn = self._connect()
+        try:
+            entries = (self._cron_entries(conn, since, until, owner)
+                       + self._task_deadlines(conn, since, until, owner))
+        finally:
+            conn.close()
+        entries.sort(key=lambda e: e["at"])
+        page = entries[offset:offset + limit]
+        return {
+            "since": since,
+            "until": until,
+            "total": len(entries),
+            "offset": offset,
+            "count": len(page),
+            "next_offset": offset + limit if offset + limit < len(entries) else None,
+            "entries": page,
+        }
+
+
+__all__ = ["CalendarFeed"]
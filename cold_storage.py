@@ -0,0 +1,161 @@
+#!/usr/bin/env python3
+"""
+Cold Storage Tiering for Leviathan Super-Brain
+==============================================
+Keeps the hot SQLite database small: transcripts older than N days are
+compressed to the cold store (one gzip JSON blob per turn, written
+through the configured object-storage backend — local disk by default,
+S3/MinIO for stateless cluster nodes) and the row in turn_transcripts
+becomes a stub — summary columns stay for searchability, message bodies
+move out. Reads rehydrate transparently, so callers never notice which
+tier a turn lives in.
+
+Env:
+  COLD_STORE_DIR            — blob directory for the local backend
+                              (default /data/cold-store)
+  COLD_TIER_AFTER_DAYS      — age before tiering (default 7)
+  OBJECT_STORE_BACKEND      — see object_storage.py
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import gzip
+import logging
+from datetime import datetime, timedelta, timezone
+
+import object_storage
+from transcripts import TranscriptStore
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+COLD_STORE_DIR = os.environ.get("COLD_STORE_DIR", "/data/cold-store")
+COLD_TIER_AFTER_DAYS = int(os.environ.get("COLD_TIER_AFTER_DAYS", "7"))
+
+# The heavy columns moved to the blob; everything else stays hot.
+TIERED_COLUMNS = ("messages", "tool_calls", "final_reply")
+
+log = logging.getLogger("cold_storage")
+
+
+class ColdStorage:
+    """Tiering + transparent rehydration for turn transcripts."""
+
+    def __init__(self, db_path: str = DB_PATH, cold_dir: str = COLD_STORE_DIR,
+                 backend=None):
+        self.db_path = db_path
+        self.cold_dir = cold_dir
+        self.backend = backend or object_storage.from_env(cold_dir)
+        self.transcript_store = TranscriptStore(db_path)
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            try:
+                conn.execute(
+                    "ALTER TABLE turn_transcripts ADD COLUMN cold_path TEXT"
+                )
+            except sqlite3.OperationalError:
+                pass  # column already present
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _blob_key(turn_id: str) -> str:
+        # Shard by prefix so one directory never holds millions of files.
+        return f"{turn_id[:2]}/{turn_id}.json.gz"
+
+    def tier_pass(self, older_than_days: int = COLD_TIER_AFTER_DAYS) -> dict:
+        """Move eligible transcripts to the cold store. Returns counts."""
+        cutoff = (datetime.now(timezone.utc) - timedelta(days=older_than_days)).isoformat()
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            rows = conn.execute(
+                """SELECT turn_id, messages, tool_calls, final_reply
+                   FROM turn_transcripts
+                   WHERE created_at < ? AND cold_path IS NULL""",
+                (cutoff,),
+            ).fetchall()
+
+            tiered = failed = 0
+            for row in rows:
+                turn_id = row["turn_id"]
+                blob_key = self._blob_key(turn_id)
+                try:
+                    blob = gzip.compress(json.dumps(
+                        {col: row[col] for col in TIERED_COLUMNS}).encode())
+                    self.backend.put(blob_key, blob,
+                                     tags={"tier": "cold-transcript"})
+                    conn.execute(
+                        """UPDATE turn_transcripts
+                           SET messages = NULL, tool_calls = NULL,
+                               final_reply = NULL, cold_path = ?
+                           WHERE turn_id = ?""",
+                        (blob_key, turn_id),
+                    )
+                    tiered += 1
+                except Exception as e:
+                    failed += 1
+                    log.error(f"[COLD] Tiering {turn_id} failed: {e}")
+            conn.commit()
+            if tiered:
+                log.info(f"[COLD] Tiered {tiered} transcripts older than "
+                         f"{older_than_days}d via {self.backend.name} backend")
+            return {"tiered": tiered, "failed": failed, "cutoff": cutoff}
+        finally:
+            conn.close()
+
+    def fetch_transcript(self, turn_id: str) -> dict:
+        """Full transcript regardless of tier — rehydrates cold rows."""
+        transcript = self.transcript_store.get_transcript(turn_id)
+        if "error" in transcript or not transcript.get("cold_path"):
+            return transcript
+        cold_path = transcript["cold_path"]
+        try:
+            if cold_path.startswith("/"):
+                # Row tiered before the backend abstraction — the column
+                # holds an absolute filesystem path
+                with gzip.open(cold_path, "rt") as f:
+                    blob = json.load(f)
+            else:
+                blob = json.loads(gzip.decompress(
+                    self.backend.get(cold_path)))
+        except Exception as e:
+            log.error(f"[COLD] Rehydration of {turn_id} failed: {e}")
+            return {**transcript, "rehydration_error": str(e)}
+        transcript["messages"] = json.loads(blob.get("messages") or "[]")
+        transcript["tool_calls"] = json.loads(blob.get("tool_calls") or "[]")
+        transcript["final_reply"] = blob.get("final_reply")
+        transcript["from_cold_store"] = True
+        return transcript
+
+    def stats(self) -> dict:
+        conn = self._connect()
+        try:
+            hot = conn.execute(
+                "SELECT COUNT(*) FROM turn_transcripts WHERE cold_path IS NULL"
+            ).fetchone()[0]
+            cold = conn.execute(
+                "SELECT COUNT(*) FROM turn_transcripts WHERE cold_path IS NOT NULL"
+            ).fetchone()[0]
+            return {"hot": hot, "cold": cold,
+                    "storage": self.backend.describe(),
+                    "tier_after_days": COLD_TIER_AFTER_DAYS}
+        finally:
+            conn.close()
+
+
+__all__ = ["ColdStorage"]
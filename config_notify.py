@@ -0,0 +1,163 @@
+#!/usr/bin/env python3
+"""
+Config Change Notifications for Leviathan Super-Brain
+=====================================================
+When an operator live-updates something an agent depends on — its
+manifest/prompt, its quota, its tool bindings — the agent should hear
+about it instead of silently behaving differently mid-conversation.
+Each change lands here as a pending notice; the next turn for that
+agent gets a structured [SYSTEM NOTICE] block prepended so the model
+can acknowledge the new instructions, and a 'config.changed' event goes
+out on the bus for dashboards and exec hooks.
+
+Notices are consumed (acknowledged) when injected; an agent that never
+takes another turn just accumulates them harmlessly.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import logging
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+
+# At most this many notices get injected into one turn; older ones are
+# summarized as a count so a long-idle agent isn't flooded
+CONFIG_NOTICE_INJECT_LIMIT = int(
+    os.environ.get("CONFIG_NOTICE_INJECT_LIMIT", "5"))
+
+log = logging.getLogger("config_notify")
+
+
+class ConfigChangeNotifier:
+    """Pending per-agent config change notices, SQLite-backed."""
+
+    def __init__(self, db_path: str = DB_PATH, event_bus=None):
+        self.db_path = db_path
+        self.event_bus = event_bus
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS config_notices (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    agent_id TEXT NOT NULL,
+                    kind TEXT NOT NULL,
+                    summary TEXT NOT NULL,
+                    detail TEXT,
+                    changed_by TEXT,
+                    created_at TEXT NOT NULL,
+                    acknowledged_at TEXT
+                )
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_config_notices_pending
+                ON config_notices (agent_id, acknowledged_at)
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _now() -> str:
+        return datetime.now(timezone.utc).isoformat()
+
+    def notify(self, agent_id: str, kind: str, summary: str,
+               detail: dict = None, changed_by: str = None) -> dict:
+        """
+        Record one config change against an agent. kind is what changed
+        ('manifest', 'quota', 'tools', ...); summary is the one-line,
+        model-readable description injected into the next turn.
+        """
+        conn = self._connect()
+        try:
+            cursor = conn.execute(
+                """INSERT INTO config_notices
+                   (agent_id, kind, summary, detail, changed_by, created_at)
+                   VALUES (?, ?, ?, ?, ?, ?)""",
+                (agent_id, kind, summary,
+                 json.dumps(detail) if detail else None,
+                 changed_by, self._now()),
+            )
+            conn.commit()
+            notice_id = cursor.lastrowid
+        finally:
+            conn.close()
+        log.info(f"[CONFIG] {agent_id} {kind} changed: {summary}")
+        if self.event_bus:
+            self.event_bus.publish("config.changed", {
+                "agent_id": agent_id, "kind": kind, "summary": summary,
+                "changed_by": changed_by, "notice_id": notice_id,
+            })
+        return {"notice_id": notice_id, "agent_id": agent_id, "kind": kind}
+
+    def pending(self, agent_id: str) -> list:
+        """Unacknowledged notices for an agent, oldest first."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            rows = [dict(r) for r in conn.execute(
+                """SELECT * FROM config_notices
+                   WHERE agent_id = ? AND acknowledged_at IS NULL
+                   ORDER BY id""", (agent_id,)).fetchall()]
+            for row in rows:
+                row["detail"] = json.loads(row["detail"]) if row["detail"] else None
+            return rows
+        finally:
+            conn.close()
+
+    def consume(self, agent_id: str) -> dict:
+        """
+        Acknowledge all pending notices and return the context block to
+        prepend to the agent's next turn (empty string when there is
+        nothing pending). Call exactly once per injected turn.
+        """
+        notices = self.pending(agent_id)
+        if not notices:
+            return {"agent_id": agent_id, "count": 0, "context": ""}
+        conn = self._connect()
+        try:
+            conn.execute(
+                """UPDATE config_notices SET acknowledged_at = ?
+                   WHERE agent_id = ? AND acknowledged_at IS NULL""",
+                (self._now(), agent_id),
+            )
+            conn.commit()
+        finally:
+            conn.close()
+        return {"agent_id": agent_id, "count": len(notices),
+                "context": self.render_context(notices)}
+
+    @staticmethod
+    def render_context(notices: list) -> str:
+        """The structured block the model sees. Deliberately explicit:
+        the agent is asked to acknowledge, not just informed."""
+        shown = notices[-CONFIG_NOTICE_INJECT_LIMIT:]
+        lines = ["[SYSTEM NOTICE] Your configuration changed since your "
+                 "last turn:"]
+        if len(notices) > len(shown):
+            lines.append(f"  (… and {len(notices) - len(shown)} earlier "
+                         f"changes, see /agents/<id>/config-notices)")
+        for n in shown:
+            by = f" (by {n['changed_by']})" if n.get("changed_by") else ""
+            lines.append(f"  - [{n['kind']}] {n['summary']}{by}")
+        lines.append("Acknowledge these changes and apply them from this "
+                     "turn onward.")
+        return "\n".join(lines)
+
+
+__all__ = ["ConfigChangeNotifier", "CONFIG_NOTICE_INJECT_LIMIT"]
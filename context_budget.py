@@ -0,0 +1,162 @@
+#!/usr/bin/env python3
+"""
+Conversation Context Budgets for Leviathan Super-Brain
+======================================================
+Long conversations quietly become the biggest line item: every turn
+drags the whole history back through the input meter. This module
+assembles the history slice carried into a turn under a per-agent token
+budget — newest turns ride in full, older turns get condensed to
+one-line digests built from their stored columns, and the oldest are
+dropped. A trimmed history always starts with an explicit
+"[context trimmed: ...]" marker so the agent knows information may be
+missing instead of confidently hallucinating around the gap.
+
+The budget comes from the agent manifest's `context_budget_tokens`
+(CONTEXT_BUDGET_DEFAULT_TOKENS when unset), so a chatty support agent
+and a terse cron agent can carry different amounts of history.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import logging
+
+from usage_store import estimate_tokens
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+
+# History tokens carried per turn when the manifest doesn't say
+CONTEXT_BUDGET_DEFAULT_TOKENS = int(
+    os.environ.get("CONTEXT_BUDGET_DEFAULT_TOKENS", "24000"))
+
+# Size of the digest an older turn condenses to
+CONTEXT_DIGEST_MAX_CHARS = int(
+    os.environ.get("CONTEXT_DIGEST_MAX_CHARS", "300"))
+
+log = logging.getLogger("context_budget")
+
+
+class ContextBudgetManager:
+    """Budget-bounded history assembly over turn_transcripts."""
+
+    def __init__(self, db_path: str = DB_PATH, registry=None):
+        self.db_path = db_path
+        self.registry = registry
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def budget_for(self, agent_id: str) -> int:
+        """The agent's configured history budget (manifest key
+        `context_budget_tokens`, else the deployment default)."""
+        if self.registry is not None:
+            agent = self.registry.get_agent(agent_id)
+            if "error" not in agent:
+                configured = agent["manifest"].get("context_budget_tokens")
+                if configured:
+                    return int(configured)
+        return CONTEXT_BUDGET_DEFAULT_TOKENS
+
+    @staticmethod
+    def _turn_messages(turn: dict) -> list:
+        """A turn's full message list: recorded inputs plus the reply."""
+        messages = list(json.loads(turn["messages"] or "[]"))
+        if turn["final_reply"]:
+            messages.append({"role": "assistant",
+                             "content": turn["final_reply"]})
+        return messages
+
+    @staticmethod
+    def _digest(turn: dict) -> str:
+        """One-line stand-in for a turn, built from stored columns —
+        the user's opening words and the reply's, both truncated."""
+        messages = json.loads(turn["messages"] or "[]")
+        asked = next((m.get("content", "") for m in messages
+                      if m.get("role") == "user"), "")
+        replied = turn["final_reply"] or ""
+        half = CONTEXT_DIGEST_MAX_CHARS // 2
+        return (f"[earlier turn] user: {asked[:half]}"
+                f" → assistant: {replied[:half]}")
+
+    @staticmethod
+    def _tokens(messages: list) -> int:
+        return sum(estimate_tokens(m.get("content") or "") for m in messages)
+
+    def build_context(self, conversation_id: str, agent_id: str,
+                      budget_tokens: int = None) -> dict:
+        """
+        The history to carry into the conversation's next turn, within
+        budget. Walks turns newest-first: full turns until the budget's
+        spoken for, then digests for as long as they still fit, then
+        nothing. Cold-tiered turns (message bodies moved out) are
+        digested from their stub rather than rehydrated — history
+        assembly must never block on the cold store.
+        """
+        budget = budget_tokens or self.budget_for(agent_id)
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            turns = [dict(r) for r in conn.execute(
+                """SELECT turn_id, messages, final_reply, created_at
+                   FROM turn_transcripts
+                   WHERE conversation_id = ? AND agent_id = ?
+                   ORDER BY created_at DESC""",
+                (conversation_id, agent_id)).fetchall()]
+        finally:
+            conn.close()
+
+        spent = 0
+        kept = []  # newest-first while assembling
+        full = summarized = dropped = 0
+        digests_only = False
+        for turn in turns:
+            if not digests_only:
+                messages = self._turn_messages(turn)
+                cost = self._tokens(messages)
+                if cost and spent + cost <= budget:
+                    kept.append(messages)
+                    spent += cost
+                    full += 1
+                    continue
+                digests_only = True
+            digest = self._digest(turn)
+            cost = estimate_tokens(digest)
+            if spent + cost <= budget:
+                kept.append([{"role": "system", "content": digest}])
+                spent += cost
+                summarized += 1
+            else:
+                dropped += 1
+
+        messages = [m for turn in reversed(kept) for m in turn]
+        trimmed = summarized > 0 or dropped > 0
+        if trimmed:
+            marker = (f"[context trimmed: {summarized} earlier turn(s) "
+                      f"condensed, {dropped} dropped — information from "
+                      f"earlier in this conversation may be missing]")
+            messages.insert(0, {"role": "system", "content": marker})
+            log.info(f"[CONTEXT] {agent_id}/{conversation_id}: "
+                     f"{full} full, {summarized} condensed, {dropped} "
+                     f"dropped (~{spent}/{budget} tokens)")
+        return {
+            "conversation_id": conversation_id,
+            "agent_id": agent_id,
+            "budget_tokens": budget,
+            "estimated_tokens": spent,
+            "turns_full": full,
+            "turns_summarized": summarized,
+            "turns_dropped": dropped,
+            "trimmed": trimmed,
+            "messages": messages,
+        }
+
+
+__all__ = ["ContextBudgetManager", "CONTEXT_BUDGET_DEFAULT_TOKENS"]
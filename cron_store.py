@@ -0,0 +1,170 @@
+#!/usr/bin/env python3
+"""
+Cron Store for Leviathan Super-Brain
+====================================
+Persistent store for scheduled entries — one-shot reminders today,
+recurring jobs tomorrow. Entries survive restarts (unlike the in-memory
+`schedule` jobs) and are drained by a dispatch daemon that scans for due
+rows and routes their payloads out.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import uuid
+import logging
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+
+log = logging.getLogger("cron_store")
+
+VALID_STATUSES = ("scheduled", "fired", "cancelled")
+
+
+class CronStore:
+    """SQLite-backed scheduled entries with a due-entry scan."""
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS cron_entries (
+                    entry_id TEXT PRIMARY KEY,
+                    owner TEXT NOT NULL,
+                    kind TEXT NOT NULL DEFAULT 'one_shot',
+                    fire_at TEXT NOT NULL,
+                    payload_json TEXT,
+                    status TEXT NOT NULL DEFAULT 'scheduled',
+                    created_at TEXT NOT NULL,
+                    fired_at TEXT
+                )
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_cron_due
+                ON cron_entries (status, fire_at)
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _now() -> str:
+        return datetime.now(timezone.utc).isoformat()
+
+    @staticmethod
+    def _row_to_dict(row) -> dict:
+        entry = dict(row)
+        entry["payload"] = json.loads(entry.pop("payload_json")) if entry.get("payload_json") else {}
+        return entry
+
+    def add_one_shot(self, owner: str, fire_at: str, payload: dict = None) -> dict:
+        """Schedule a one-shot entry that fires once at `fire_at` (UTC ISO)."""
+        entry_id = f"cron-{uuid.uuid4().hex[:12]}"
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT INTO cron_entries
+                   (entry_id, owner, kind, fire_at, payload_json, status, created_at)
+                   VALUES (?, ?, 'one_shot', ?, ?, 'scheduled', ?)""",
+                (entry_id, owner, fire_at, json.dumps(payload or {}), self._now()),
+            )
+            conn.commit()
+            log.info(f"[CRON] Scheduled {entry_id} for {owner} at {fire_at}")
+            return self.get_entry(entry_id)
+        finally:
+            conn.close()
+
+    def get_entry(self, entry_id: str) -> dict:
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            row = conn.execute(
+                "SELECT * FROM cron_entries WHERE entry_id = ?", (entry_id,)
+            ).fetchone()
+            if not row:
+                return {"error": f"Unknown entry: {entry_id}"}
+            return self._row_to_dict(row)
+        finally:
+            conn.close()
+
+    def list_entries(self, owner: str = None, status: str = "scheduled") -> list:
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            query = "SELECT * FROM cron_entries WHERE 1=1"
+            params = []
+            if owner:
+                query += " AND owner = ?"
+                params.append(owner)
+            if status:
+                query += " AND status = ?"
+                params.append(status)
+            query += " ORDER BY fire_at"
+            return [self._row_to_dict(r) for r in conn.execute(query, params).fetchall()]
+        finally:
+            conn.close()
+
+    def cancel(self, entry_id: str, owner: str = None) -> dict:
+        """Cancel a scheduled entry. If `owner` is given, it must match."""
+        entry = self.get_entry(entry_id)
+        if "error" in entry:
+            return entry
+        if owner and entry["owner"] != owner:
+            return {"error": f"Entry {entry_id} is not owned by {owner}"}
+        if entry["status"] != "scheduled":
+            return {"error": f"Entry {entry_id} is already {entry['status']}"}
+        conn = self._connect()
+        try:
+            conn.execute(
+                "UPDATE cron_entries SET status = 'cancelled' WHERE entry_id = ?",
+                (entry_id,),
+            )
+            conn.commit()
+            log.info(f"[CRON] Cancelled {entry_id}")
+            return self.get_entry(entry_id)
+        finally:
+            conn.close()
+
+    def due_entries(self, now: str = None) -> list:
+        """All scheduled entries whose fire_at has passed."""
+        now = now or self._now()
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            return [self._row_to_dict(r) for r in conn.execute(
+                "SELECT * FROM cron_entries WHERE status = 'scheduled' AND fire_at <= ? "
+                "ORDER BY fire_at",
+                (now,),
+            ).fetchall()]
+        finally:
+            conn.close()
+
+    def mark_fired(self, entry_id: str):
+        conn = self._connect()
+        try:
+            conn.execute(
+                "UPDATE cron_entries SET status = 'fired', fired_at = ? WHERE entry_id = ?",
+                (self._now(), entry_id),
+            )
+            conn.commit()
+        finally:
+            conn.close()
+
+
+__all__ = ["CronStore"]
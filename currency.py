@@ -0,0 +1,122 @@
+#!/usr/bin/env python3
+"""
+Currency Conversion for Leviathan Super-Brain
+=============================================
+Reporting-side currency conversion: every stored cost stays canonical
+USD, but finance teams read reports in EUR/GBP/INR. A converter walks a
+report payload and adds a converted companion next to every *_usd field,
+so existing consumers keep their USD numbers untouched.
+
+Rates come from CURRENCY_RATES_JSON (units per USD) with a built-in
+fallback table, or from a pluggable rate_source callable (live FX feed)
+cached for CURRENCY_RATES_TTL_SECONDS.
+
+Author: Leviathan DevOps
+"""
+
+import os
+import json
+import time
+import logging
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+# Default report currency when a request doesn't name one
+REPORT_CURRENCY = os.environ.get("REPORT_CURRENCY", "USD")
+
+# Static fallback rates (units per 1 USD) — deliberately coarse; set
+# CURRENCY_RATES_JSON or a rate_source for anything finance-grade
+FALLBACK_RATES = {
+    "USD": 1.0,
+    "EUR": 0.92,
+    "GBP": 0.79,
+    "INR": 83.0,
+    "JPY": 148.0,
+    "AUD": 1.52,
+    "CAD": 1.36,
+}
+
+CURRENCY_RATES_TTL_SECONDS = int(os.environ.get("CURRENCY_RATES_TTL_SECONDS", "3600"))
+
+log = logging.getLogger("currency")
+
+
+class CurrencyConverter:
+    """USD → report-currency conversion with a pluggable rate source."""
+
+    def __init__(self, rate_source=None):
+        # rate_source: callable returning {currency: units_per_usd};
+        # failures fall back to the static/env table
+        self.rate_source = rate_source
+        self._rates = dict(FALLBACK_RATES)
+        env_rates = os.environ.get("CURRENCY_RATES_JSON")
+        if env_rates:
+            try:
+                self._rates.update({k.upper(): float(v)
+                                    for k, v in json.loads(env_rates).items()})
+            except (json.JSONDecodeError, TypeError, ValueError) as e:
+                log.warning(f"[CURRENCY] Bad CURRENCY_RATES_JSON ignored: {e}")
+        self._fetched_at = 0.0
+
+    def rates(self) -> dict:
+        """Current rate table, refreshing from the rate source when the
+        cache is older than CURRENCY_RATES_TTL_SECONDS."""
+        if (self.rate_source is not None
+                and time.time() - self._fetched_at > CURRENCY_RATES_TTL_SECONDS):
+            try:
+                fresh = self.rate_source()
+                self._rates.update({k.upper(): float(v) for k, v in fresh.items()})
+                self._fetched_at = time.time()
+                log.info(f"[CURRENCY] Refreshed {len(fresh)} rates from source")
+            except Exception as e:
+                log.warning(f"[CURRENCY] Rate source failed, keeping cached: {e}")
+                self._fetched_at = time.time()  # don't hammer a dead feed
+        return self._rates
+
+    def supported(self) -> list:
+        return sorted(self.rates().keys())
+
+    def rate(self, currency: str) -> float:
+        """Units of `currency` per 1 USD, or None if unknown."""
+        return self.rates().get((currency or "USD").upper())
+
+    def convert(self, amount_usd: float, currency: str) -> float:
+        rate = self.rate(currency)
+        if rate is None:
+            raise ValueError(f"Unknown currency: {currency}")
+        return round((amount_usd or 0.0) * rate, 6)
+
+    def convert_payload(self, payload, currency: str):
+        """
+        Deep-copy a report payload, adding a `<field>_<cur>` companion
+        next to every numeric `<field>_usd`. USD stays in place — the
+        canonical value is never rewritten. Top-level dicts also get
+        `currency` and `exchange_rate` so the reader knows what they're
+        looking at.
+        """
+        currency = (currency or "USD").upper()
+        rate = self.rate(currency)
+        if rate is None:
+            raise ValueError(f"Unknown currency: {currency}")
+        converted = self._walk(payload, currency, rate)
+        if isinstance(converted, dict):
+            converted["currency"] = currency
+            converted["exchange_rate"] = rate
+        return converted
+
+    def _walk(self, node, currency, rate):
+        if isinstance(node, dict):
+            out = {}
+            for key, value in node.items():
+                out[key] = self._walk(value, currency, rate)
+                if (key.endswith("_usd") and isinstance(value, (int, float))
+                        and currency != "USD"):
+                    out[f"{key[:-4]}_{currency.lower()}"] = round(value * rate, 6)
+            return out
+        if isinstance(node, list):
+            return [self._walk(item, currency, rate) for item in node]
+        return node
+
+
+__all__ = ["CurrencyConverter", "REPORT_CURRENCY", "FALLBACK_RATES"]
@@ -0,0 +1,182 @@
+#!/usr/bin/env python3
+"""
+Per-User Data Purge for Leviathan Super-Brain
+=============================================
+Right-to-be-forgotten support: purge_user_data(external_identity)
+deletes or anonymizes one user's data across every store — transcripts,
+usage records, reminders, handoffs, broadcast pairing records — and
+produces a signed deletion report for the compliance request.
+
+Deletion vs anonymization per store:
+  - turn_transcripts     → rows deleted (conversation content)
+  - handoff sessions/msgs → rows deleted (conversation content)
+  - cron_entries         → rows deleted (reminder text is user content)
+  - broadcast_recipients → rows deleted (pairing record)
+  - usage_records        → user_id nulled, rows kept (billing aggregates
+                           must survive, they carry no content)
+
+The report is HMAC-signed with PURGE_SIGNING_KEY so it can be handed to
+the requester and verified later against tampering.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import hmac
+import hashlib
+import logging
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+PURGE_SIGNING_KEY = os.environ.get("PURGE_SIGNING_KEY", "leviathan-purge-dev-key")
+
+log = logging.getLogger("data_purge")
+
+# (table, column, action) — every place an external identity can land.
+# Tables are skipped silently if absent (older data directories).
+PURGE_TARGETS = (
+    ("turn_transcripts", "user_id", "delete"),
+    ("handoff_sessions", "user_ref", "delete"),
+    ("cron_entries", "owner", "delete"),
+    ("broadcast_recipients", "user_ref", "delete"),
+    ("broadcast_deliveries", "user_ref", "delete"),
+    ("usage_records", "user_id", "anonymize"),
+)
+
+
+class DataPurge:
+    """Cross-store purge with a signed deletion report."""
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS purge_reports (
+                    purge_id TEXT PRIMARY KEY,
+                    external_identity TEXT NOT NULL,
+                    report_json TEXT NOT NULL,
+                    signature TEXT NOT NULL,
+                    purged_at TEXT NOT NULL
+                )
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _sign(report: dict) -> str:
+        payload = json.dumps(report, sort_keys=True).encode()
+        return hmac.new(PURGE_SIGNING_KEY.encode(), payload,
+                        hashlib.sha256).hexdigest()
+
+    @staticmethod
+    def verify_report(report: dict, signature: str) -> bool:
+        expected = hmac.new(PURGE_SIGNING_KEY.encode(),
+                            json.dumps(report, sort_keys=True).encode(),
+                            hashlib.sha256).hexdigest()
+        return hmac.compare_digest(expected, signature)
+
+    def purge_user_data(self, external_identity: str, requested_by: str = None) -> dict:
+        """Delete/anonymize everything tied to `external_identity` and
+        return the signed deletion report."""
+        purged_at = datetime.now(timezone.utc).isoformat()
+        purge_id = hashlib.sha256(
+            f"{external_identity}:{purged_at}".encode()
+        ).hexdigest()[:16]
+
+        stores = {}
+        conn = self._connect()
+        try:
+            # Handoff transcripts hang off handoff_sessions — collect ids first.
+            try:
+                handoff_ids = [r[0] for r in conn.execute(
+                    "SELECT handoff_id FROM handoff_sessions WHERE user_ref = ?",
+                    (external_identity,),
+                ).fetchall()]
+                if handoff_ids:
+                    placeholders = ",".join("?" * len(handoff_ids))
+                    cur = conn.execute(
+                        f"DELETE FROM handoff_messages WHERE handoff_id IN ({placeholders})",
+                        handoff_ids,
+                    )
+                    stores["handoff_messages"] = {"action": "delete",
+                                                  "rows": cur.rowcount}
+            except sqlite3.OperationalError:
+                pass
+
+            for table, column, action in PURGE_TARGETS:
+                try:
+                    if action == "delete":
+                        cur = conn.execute(
+                            f"DELETE FROM {table} WHERE {column} = ?",
+                            (external_identity,),
+                        )
+                    else:
+                        cur = conn.execute(
+                            f"UPDATE {table} SET {column} = NULL WHERE {column} = ?",
+                            (external_identity,),
+                        )
+                    stores[table] = {"action": action, "rows": cur.rowcount}
+                except sqlite3.OperationalError:
+                    stores[table] = {"action": "skipped", "rows": 0,
+                                     "note": "table not present"}
+            conn.commit()
+        finally:
+            conn.close()
+
+        report = {
+            "purge_id": purge_id,
+            "external_identity": external_identity,
+            "requested_by": requested_by,
+            "purged_at": purged_at,
+            "stores": stores,
+            "total_rows": sum(s["rows"] for s in stores.values()),
+        }
+        signature = self._sign(report)
+
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT INTO purge_reports
+                   (purge_id, external_identity, report_json, signature, purged_at)
+                   VALUES (?, ?, ?, ?, ?)""",
+                (purge_id, external_identity, json.dumps(report), signature, purged_at),
+            )
+            conn.commit()
+        finally:
+            conn.close()
+
+        log.info(f"[PURGE] {external_identity}: {report['total_rows']} rows "
+                 f"across {len(stores)} stores (purge {purge_id})")
+        return {"report": report, "signature": signature}
+
+    def get_report(self, purge_id: str) -> dict:
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                "SELECT report_json, signature FROM purge_reports WHERE purge_id = ?",
+                (purge_id,),
+            ).fetchone()
+            if not row:
+                return {"error": f"Unknown purge: {purge_id}"}
+            return {"report": json.loads(row[0]), "signature": row[1]}
+        finally:
+            conn.close()
+
+
+__all__ = ["DataPurge"]
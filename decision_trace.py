@@ -0,0 +1,183 @@
+#!/usr/bin/env python3
+"""
+Decision Traces for Leviathan Super-Brain
+=========================================
+"Why did the agent do that?" — the answer, as data. Every traced
+operation (an agent turn, a tool call) records the chain of decisions
+the kernel subsystems made along the way: budget gate verdict, config
+notices injected, binding check, breaker state, model routed, fallback
+taken. Support pulls the trace by id instead of grepping debug logs and
+reconstructing the order by timestamp.
+
+A trace is one row; its steps are an ordered JSON list written when the
+trace finishes, so a trace is either complete or absent — never a
+half-written log. History is pruned, not archived: traces answer "what
+just happened", the audit stores keep the durable record.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import uuid
+import logging
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+
+# Traces kept before the oldest are pruned
+TRACE_HISTORY_LIMIT = int(os.environ.get("TRACE_HISTORY_LIMIT", "2000"))
+
+log = logging.getLogger("decision_trace")
+
+
+class DecisionTrace:
+    """One in-progress trace. step() appends decisions in order;
+    finish() writes the whole thing atomically."""
+
+    def __init__(self, tracer, agent_id: str, kind: str):
+        self.tracer = tracer
+        self.trace_id = f"trc-{uuid.uuid4().hex[:12]}"
+        self.agent_id = agent_id
+        self.kind = kind
+        self.started_at = datetime.now(timezone.utc).isoformat()
+        self.steps = []
+
+    def step(self, subsystem: str, decision: str, **detail):
+        entry = {"subsystem": subsystem, "decision": decision,
+                 "at": datetime.now(timezone.utc).isoformat()}
+        if detail:
+            entry["detail"] = detail
+        self.steps.append(entry)
+
+    def finish(self, outcome: str) -> str:
+        self.tracer._store(self, outcome)
+        return self.trace_id
+
+
+class DecisionTracer:
+    """SQLite-backed trace store plus the begin/finish lifecycle."""
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self._writes = 0
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS decision_traces (
+                    trace_id TEXT PRIMARY KEY,
+                    agent_id TEXT,
+                    kind TEXT NOT NULL,
+                    outcome TEXT NOT NULL,
+                    steps TEXT NOT NULL,
+                    started_at TEXT NOT NULL,
+                    finished_at TEXT NOT NULL
+                )
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_traces_agent
+                ON decision_traces (agent_id, started_at)
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    def begin(self, agent_id: str, kind: str) -> DecisionTrace:
+        return DecisionTrace(self, agent_id, kind)
+
+    def record(self, agent_id: str, kind: str, outcome: str,
+               steps: list) -> str:
+        """One-shot variant for call sites that already have the full
+        step list in hand (e.g. the tool executor)."""
+        trace = DecisionTrace(self, agent_id, kind)
+        for s in steps:
+            trace.steps.append({
+                "subsystem": s["subsystem"], "decision": s["decision"],
+                "at": s.get("at", datetime.now(timezone.utc).isoformat()),
+                **({"detail": s["detail"]} if s.get("detail") else {}),
+            })
+        return trace.finish(outcome)
+
+    def _store(self, trace: DecisionTrace, outcome: str):
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT INTO decision_traces
+                   (trace_id, agent_id, kind, outcome, steps,
+                    started_at, finished_at)
+                   VALUES (?, ?, ?, ?, ?, ?, ?)""",
+                (trace.trace_id, trace.agent_id, trace.kind, outcome,
+                 json.dumps(trace.steps), trace.started_at,
+                 datetime.now(timezone.utc).isoformat()),
+            )
+            self._writes += 1
+            if self._writes % 200 == 0:
+                conn.execute(
+                    """DELETE FROM decision_traces WHERE trace_id NOT IN (
+                         SELECT trace_id FROM decision_traces
+                         ORDER BY started_at DESC LIMIT ?)""",
+                    (TRACE_HISTORY_LIMIT,),
+                )
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _hydrate(row: sqlite3.Row) -> dict:
+        trace = dict(row)
+        trace["steps"] = json.loads(trace["steps"])
+        return trace
+
+    def get(self, trace_id: str) -> dict:
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            row = conn.execute(
+                "SELECT * FROM decision_traces WHERE trace_id = ?",
+                (trace_id,)).fetchone()
+            if not row:
+                return {"error": f"Unknown trace: {trace_id}"}
+            return self._hydrate(row)
+        finally:
+            conn.close()
+
+    def recent(self, agent_id: str = None, kind: str = None,
+               outcome: str = None, limit: int = 50) -> list:
+        """Newest-first trace list, filterable — 'show me everything
+        that got blocked for agent X this afternoon'."""
+        query = "SELECT * FROM decision_traces WHERE 1=1"
+        params = []
+        if agent_id:
+            query += " AND agent_id = ?"
+            params.append(agent_id)
+        if kind:
+            query += " AND kind = ?"
+            params.append(kind)
+        if outcome:
+            query += " AND outcome = ?"
+            params.append(outcome)
+        query += " ORDER BY started_at DESC LIMIT ?"
+        params.append(min(int(limit), 500))
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            return [self._hydrate(r)
+                    for r in conn.execute(query, params).fetchall()]
+        finally:
+            conn.close()
+
+
+__all__ = ["DecisionTracer", "DecisionTrace"]
@@ -0,0 +1,199 @@
+#!/usr/bin/env python3
+"""
+Delivery Tracker for Leviathan Super-Brain
+==========================================
+Persistent tracking of outbound message deliveries. Previous tracker
+state lived in memory only — a restart forgot which messages had been
+delivered, so in-flight messages were silently dropped or double-sent.
+Entries now persist to SQLite (WAL, safe for concurrent daemon threads)
+with explicit status transitions, and a reconciliation pass on startup
+re-queues or expires whatever was in flight when the process died.
+
+Lifecycle:
+  queued → sending → delivered
+                   ↘ failed (retryable, back to queued)
+  queued/sending past TTL → expired (terminal)
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import uuid
+import logging
+from datetime import datetime, timedelta, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+DELIVERY_TTL_MINUTES = int(os.environ.get("DELIVERY_TTL_MINUTES", "60"))
+MAX_DELIVERY_ATTEMPTS = int(os.environ.get("MAX_DELIVERY_ATTEMPTS", "3"))
+
+VALID_TRANSITIONS = {
+    "queued": ["sending", "expired"],
+    "sending": ["delivered", "failed", "expired"],
+    "failed": ["queued", "expired"],
+}
+
+log = logging.getLogger("delivery_tracker")
+
+
+class DeliveryTracker:
+    """
+    SQLite-backed delivery tracker. Each outbound message gets an entry
+    before the send is attempted; senders mark transitions as the send
+    progresses. All state survives restarts.
+    """
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        """Create delivery tables if they don't exist."""
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS delivery_entries (
+                    delivery_id TEXT PRIMARY KEY,
+                    channel TEXT NOT NULL,
+                    recipient TEXT NOT NULL,
+                    payload TEXT,
+                    status TEXT NOT NULL DEFAULT 'queued',
+                    attempts INTEGER NOT NULL DEFAULT 0,
+                    last_error TEXT,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    delivered_at TEXT
+                )
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_delivery_status
+                ON delivery_entries(status, created_at)
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _now() -> str:
+        return datetime.now(timezone.utc).isoformat()
+
+    def track(self, channel: str, recipient: str, payload: dict = None) -> dict:
+        """Create a queued delivery entry before attempting the send."""
+        delivery_id = uuid.uuid4().hex[:16]
+        now = self._now()
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT INTO delivery_entries
+                   (delivery_id, channel, recipient, payload, status, created_at, updated_at)
+                   VALUES (?, ?, ?, ?, 'queued', ?, ?)""",
+                (delivery_id, channel, recipient, json.dumps(payload or {}), now, now),
+            )
+            conn.commit()
+            return {"delivery_id": delivery_id, "status": "queued", "created_at": now}
+        finally:
+            conn.close()
+
+    def mark(self, delivery_id: str, status: str, error: str = None) -> dict:
+        """Transition a delivery entry, enforcing VALID_TRANSITIONS."""
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                "SELECT status, attempts FROM delivery_entries WHERE delivery_id = ?",
+                (delivery_id,),
+            ).fetchone()
+            if not row:
+                return {"error": f"Unknown delivery: {delivery_id}"}
+            current, attempts = row
+            if status not in VALID_TRANSITIONS.get(current, []):
+                return {
+                    "error": f"Invalid transition: {current} → {status}",
+                    "allowed": VALID_TRANSITIONS.get(current, []),
+                }
+
+            now = self._now()
+            delivered_at = now if status == "delivered" else None
+            new_attempts = attempts + 1 if status == "sending" else attempts
+            conn.execute(
+                """UPDATE delivery_entries
+                   SET status = ?, attempts = ?, last_error = COALESCE(?, last_error),
+                       updated_at = ?, delivered_at = COALESCE(?, delivered_at)
+                   WHERE delivery_id = ?""",
+                (status, new_attempts, error, now, delivered_at, delivery_id),
+            )
+            conn.commit()
+            return {"delivery_id": delivery_id, "status": status, "attempts": new_attempts}
+        finally:
+            conn.close()
+
+    def reconcile_on_startup(self) -> dict:
+        """
+        Reconciliation pass for entries left in flight by a previous
+        process: entries past the TTL or out of attempts are expired,
+        the rest go back to 'queued' for re-send by the delivery daemon.
+        """
+        cutoff = (datetime.now(timezone.utc) - timedelta(minutes=DELIVERY_TTL_MINUTES)).isoformat()
+        now = self._now()
+        conn = self._connect()
+        try:
+            expired = conn.execute(
+                """UPDATE delivery_entries SET status = 'expired', updated_at = ?
+                   WHERE status IN ('queued', 'sending', 'failed')
+                   AND (created_at < ? OR attempts >= ?)""",
+                (now, cutoff, MAX_DELIVERY_ATTEMPTS),
+            ).rowcount
+            requeued = conn.execute(
+                """UPDATE delivery_entries SET status = 'queued', updated_at = ?
+                   WHERE status IN ('sending', 'failed')""",
+                (now,),
+            ).rowcount
+            conn.commit()
+            log.info(f"[RECONCILE] Startup pass: {requeued} re-queued, {expired} expired")
+            return {"requeued": requeued, "expired": expired}
+        finally:
+            conn.close()
+
+    def pending(self, limit: int = 100) -> list:
+        """Queued entries waiting for a send attempt."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            rows = conn.execute(
+                "SELECT * FROM delivery_entries WHERE status = 'queued' ORDER BY created_at LIMIT ?",
+                (limit,),
+            ).fetchall()
+            results = []
+            for row in rows:
+                entry = dict(row)
+                entry["payload"] = json.loads(entry["payload"]) if entry["payload"] else {}
+                results.append(entry)
+            return results
+        finally:
+            conn.close()
+
+    def stats(self) -> dict:
+        """Delivery counts by status, for health/status endpoints."""
+        conn = self._connect()
+        try:
+            by_status = dict(conn.execute(
+                "SELECT status, COUNT(*) FROM delivery_entries GROUP BY status"
+            ).fetchall())
+            return {
+                "by_status": by_status,
+                "in_flight": by_status.get("queued", 0) + by_status.get("sending", 0),
+            }
+        finally:
+            conn.close()
+
+
+__all__ = ["DeliveryTracker"]
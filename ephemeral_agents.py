@@ -0,0 +1,247 @@
+#!/usr/bin/env python3
+"""
+Time-Boxed Ephemeral Agents for Leviathan Super-Brain
+=====================================================
+Coordinators can spawn one-off agents (a research agent for a single
+investigation, a reviewer for one PR) that are born with a limit: a TTL,
+a turn budget, or both. The kernel cleans them up automatically when the
+limit is hit — transcripts deleted, manifest unregistered — their spend
+rolls up to the spawner for accounting, and the registry hides them from
+normal listings so the fleet view stays about the permanent roster.
+
+Features:
+  1. spawn() — register a limited-lifetime agent on behalf of a spawner
+  2. note_turn() — turn-budget bookkeeping, flags exhaustion
+  3. cleanup_pass() — reap expired/exhausted agents and their memory
+  4. spend_rollup() — spawner's own spend plus all of its ephemerals'
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import secrets
+import os
+import logging
+from datetime import datetime, timedelta, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+
+# Hard ceiling so a typo'd TTL can't create a year-long "ephemeral" agent
+EPHEMERAL_MAX_TTL_SECONDS = int(os.environ.get("EPHEMERAL_MAX_TTL_SECONDS", str(7 * 24 * 3600)))
+
+log = logging.getLogger("ephemeral_agents")
+
+
+class EphemeralAgentManager:
+    """Lifecycle bookkeeping for spawned agents with a TTL or turn limit."""
+
+    def __init__(self, registry, usage_store=None, db_path: str = DB_PATH):
+        self.registry = registry
+        self.usage_store = usage_store
+        self.db_path = db_path
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS ephemeral_agents (
+                    agent_id TEXT PRIMARY KEY,
+                    spawner_id TEXT NOT NULL,
+                    expires_at TEXT,
+                    max_turns INTEGER,
+                    turns_used INTEGER NOT NULL DEFAULT 0,
+                    status TEXT NOT NULL DEFAULT 'active',
+                    spawned_at TEXT NOT NULL,
+                    cleaned_at TEXT
+                )
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_ephemeral_spawner
+                ON ephemeral_agents(spawner_id)
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _now() -> datetime:
+        return datetime.now(timezone.utc)
+
+    def spawn(self, spawner_id: str, name: str, manifest: dict,
+              ttl_seconds: int = None, max_turns: int = None) -> dict:
+        """
+        Register a time-boxed agent. At least one limit is required —
+        an ephemeral agent without one is just a regular agent with a
+        misleading name. The manifest is labelled so the registry can
+        keep it out of normal listings.
+        """
+        if not ttl_seconds and not max_turns:
+            return {"error": "Ephemeral agents need 'ttl_seconds' or 'max_turns'"}
+        if ttl_seconds and ttl_seconds > EPHEMERAL_MAX_TTL_SECONDS:
+            return {"error": f"ttl_seconds exceeds maximum "
+                             f"({EPHEMERAL_MAX_TTL_SECONDS}s)"}
+
+        agent_id = f"eph-{secrets.token_hex(6)}"
+        now = self._now()
+        expires_at = (now + timedelta(seconds=ttl_seconds)).isoformat() if ttl_seconds else None
+
+        manifest = dict(manifest or {})
+        labels = dict(manifest.get("labels") or {})
+        labels["ephemeral"] = True
+        labels["spawner"] = spawner_id
+        manifest["labels"] = labels
+        self.registry.register(agent_id, name, manifest)
+
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT INTO ephemeral_agents
+                   (agent_id, spawner_id, expires_at, max_turns, spawned_at)
+                   VALUES (?, ?, ?, ?, ?)""",
+                (agent_id, spawner_id, expires_at, max_turns, now.isoformat()),
+            )
+            conn.commit()
+        finally:
+            conn.close()
+        log.info(f"[EPHEMERAL] {spawner_id} spawned {agent_id} "
+                 f"(ttl={ttl_seconds}s, max_turns={max_turns})")
+        return {"agent_id": agent_id, "spawner_id": spawner_id,
+                "expires_at": expires_at, "max_turns": max_turns,
+                "status": "active"}
+
+    def get(self, agent_id: str) -> dict:
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            row = conn.execute(
+                "SELECT * FROM ephemeral_agents WHERE agent_id = ?", (agent_id,)
+            ).fetchone()
+            return dict(row) if row else None
+        finally:
+            conn.close()
+
+    def spawner_of(self, agent_id: str) -> str:
+        """The spawner an ephemeral agent's spend rolls up to, or None
+        for regular agents."""
+        entry = self.get(agent_id)
+        return entry["spawner_id"] if entry else None
+
+    def note_turn(self, agent_id: str) -> dict:
+        """Count one turn against the agent's budget. No-op for regular
+        agents; returns the remaining allowance for ephemeral ones."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            row = conn.execute(
+                "SELECT max_turns, turns_used, status FROM ephemeral_agents "
+                "WHERE agent_id = ?", (agent_id,),
+            ).fetchone()
+            if not row or row["status"] != "active":
+                return None
+            used = row["turns_used"] + 1
+            conn.execute(
+                "UPDATE ephemeral_agents SET turns_used = ? WHERE agent_id = ?",
+                (used, agent_id),
+            )
+            conn.commit()
+            remaining = (row["max_turns"] - used) if row["max_turns"] else None
+            return {"agent_id": agent_id, "turns_used": used,
+                    "turns_remaining": remaining,
+                    "exhausted": remaining is not None and remaining <= 0}
+        finally:
+            conn.close()
+
+    def list_active(self, spawner_id: str = None) -> list:
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            query = "SELECT * FROM ephemeral_agents WHERE status = 'active'"
+            params = []
+            if spawner_id:
+                query += " AND spawner_id = ?"
+                params.append(spawner_id)
+            return [dict(r) for r in conn.execute(
+                query + " ORDER BY spawned_at", params).fetchall()]
+        finally:
+            conn.close()
+
+    def _expired(self, entry: dict, now: str) -> str:
+        """Reason the entry should be reaped, or None while within limits."""
+        if entry["expires_at"] and entry["expires_at"] <= now:
+            return "ttl"
+        if entry["max_turns"] and entry["turns_used"] >= entry["max_turns"]:
+            return "turns"
+        return None
+
+    def cleanup_pass(self) -> list:
+        """
+        Reap every active ephemeral agent past its limit: delete its
+        transcripts, unregister it from the registry, mark it cleaned.
+        Returns the reaped entries for logging.
+        """
+        now = self._now().isoformat()
+        reaped = []
+        for entry in self.list_active():
+            reason = self._expired(entry, now)
+            if not reason:
+                continue
+            agent_id = entry["agent_id"]
+            conn = self._connect()
+            try:
+                # Ephemeral memory dies with the agent
+                try:
+                    conn.execute("DELETE FROM turn_transcripts WHERE agent_id = ?",
+                                 (agent_id,))
+                except sqlite3.OperationalError:
+                    pass  # no transcript table on this data dir
+                conn.execute("DELETE FROM agent_skills WHERE agent_id = ?", (agent_id,))
+                conn.execute("DELETE FROM agent_manifests WHERE agent_id = ?", (agent_id,))
+                conn.execute(
+                    "UPDATE ephemeral_agents SET status = 'cleaned', cleaned_at = ? "
+                    "WHERE agent_id = ?", (now, agent_id),
+                )
+                conn.commit()
+            finally:
+                conn.close()
+            entry["reaped_for"] = reason
+            reaped.append(entry)
+            log.info(f"[EPHEMERAL] Reaped {agent_id} ({reason} limit, "
+                     f"spawner {entry['spawner_id']})")
+        return reaped
+
+    def spend_rollup(self, spawner_id: str) -> dict:
+        """Spawner's own spend plus everything its ephemerals (live and
+        cleaned) burned — the number that goes against its budget."""
+        conn = self._connect()
+        try:
+            children = [r[0] for r in conn.execute(
+                "SELECT agent_id FROM ephemeral_agents WHERE spawner_id = ?",
+                (spawner_id,),
+            ).fetchall()]
+        finally:
+            conn.close()
+        rollup = {"spawner_id": spawner_id, "ephemeral_agents": len(children),
+                  "own_cost_usd": 0.0, "ephemeral_cost_usd": 0.0}
+        if self.usage_store is not None:
+            for row in self.usage_store.agent_totals(agent_id=spawner_id):
+                rollup["own_cost_usd"] = round(row["cost_usd"] or 0.0, 6)
+            for child in children:
+                for row in self.usage_store.agent_totals(agent_id=child):
+                    rollup["ephemeral_cost_usd"] += row["cost_usd"] or 0.0
+            rollup["ephemeral_cost_usd"] = round(rollup["ephemeral_cost_usd"], 6)
+        rollup["total_cost_usd"] = round(
+            rollup["own_cost_usd"] + rollup["ephemeral_cost_usd"], 6)
+        return rollup
+
+
+__all__ = ["EphemeralAgentManager"]
@@ -0,0 +1,194 @@
+#!/usr/bin/env python3
+"""
+Error Reporting Sink for Leviathan Super-Brain
+==============================================
+Batches kernel/agent errors to a configurable external sink — a
+Sentry-compatible store endpoint or a generic JSON webhook — with
+correlation IDs, agent context and sampling controls. Production
+failures reach us before users complain.
+
+Configuration (env):
+  ERROR_SINK_URL       — where batches go (empty = reporting disabled)
+  ERROR_SINK_KIND      — 'webhook' (default) or 'sentry'
+  ERROR_SAMPLE_RATE    — 0.0–1.0, fraction of non-critical errors kept
+  ERROR_FLUSH_SECONDS  — batch flush interval (default 30)
+  ERROR_BATCH_MAX      — max events per batch (default 50)
+
+Author: Leviathan DevOps
+"""
+
+import os
+import json
+import time
+import uuid
+import random
+import logging
+import threading
+from collections import deque
+from datetime import datetime, timezone
+
+import requests
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+ERROR_SINK_URL = os.environ.get("ERROR_SINK_URL", "")
+ERROR_SINK_KIND = os.environ.get("ERROR_SINK_KIND", "webhook")
+ERROR_SAMPLE_RATE = float(os.environ.get("ERROR_SAMPLE_RATE", "1.0"))
+ERROR_FLUSH_SECONDS = int(os.environ.get("ERROR_FLUSH_SECONDS", "30"))
+ERROR_BATCH_MAX = int(os.environ.get("ERROR_BATCH_MAX", "50"))
+MAX_BUFFER = 1000  # drop oldest beyond this — reporting must never OOM us
+
+log = logging.getLogger("error_reporter")
+
+
+class ErrorReporter:
+    """
+    Thread-safe buffered error reporter with a background flush loop.
+
+    Events are sampled on capture (critical events always kept), held in
+    a bounded deque, and flushed in batches to the configured sink.
+    """
+
+    def __init__(self, sink_url: str = ERROR_SINK_URL, sink_kind: str = ERROR_SINK_KIND,
+                 sample_rate: float = ERROR_SAMPLE_RATE):
+        self.sink_url = sink_url
+        self.sink_kind = sink_kind
+        self.sample_rate = max(0.0, min(1.0, sample_rate))
+        self.buffer = deque(maxlen=MAX_BUFFER)
+        self.lock = threading.Lock()
+        self.stats = {"captured": 0, "sampled_out": 0, "sent": 0, "send_failures": 0}
+        self._flusher = None
+
+    @property
+    def enabled(self) -> bool:
+        return bool(self.sink_url)
+
+    def capture(self, error, agent_id: str = None, correlation_id: str = None,
+                context: dict = None, critical: bool = False) -> str:
+        """
+        Record one error event. Returns the correlation ID (generated if
+        not supplied) so callers can surface it to users/logs.
+        """
+        correlation_id = correlation_id or uuid.uuid4().hex[:12]
+        if not self.enabled:
+            return correlation_id
+
+        # Sampling — critical events (panics, crashes) always go through
+        if not critical and random.random() > self.sample_rate:
+            with self.lock:
+                self.stats["sampled_out"] += 1
+            return correlation_id
+
+        event = {
+            "correlation_id": correlation_id,
+            "timestamp": datetime.now(timezone.utc).isoformat(),
+            "level": "fatal" if critical else "error",
+            "error_type": type(error).__name__ if isinstance(error, BaseException) else "message",
+            "message": str(error),
+            "code": getattr(error, "code", None),
+            "agent_id": agent_id,
+            "context": context or {},
+            "service": "cloudfang-leviathan",
+        }
+        with self.lock:
+            self.buffer.append(event)
+            self.stats["captured"] += 1
+        return correlation_id
+
+    def _drain(self) -> list:
+        with self.lock:
+            batch = []
+            while self.buffer and len(batch) < ERROR_BATCH_MAX:
+                batch.append(self.buffer.popleft())
+            return batch
+
+    def _send(self, batch: list) -> bool:
+        """Send one batch to the sink. Returns success."""
+        try:
+            if self.sink_kind == "sentry":
+                # Sentry store API: one event per request
+                headers = {"Content-Type": "application/json"}
+                dsn_key = os.environ.get("ERROR_SINK_SENTRY_KEY", "")
+                if dsn_key:
+                    headers["X-Sentry-Auth"] = (
+                        f"Sentry sentry_version=7, sentry_key={dsn_key}, "
+                        f"sentry_client=leviathan/1.0"
+                    )
+                for event in batch:
+                    sentry_event = {
+                        "event_id": uuid.uuid4().hex,
+                        "timestamp": event["timestamp"],
+                        "level": event["level"],
+                        "message": event["message"],
+                        "tags": {
+                            "correlation_id": event["correlation_id"],
+                            "agent_id": event["agent_id"] or "none",
+                            "code": event["code"] or "none",
+                        },
+                        "extra": event["context"],
+                        "platform": "python",
+                        "server_name": event["service"],
+                    }
+                    resp = requests.post(self.sink_url, headers=headers,
+                                         json=sentry_event, timeout=10)
+                    if resp.status_code >= 400:
+                        return False
+                return True
+            # generic webhook: whole batch as one JSON payload
+            resp = requests.post(self.sink_url, json={"events": batch}, timeout=10)
+            return resp.status_code < 400
+        except Exception as e:
+            log.warning(f"[SINK] Send failed: {e}")
+            return False
+
+    def flush(self) -> int:
+        """Flush buffered events now. Returns how many were sent."""
+        sent = 0
+        while True:
+            batch = self._drain()
+            if not batch:
+                break
+            if self._send(batch):
+                sent += len(batch)
+                with self.lock:
+                    self.stats["sent"] += len(batch)
+            else:
+                # Put the batch back and stop — the sink is unhappy
+                with self.lock:
+                    self.stats["send_failures"] += 1
+                    for event in reversed(batch):
+                        self.buffer.appendleft(event)
+                break
+        return sent
+
+    def start_flusher(self):
+        """Start the background flush thread (idempotent)."""
+        if self._flusher or not self.enabled:
+            return
+        self._flusher = threading.Thread(target=self._flush_loop, daemon=True,
+                                         name="ErrorReporterFlush")
+        self._flusher.start()
+        log.info(f"[SINK] Error reporter started (kind={self.sink_kind}, "
+                 f"sample_rate={self.sample_rate}, every {ERROR_FLUSH_SECONDS}s)")
+
+    def _flush_loop(self):
+        while True:
+            time.sleep(ERROR_FLUSH_SECONDS)
+            try:
+                self.flush()
+            except Exception as e:
+                log.error(f"[SINK] Flush loop error: {e}")
+
+    def status(self) -> dict:
+        with self.lock:
+            return {
+                "enabled": self.enabled,
+                "sink_kind": self.sink_kind,
+                "sample_rate": self.sample_rate,
+                "buffered": len(self.buffer),
+                **self.stats,
+            }
+
+
+__all__ = ["ErrorReporter"]
@@ -0,0 +1,179 @@
+#!/usr/bin/env python3
+"""
+Structured Error Taxonomy for Leviathan Super-Brain
+===================================================
+Every LeviathanError carries a stable machine-readable code, a retryable
+flag, an HTTP status mapping for the APIs, and a safe user-facing
+message. Gateways and the dashboard present errors consistently instead
+of leaking internals (stack traces, provider payloads, file paths).
+
+Usage:
+    raise QuotaExceededError(f"agent {agent_id} hit daily token quota")
+
+    # In Flask handlers:
+    except LeviathanError as e:
+        return jsonify(e.to_dict()), e.http_status
+
+The `detail` passed to the constructor is logged but never shown to end
+users — user_message is the only text that leaves the system.
+
+Author: Leviathan DevOps
+"""
+
+import logging
+
+log = logging.getLogger("errors")
+
+
+class LeviathanError(Exception):
+    """
+    Base error. Subclasses define the taxonomy; instances add detail.
+
+    Class attributes:
+      code         — stable machine-readable identifier (never changes)
+      retryable    — whether the caller may retry the same request
+      http_status  — status mapping for the HTTP APIs
+      user_message — safe text for end users (no internals)
+    """
+
+    code = "internal_error"
+    retryable = False
+    http_status = 500
+    user_message = "Something went wrong. The team has been notified."
+
+    def __init__(self, detail: str = None):
+        super().__init__(detail or self.user_message)
+        self.detail = detail
+
+    def to_dict(self) -> dict:
+        """API-facing representation. Internal detail is deliberately omitted."""
+        return {
+            "error": self.code,
+            "message": self.user_message,
+            "retryable": self.retryable,
+        }
+
+
+class ConfigError(LeviathanError):
+    code = "config_error"
+    retryable = False
+    http_status = 500
+    user_message = "The system is misconfigured. An operator needs to intervene."
+
+
+class AuthError(LeviathanError):
+    code = "auth_error"
+    retryable = False
+    http_status = 401
+    user_message = "Authentication failed."
+
+
+class NotFoundError(LeviathanError):
+    code = "not_found"
+    retryable = False
+    http_status = 404
+    user_message = "The requested resource does not exist."
+
+
+class ValidationError(LeviathanError):
+    code = "validation_error"
+    retryable = False
+    http_status = 400
+    user_message = "The request was malformed or missing required fields."
+
+
+class QuotaExceededError(LeviathanError):
+    code = "quota_exceeded"
+    retryable = True  # retry after the quota window resets
+    http_status = 429
+    user_message = "Usage limit reached. Please try again later."
+
+
+class BudgetExhaustedError(LeviathanError):
+    code = "budget_exhausted"
+    retryable = True
+    http_status = 429
+    user_message = "The spending budget for this period is exhausted."
+
+
+class SpendingFrozenError(LeviathanError):
+    code = "spending_frozen"
+    retryable = True
+    http_status = 403
+    user_message = "Paid operations are temporarily paused by an operator."
+
+
+class ApprovalRequiredError(LeviathanError):
+    code = "approval_required"
+    retryable = True  # retry once the approval is granted
+    http_status = 403
+    user_message = "This action needs confirmation before it can run."
+
+
+class ProviderError(LeviathanError):
+    code = "provider_error"
+    retryable = True
+    http_status = 502
+    user_message = "The AI provider returned an error. Please try again."
+
+
+class ProviderTimeoutError(ProviderError):
+    code = "provider_timeout"
+    retryable = True
+    http_status = 504
+    user_message = "The AI provider took too long to respond. Please try again."
+
+
+class GatewayError(LeviathanError):
+    code = "gateway_error"
+    retryable = True
+    http_status = 502
+    user_message = "Message delivery failed. We'll retry shortly."
+
+
+class StorageError(LeviathanError):
+    code = "storage_error"
+    retryable = True
+    http_status = 503
+    user_message = "A storage operation failed. Please try again."
+
+
+# Stable registry: code → class. New variants must be added here so the
+# dashboard and gateways can enumerate the taxonomy.
+ERROR_TAXONOMY = {
+    cls.code: cls
+    for cls in (
+        LeviathanError, ConfigError, AuthError, NotFoundError, ValidationError,
+        QuotaExceededError, BudgetExhaustedError, SpendingFrozenError,
+        ApprovalRequiredError, ProviderError, ProviderTimeoutError,
+        GatewayError, StorageError,
+    )
+}
+
+
+def from_code(code: str, detail: str = None) -> LeviathanError:
+    """Reconstruct an error instance from its stable code."""
+    cls = ERROR_TAXONOMY.get(code, LeviathanError)
+    return cls(detail)
+
+
+def taxonomy_table() -> list:
+    """Full taxonomy for the dashboard: code, retryable, status, message."""
+    return [
+        {
+            "code": cls.code,
+            "retryable": cls.retryable,
+            "http_status": cls.http_status,
+            "user_message": cls.user_message,
+        }
+        for cls in ERROR_TAXONOMY.values()
+    ]
+
+
+__all__ = [
+    "LeviathanError", "ConfigError", "AuthError", "NotFoundError",
+    "ValidationError", "QuotaExceededError", "BudgetExhaustedError",
+    "SpendingFrozenError", "ApprovalRequiredError", "ProviderError",
+    "ProviderTimeoutError", "GatewayError", "StorageError",
+    "ERROR_TAXONOMY", "from_code", "taxonomy_table",
+]
@@ -0,0 +1,166 @@
+#!/usr/bin/env python3
+"""
+Event Bus for Leviathan Super-Brain
+===================================
+In-process pub/sub connecting daemons, stores and gateways. Publishers
+emit topic events; each subscriber gets its own bounded queue drained by
+a dedicated worker thread, so one slow consumer can't stall the others.
+
+Every subscriber exposes health metrics — queue depth, processing
+latency, error rate, last-consumed sequence — so we can see which agent
+is falling behind *before* its queue overflows.
+
+Usage:
+    bus = EventBus()
+    bus.subscribe("budget.*", "alerter", handler_fn)
+    bus.publish("budget.warning", {"agent_id": "a1", "pct": 85})
+
+Author: Leviathan DevOps
+"""
+
+import os
+import queue
+import time
+import logging
+import threading
+import fnmatch
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+SUBSCRIBER_QUEUE_MAX = int(os.environ.get("EVENT_BUS_QUEUE_MAX", "1000"))
+
+log = logging.getLogger("event_bus")
+
+
+class Subscriber:
+    """One subscriber: pattern, handler, bounded queue, worker thread, metrics."""
+
+    def __init__(self, name: str, pattern: str, handler):
+        self.name = name
+        self.pattern = pattern
+        self.handler = handler
+        self.queue = queue.Queue(maxsize=SUBSCRIBER_QUEUE_MAX)
+        self.lock = threading.Lock()
+        # Metrics
+        self.consumed = 0
+        self.errors = 0
+        self.dropped = 0
+        self.last_sequence = 0
+        self.last_consumed_at = None
+        self.latency_ms_avg = 0.0  # exponential moving average
+        self.thread = threading.Thread(target=self._run, daemon=True,
+                                       name=f"bus-{name}")
+        self.thread.start()
+
+    def matches(self, topic: str) -> bool:
+        return fnmatch.fnmatch(topic, self.pattern)
+
+    def offer(self, event: dict) -> bool:
+        """Enqueue an event; drops (and counts) when the queue is full."""
+        try:
+            self.queue.put_nowait(event)
+            return True
+        except queue.Full:
+            with self.lock:
+                self.dropped += 1
+            log.warning(f"[BUS] Subscriber '{self.name}' queue full — event dropped "
+                        f"(seq {event['sequence']}, topic {event['topic']})")
+            return False
+
+    def _run(self):
+        while True:
+            event = self.queue.get()
+            started = time.time()
+            try:
+                self.handler(event)
+                ok = True
+            except Exception as e:
+                ok = False
+                log.error(f"[BUS] Handler '{self.name}' failed on {event['topic']}: {e}")
+            elapsed_ms = (time.time() - started) * 1000
+            with self.lock:
+                self.consumed += 1
+                if not ok:
+                    self.errors += 1
+                self.last_sequence = event["sequence"]
+                self.last_consumed_at = datetime.now(timezone.utc).isoformat()
+                # EWMA with alpha 0.2 — smooth but responsive
+                self.latency_ms_avg = 0.8 * self.latency_ms_avg + 0.2 * elapsed_ms
+
+    def metrics(self, bus_sequence: int) -> dict:
+        with self.lock:
+            return {
+                "subscriber": self.name,
+                "pattern": self.pattern,
+                "queue_depth": self.queue.qsize(),
+                "consumed": self.consumed,
+                "errors": self.errors,
+                "dropped": self.dropped,
+                "error_rate": round(self.errors / self.consumed, 4) if self.consumed else 0.0,
+                "latency_ms_avg": round(self.latency_ms_avg, 2),
+                "last_sequence": self.last_sequence,
+                "lag": max(0, bus_sequence - self.last_sequence),
+                "last_consumed_at": self.last_consumed_at,
+            }
+
+
+class EventBus:
+    """Topic-based pub/sub with wildcard subscriptions and health metrics."""
+
+    def __init__(self):
+        self.subscribers = {}
+        self.lock = threading.Lock()
+        self.sequence = 0
+        self.published = 0
+
+    def subscribe(self, pattern: str, name: str, handler) -> Subscriber:
+        """
+        Register a handler for topics matching `pattern` (fnmatch-style,
+        e.g. "budget.*"). `name` must be unique; re-subscribing replaces.
+        """
+        sub = Subscriber(name, pattern, handler)
+        with self.lock:
+            self.subscribers[name] = sub
+        log.info(f"[BUS] Subscribed '{name}' to '{pattern}'")
+        return sub
+
+    def unsubscribe(self, name: str) -> bool:
+        with self.lock:
+            return self.subscribers.pop(name, None) is not None
+
+    def publish(self, topic: str, payload: dict = None) -> dict:
+        """Publish an event to all matching subscribers. Never blocks."""
+        with self.lock:
+            self.sequence += 1
+            self.published += 1
+            event = {
+                "sequence": self.sequence,
+                "topic": topic,
+                "payload": payload or {},
+                "published_at": datetime.now(timezone.utc).isoformat(),
+            }
+            matching = [s for s in self.subscribers.values() if s.matches(topic)]
+        delivered = sum(1 for sub in matching if sub.offer(event))
+        return {"sequence": event["sequence"], "topic": topic,
+                "matched": len(matching), "delivered": delivered}
+
+    def metrics(self) -> dict:
+        """Bus-wide health: per-subscriber lag, depth, latency, error rate."""
+        with self.lock:
+            seq = self.sequence
+            subs = list(self.subscribers.values())
+        return {
+            "published": self.published,
+            "current_sequence": seq,
+            "subscriber_count": len(subs),
+            "subscribers": [s.metrics(seq) for s in subs],
+        }
+
+
+# Shared bus instance — daemons and stores import this rather than
+# constructing their own.
+bus = EventBus()
+
+__all__ = ["EventBus", "Subscriber", "bus"]
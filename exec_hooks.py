@@ -0,0 +1,147 @@
+#!/usr/bin/env python3
+"""
+Exec Hooks for Leviathan Super-Brain
+====================================
+Config-defined "run this command when that happens" automation: selected
+bus events (agent crashed, approval granted, budget exhausted) trigger a
+local command with templated arguments, so one-line site-specific glue —
+poke systemd, touch a flag file, page a legacy alerter — doesn't need a
+plugin or a webhook receiver.
+
+Hooks come from EXEC_HOOKS_JSON, a list of:
+  {"name": "restart-agent", "topics": ["agent.crashed"],
+   "command": ["/opt/hooks/restart.sh", "{agent_id}"],
+   "timeout_seconds": 30}
+{placeholders} in arguments resolve from the event payload (plus topic /
+sequence / published_at); commands are argv lists, never shell strings.
+Output is captured (truncated) into a bounded in-memory history.
+
+Author: Leviathan DevOps
+"""
+
+import json
+import os
+import fnmatch
+import logging
+import subprocess
+import threading
+from collections import deque
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+EXEC_HOOKS_JSON = os.environ.get("EXEC_HOOKS_JSON", "")
+
+EXEC_HOOK_DEFAULT_TIMEOUT = int(os.environ.get("EXEC_HOOK_DEFAULT_TIMEOUT", "30"))
+EXEC_HOOK_OUTPUT_CAP = int(os.environ.get("EXEC_HOOK_OUTPUT_CAP", "4000"))
+EXEC_HOOK_HISTORY = int(os.environ.get("EXEC_HOOK_HISTORY", "200"))
+
+log = logging.getLogger("exec_hooks")
+
+
+class _SafeDict(dict):
+    """Leave unknown {placeholders} intact instead of raising."""
+    def __missing__(self, key):
+        return "{" + key + "}"
+
+
+class ExecHookRunner:
+    """Event bus → local command bridge with templating and capture."""
+
+    def __init__(self, hooks_json: str = EXEC_HOOKS_JSON):
+        self.hooks = []
+        self.history = deque(maxlen=EXEC_HOOK_HISTORY)
+        self._lock = threading.Lock()
+        if hooks_json:
+            try:
+                self.hooks = self._validate(json.loads(hooks_json))
+            except (json.JSONDecodeError, ValueError) as e:
+                log.error(f"[HOOKS] Bad EXEC_HOOKS_JSON ignored: {e}")
+        if self.hooks:
+            log.info(f"[HOOKS] {len(self.hooks)} exec hook(s) configured: "
+                     f"{', '.join(h['name'] for h in self.hooks)}")
+        else:
+            log.info("[HOOKS] No exec hooks configured")
+
+    @staticmethod
+    def _validate(raw) -> list:
+        if not isinstance(raw, list):
+            raise ValueError("EXEC_HOOKS_JSON must be a list of hooks")
+        hooks = []
+        for hook in raw:
+            if not hook.get("name") or not hook.get("topics") \
+                    or not isinstance(hook.get("command"), list) \
+                    or not hook["command"]:
+                raise ValueError(f"Hook needs 'name', 'topics' and a "
+                                 f"'command' argv list: {hook}")
+            hooks.append({
+                "name": hook["name"],
+                "topics": list(hook["topics"]),
+                "command": [str(a) for a in hook["command"]],
+                "timeout_seconds": int(hook.get("timeout_seconds",
+                                                EXEC_HOOK_DEFAULT_TIMEOUT)),
+            })
+        return hooks
+
+    def handle_event(self, event: dict):
+        """Bus subscriber handler: run every hook whose topic filter
+        matches. Hooks run inline on the bus delivery thread — they're
+        meant for second-scale glue, the timeout keeps them honest."""
+        for hook in self.hooks:
+            if any(fnmatch.fnmatch(event["topic"], t) for t in hook["topics"]):
+                self._run(hook, event)
+
+    def _run(self, hook: dict, event: dict):
+        payload = event.get("payload") or {}
+        context = _SafeDict({k: str(v) for k, v in payload.items()})
+        context.update(topic=event["topic"],
+                       sequence=str(event.get("sequence", "")),
+                       published_at=event.get("published_at", ""))
+        argv = [arg.format_map(context) for arg in hook["command"]]
+
+        entry = {
+            "hook": hook["name"],
+            "topic": event["topic"],
+            "command": argv,
+            "started_at": datetime.now(timezone.utc).isoformat(),
+        }
+        try:
+            proc = subprocess.run(
+                argv, capture_output=True, text=True,
+                timeout=hook["timeout_seconds"],
+            )
+            entry["exit_code"] = proc.returncode
+            entry["stdout"] = proc.stdout[:EXEC_HOOK_OUTPUT_CAP]
+            entry["stderr"] = proc.stderr[:EXEC_HOOK_OUTPUT_CAP]
+            if proc.returncode != 0:
+                log.warning(f"[HOOKS] {hook['name']} exited "
+                            f"{proc.returncode} for {event['topic']}")
+        except subprocess.TimeoutExpired:
+            entry["error"] = f"timed out after {hook['timeout_seconds']}s"
+            log.warning(f"[HOOKS] {hook['name']} timed out for {event['topic']}")
+        except OSError as e:
+            entry["error"] = str(e)
+            log.warning(f"[HOOKS] {hook['name']} failed to start: {e}")
+        with self._lock:
+            self.history.append(entry)
+
+    def attach(self, bus):
+        """Subscribe to the shared event bus (one wildcard subscription,
+        same shape as the webhook notifier)."""
+        if self.hooks:
+            bus.subscribe("*", "exec-hooks", self.handle_event)
+
+    def status(self) -> dict:
+        with self._lock:
+            recent = list(self.history)[-20:]
+        return {
+            "hooks": [{"name": h["name"], "topics": h["topics"],
+                       "timeout_seconds": h["timeout_seconds"]}
+                      for h in self.hooks],
+            "executions": len(self.history),
+            "recent": recent,
+        }
+
+
+__all__ = ["ExecHookRunner"]
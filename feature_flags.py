@@ -0,0 +1,213 @@
+#!/usr/bin/env python3
+"""
+Feature Flags for Leviathan Super-Brain
+=======================================
+Runtime-evaluated flags so risky new behaviors (streaming replies, a new
+router) can roll out gradually instead of flipping for everyone at once.
+A flag is one row:
+
+  - enabled        — the master switch; off means off for everybody
+  - percentage     — 0–100 rollout; a subject (user, else agent) hashes
+    into a stable bucket, so the same user stays in or out across calls
+  - tenants        — optional allowlist; when set, only these tenants
+    see the flag at all (percentage applies inside the allowlist)
+
+Evaluation reads the store on every call — an update is live on the
+next check, no reload, same hot-path behaviour as the quota store.
+FEATURE_FLAGS_JSON seeds flags at boot for config-managed deployments
+(existing rows win, so runtime changes survive restarts).
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import hashlib
+import logging
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+
+# Boot-time seed: {"streaming_replies": {"enabled": true, "percentage": 10}}
+FEATURE_FLAGS_JSON = os.environ.get("FEATURE_FLAGS_JSON", "")
+
+log = logging.getLogger("feature_flags")
+
+
+class FeatureFlagStore:
+    """SQLite-backed flags, evaluated per call (hot-reloadable)."""
+
+    def __init__(self, db_path: str = DB_PATH,
+                 seed_json: str = FEATURE_FLAGS_JSON):
+        self.db_path = db_path
+        self.ensure_schema()
+        if seed_json:
+            self._seed(seed_json)
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS feature_flags (
+                    name TEXT PRIMARY KEY,
+                    enabled INTEGER NOT NULL DEFAULT 0,
+                    percentage REAL NOT NULL DEFAULT 100,
+                    tenants TEXT,
+                    description TEXT,
+                    updated_by TEXT,
+                    updated_at TEXT NOT NULL
+                )
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    def _seed(self, seed_json: str):
+        """Insert env-configured flags that don't exist yet (runtime
+        edits beat the seed)."""
+        try:
+            flags = json.loads(seed_json)
+        except json.JSONDecodeError as e:
+            log.error(f"[FLAGS] Bad FEATURE_FLAGS_JSON ignored: {e}")
+            return
+        conn = self._connect()
+        try:
+            for name, spec in flags.items():
+                exists = conn.execute(
+                    "SELECT 1 FROM feature_flags WHERE name = ?", (name,)
+                ).fetchone()
+                if exists:
+                    continue
+                conn.execute(
+                    """INSERT INTO feature_flags
+                       (name, enabled, percentage, tenants, description,
+                        updated_by, updated_at)
+                       VALUES (?, ?, ?, ?, ?, 'seed', ?)""",
+                    (name, 1 if spec.get("enabled") else 0,
+                     float(spec.get("percentage", 100)),
+                     json.dumps(spec["tenants"]) if spec.get("tenants") else None,
+                     spec.get("description"),
+                     datetime.now(timezone.utc).isoformat()),
+                )
+                log.info(f"[FLAGS] Seeded flag '{name}' from env")
+            conn.commit()
+        finally:
+            conn.close()
+
+    def set_flag(self, name: str, enabled: bool, percentage: float = 100,
+                 tenants: list = None, description: str = None,
+                 updated_by: str = "api") -> dict:
+        """Upsert a flag — live on the next evaluation."""
+        percentage = min(max(float(percentage), 0.0), 100.0)
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT OR REPLACE INTO feature_flags
+                   (name, enabled, percentage, tenants, description,
+                    updated_by, updated_at)
+                   VALUES (?, ?, ?, ?, ?, ?, ?)""",
+                (name, 1 if enabled else 0, percentage,
+                 json.dumps(tenants) if tenants else None, description,
+                 updated_by, datetime.now(timezone.utc).isoformat()),
+            )
+            conn.commit()
+        finally:
+            conn.close()
+        log.info(f"[FLAGS] '{name}' set: enabled={enabled} "
+                 f"percentage={percentage}"
+                 + (f" tenants={tenants}" if tenants else "")
+                 + f" (by {updated_by})")
+        return self.get_flag(name)
+
+    def get_flag(self, name: str) -> dict:
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            row = conn.execute(
+                "SELECT * FROM feature_flags WHERE name = ?", (name,)
+            ).fetchone()
+            if not row:
+                return {"error": f"Unknown flag: {name}"}
+            flag = dict(row)
+            flag["enabled"] = bool(flag["enabled"])
+            flag["tenants"] = json.loads(flag["tenants"]) if flag["tenants"] else None
+            return flag
+        finally:
+            conn.close()
+
+    def list_flags(self) -> list:
+        conn = self._connect()
+        try:
+            names = [r[0] for r in conn.execute(
+                "SELECT name FROM feature_flags ORDER BY name").fetchall()]
+        finally:
+            conn.close()
+        return [self.get_flag(n) for n in names]
+
+    def delete_flag(self, name: str) -> bool:
+        conn = self._connect()
+        try:
+            removed = conn.execute(
+                "DELETE FROM feature_flags WHERE name = ?", (name,)).rowcount
+            conn.commit()
+            return removed > 0
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _bucket(name: str, subject: str) -> float:
+        """Stable 0–100 bucket per (flag, subject) — the same user lands
+        on the same side of the rollout every call, and different flags
+        slice the population differently."""
+        digest = hashlib.sha256(f"{name}:{subject}".encode()).hexdigest()
+        return int(digest[:8], 16) / 0xFFFFFFFF * 100
+
+    def is_enabled(self, name: str, agent_id: str = None,
+                   tenant_id: str = None, user_id: str = None) -> bool:
+        """The yes/no answer agents and gateways branch on."""
+        return self.evaluate(name, agent_id=agent_id, tenant_id=tenant_id,
+                             user_id=user_id)["enabled"]
+
+    def evaluate(self, name: str, agent_id: str = None,
+                 tenant_id: str = None, user_id: str = None) -> dict:
+        """
+        Full evaluation with the reason — for debugging 'why am I not in
+        the rollout'. An unknown flag evaluates disabled (safe default
+        for code that ships ahead of the flag).
+        """
+        flag = self.get_flag(name)
+        if "error" in flag:
+            return {"flag": name, "enabled": False, "reason": "unknown flag"}
+        if not flag["enabled"]:
+            return {"flag": name, "enabled": False, "reason": "flag disabled"}
+        if flag["tenants"] is not None:
+            if tenant_id not in flag["tenants"]:
+                return {"flag": name, "enabled": False,
+                        "reason": f"tenant '{tenant_id}' not in allowlist"}
+        if flag["percentage"] < 100:
+            subject = user_id or agent_id or tenant_id
+            if subject is None:
+                # No stable identity to bucket on — stay conservative
+                return {"flag": name, "enabled": False,
+                        "reason": "percentage rollout needs a subject"}
+            bucket = self._bucket(name, subject)
+            if bucket >= flag["percentage"]:
+                return {"flag": name, "enabled": False,
+                        "reason": f"outside rollout "
+                                  f"({bucket:.1f} >= {flag['percentage']}%)"}
+            return {"flag": name, "enabled": True,
+                    "reason": f"in rollout ({bucket:.1f} < {flag['percentage']}%)"}
+        return {"flag": name, "enabled": True, "reason": "fully enabled"}
+
+
+__all__ = ["FeatureFlagStore", "FEATURE_FLAGS_JSON"]
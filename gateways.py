@@ -0,0 +1,525 @@
+#!/usr/bin/env python3
+"""
+Gateway Abstraction for Leviathan Super-Brain
+=============================================
+Base class for chat gateways (WhatsApp, Telegram, Discord, ...). Beyond
+plain message sending, gateways may optionally support read receipts and
+typing indicators — agents mark inbound messages as read and show typing
+while the LLM is streaming a reply. Both are opt-in per agent so
+privacy-conscious deployments can leave them off. Gateways with reply
+threading can also attach outbound messages to a quoted original, and
+the manager keeps an inbound message log so reply_to IDs resolve back
+to content and the turn that answered them.
+
+Implemented here for WhatsApp (Cloud API); other gateways inherit the
+no-op defaults until they grow native support.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import logging
+from datetime import datetime, timezone
+
+import requests
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+WHATSAPP_API_URL = os.environ.get("WHATSAPP_API_URL", "https://graph.facebook.com/v19.0")
+WHATSAPP_TOKEN = os.environ.get("WHATSAPP_TOKEN", "")
+WHATSAPP_PHONE_ID = os.environ.get("WHATSAPP_PHONE_ID", "")
+WHATSAPP_APP_ID = os.environ.get("WHATSAPP_APP_ID", "")
+WHATSAPP_APP_SECRET = os.environ.get("WHATSAPP_APP_SECRET", "")
+
+log = logging.getLogger("gateways")
+
+
+class Gateway:
+    """
+    Base gateway. Subclasses must implement send_message; read receipts
+    and typing indicators are optional capabilities — the defaults report
+    them as unsupported so callers can feature-detect.
+    """
+
+    name = "base"
+
+    def send_message(self, recipient: str, text: str, reply_to: str = None) -> dict:
+        raise NotImplementedError
+
+    @property
+    def supports_read_receipts(self) -> bool:
+        return False
+
+    @property
+    def supports_typing_indicators(self) -> bool:
+        return False
+
+    @property
+    def supports_reply_threading(self) -> bool:
+        """Whether send_message can attach a reply to a specific earlier
+        message (reply_to). Gateways without it silently send plain."""
+        return False
+
+    def mark_read(self, message_id: str) -> dict:
+        """Mark an inbound message as read. Optional capability."""
+        return {"error": f"Gateway '{self.name}' does not support read receipts"}
+
+    def send_typing(self, message_id: str, active: bool = True) -> dict:
+        """Show/hide a typing indicator in reply to an inbound message.
+        Optional capability."""
+        return {"error": f"Gateway '{self.name}' does not support typing indicators"}
+
+    def health_check(self) -> dict:
+        """Probe the connection. Returns {"status": "ok"} or
+        {"error": ..., "auth_expired": bool} so the supervisor can tell
+        auth expiry from a transient network failure."""
+        return {"status": "ok", "note": "no health probe implemented"}
+
+    def re_login(self) -> dict:
+        """Re-authenticate with stored credentials. Optional capability."""
+        return {"error": f"Gateway '{self.name}' does not support re-login"}
+
+
+class WhatsAppGateway(Gateway):
+    """WhatsApp Cloud API gateway with read-receipt and typing support."""
+
+    name = "whatsapp"
+
+    def __init__(self, token: str = WHATSAPP_TOKEN, phone_id: str = WHATSAPP_PHONE_ID):
+        self.token = token
+        self.phone_id = phone_id
+
+    def _headers(self) -> dict:
+        return {"Authorization": f"Bearer {self.token}", "Content-Type": "application/json"}
+
+    def _post(self, payload: dict) -> dict:
+        if not self.token or not self.phone_id:
+            return {"error": "WhatsApp gateway not configured (WHATSAPP_TOKEN/WHATSAPP_PHONE_ID)"}
+        try:
+            resp = requests.post(
+                f"{WHATSAPP_API_URL}/{self.phone_id}/messages",
+                headers=self._headers(), json=payload, timeout=15,
+            )
+            if resp.status_code == 200:
+                return {"status": "ok", "response": resp.json()}
+            log.warning(f"[WHATSAPP] API returned {resp.status_code}: {resp.text[:200]}")
+            return {"error": f"WhatsApp API returned {resp.status_code}"}
+        except Exception as e:
+            log.error(f"[WHATSAPP] Request failed: {e}")
+            return {"error": str(e)}
+
+    def send_message(self, recipient: str, text: str, reply_to: str = None) -> dict:
+        payload = {
+            "messaging_product": "whatsapp",
+            "to": recipient,
+            "type": "text",
+            "text": {"body": text},
+        }
+        if reply_to:
+            # Cloud API threads the message under the quoted original
+            payload["context"] = {"message_id": reply_to}
+        return self._post(payload)
+
+    @property
+    def supports_read_receipts(self) -> bool:
+        return True
+
+    @property
+    def supports_typing_indicators(self) -> bool:
+        return True
+
+    @property
+    def supports_reply_threading(self) -> bool:
+        return True
+
+    def mark_read(self, message_id: str) -> dict:
+        return self._post({
+            "messaging_product": "whatsapp",
+            "status": "read",
+            "message_id": message_id,
+        })
+
+    def send_typing(self, message_id: str, active: bool = True) -> dict:
+        # Cloud API signals typing by attaching an indicator to the read
+        # status of the inbound message being answered — it must be the
+        # triggering message's ID, not the recipient's number
+        return self._post({
+            "messaging_product": "whatsapp",
+            "status": "read",
+            "message_id": message_id,
+            "typing_indicator": {"type": "text"} if active else None,
+        })
+
+    def health_check(self) -> dict:
+        """Probe the phone-number endpoint; distinguishes an expired token
+        (401/403 — needs re-login) from transient network failure."""
+        if not self.token or not self.phone_id:
+            return {"error": "not configured", "auth_expired": False}
+        try:
+            resp = requests.get(
+                f"{WHATSAPP_API_URL}/{self.phone_id}",
+                headers=self._headers(), timeout=10,
+            )
+            if resp.status_code == 200:
+                return {"status": "ok"}
+            return {"error": f"WhatsApp API returned {resp.status_code}",
+                    "auth_expired": resp.status_code in (401, 403)}
+        except Exception as e:
+            return {"error": str(e), "auth_expired": False}
+
+    def re_login(self) -> dict:
+        """Exchange the current token for a fresh long-lived one using the
+        stored app credentials."""
+        if not WHATSAPP_APP_ID or not WHATSAPP_APP_SECRET:
+            return {"error": "no stored credentials (WHATSAPP_APP_ID/WHATSAPP_APP_SECRET)"}
+        try:
+            resp = requests.get(
+                f"{WHATSAPP_API_URL}/oauth/access_token",
+                params={
+                    "grant_type": "fb_exchange_token",
+                    "client_id": WHATSAPP_APP_ID,
+                    "client_secret": WHATSAPP_APP_SECRET,
+                    "fb_exchange_token": self.token,
+                },
+                timeout=15,
+            )
+            if resp.status_code != 200:
+                return {"error": f"token exchange returned {resp.status_code}"}
+            self.token = resp.json().get("access_token", self.token)
+            log.info("[WHATSAPP] Re-login succeeded — token refreshed")
+            return {"status": "ok"}
+        except Exception as e:
+            return {"error": str(e)}
+
+
+class GatewayManager:
+    """
+    Registry of active gateways plus per-agent privacy settings for the
+    optional operations (read receipts / typing indicators), persisted
+    in SQLite.
+    """
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self.gateways = {"whatsapp": WhatsAppGateway()}
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS gateway_agent_settings (
+                    agent_id TEXT NOT NULL,
+                    gateway TEXT NOT NULL,
+                    read_receipts INTEGER NOT NULL DEFAULT 0,
+                    typing_indicators INTEGER NOT NULL DEFAULT 0,
+                    updated_at TEXT NOT NULL,
+                    PRIMARY KEY (agent_id, gateway)
+                )
+            """)
+            # Recent inbound messages by platform ID, so a reply_to on a
+            # later message resolves to the quoted content (and, once
+            # linked, the turn that answered it)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS inbound_messages (
+                    gateway TEXT NOT NULL,
+                    message_id TEXT NOT NULL,
+                    sender TEXT,
+                    text TEXT,
+                    turn_id TEXT,
+                    received_at TEXT NOT NULL,
+                    PRIMARY KEY (gateway, message_id)
+                )
+            """)
+            # Ordered failover routes per user: primary channel first,
+            # fallbacks after (WhatsApp → Telegram → email)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS user_channel_routes (
+                    user_ref TEXT PRIMARY KEY,
+                    channels TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                )
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    def register(self, gateway: Gateway):
+        """Register/replace a gateway implementation by name."""
+        self.gateways[gateway.name] = gateway
+
+    def get(self, name: str) -> Gateway:
+        return self.gateways.get(name)
+
+    def set_agent_settings(self, agent_id: str, gateway: str,
+                           read_receipts: bool, typing_indicators: bool) -> dict:
+        """Persist an agent's opt-in for the optional gateway operations."""
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT OR REPLACE INTO gateway_agent_settings
+                   (agent_id, gateway, read_receipts, typing_indicators, updated_at)
+                   VALUES (?, ?, ?, ?, ?)""",
+                (agent_id, gateway, int(read_receipts), int(typing_indicators),
+                 datetime.now(timezone.utc).isoformat()),
+            )
+            conn.commit()
+            return self.get_agent_settings(agent_id, gateway)
+        finally:
+            conn.close()
+
+    def get_agent_settings(self, agent_id: str, gateway: str) -> dict:
+        """Agent settings for a gateway; both features default to off."""
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                """SELECT read_receipts, typing_indicators FROM gateway_agent_settings
+                   WHERE agent_id = ? AND gateway = ?""",
+                (agent_id, gateway),
+            ).fetchone()
+            return {
+                "agent_id": agent_id,
+                "gateway": gateway,
+                "read_receipts": bool(row[0]) if row else False,
+                "typing_indicators": bool(row[1]) if row else False,
+            }
+        finally:
+            conn.close()
+
+    def log_inbound(self, gateway: str, message_id: str, sender: str = None,
+                    text: str = None) -> dict:
+        """Remember an inbound message by its platform ID so later replies
+        quoting it can pull the original content into agent context."""
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT OR REPLACE INTO inbound_messages
+                   (gateway, message_id, sender, text, received_at)
+                   VALUES (?, ?, ?, ?, ?)""",
+                (gateway, message_id, sender, (text or "")[:2000],
+                 datetime.now(timezone.utc).isoformat()),
+            )
+            conn.commit()
+            return {"gateway": gateway, "message_id": message_id}
+        finally:
+            conn.close()
+
+    def get_message(self, gateway: str, message_id: str) -> dict:
+        """Look up a logged inbound message; None if unknown/expired."""
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                """SELECT sender, text, turn_id, received_at FROM inbound_messages
+                   WHERE gateway = ? AND message_id = ?""",
+                (gateway, message_id),
+            ).fetchone()
+            if not row:
+                return None
+            return {"gateway": gateway, "message_id": message_id,
+                    "sender": row[0], "text": row[1], "turn_id": row[2],
+                    "received_at": row[3]}
+        finally:
+            conn.close()
+
+    def link_turn(self, gateway: str, message_id: str, turn_id: str) -> bool:
+        """Attach the turn that answered a message, so replies to it carry
+        a reference into the original conversation turn."""
+        conn = self._connect()
+        try:
+            cursor = conn.execute(
+                """UPDATE inbound_messages SET turn_id = ?
+                   WHERE gateway = ? AND message_id = ?""",
+                (turn_id, gateway, message_id),
+            )
+            conn.commit()
+            return cursor.rowcount > 0
+        finally:
+            conn.close()
+
+    def set_channel_routes(self, user_ref: str, channels: list) -> dict:
+        """
+        Persist a user's ordered notification channels. Each entry names
+        a gateway and that user's address on it:
+          [{"gateway": "whatsapp", "recipient": "+4917..."},
+           {"gateway": "telegram", "recipient": "@user"}, ...]
+        Order is the failover order.
+        """
+        for entry in channels:
+            if not isinstance(entry, dict) or not entry.get("gateway") \
+                    or not entry.get("recipient"):
+                return {"error": "Each channel needs 'gateway' and 'recipient'"}
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT OR REPLACE INTO user_channel_routes
+                   (user_ref, channels, updated_at) VALUES (?, ?, ?)""",
+                (user_ref, json.dumps(channels),
+                 datetime.now(timezone.utc).isoformat()),
+            )
+            conn.commit()
+            return {"user_ref": user_ref, "channels": channels}
+        finally:
+            conn.close()
+
+    def get_channel_routes(self, user_ref: str) -> list:
+        """A user's failover-ordered channels; empty when unconfigured."""
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                "SELECT channels FROM user_channel_routes WHERE user_ref = ?",
+                (user_ref,),
+            ).fetchone()
+            return json.loads(row[0]) if row else []
+        finally:
+            conn.close()
+
+    def send_with_failover(self, user_ref: str, text: str,
+                           delivery_tracker=None) -> dict:
+        """
+        Walk the user's channel list in order until one send succeeds.
+        Every attempt (and its error) is recorded; the result names the
+        channel that ultimately delivered. With a delivery_tracker, each
+        attempt gets its own tracked entry.
+        """
+        channels = self.get_channel_routes(user_ref)
+        if not channels:
+            return {"error": f"No channels configured for user: {user_ref}"}
+        attempts = []
+        for entry in channels:
+            gw = self.get(entry["gateway"])
+            if not gw:
+                attempts.append({"gateway": entry["gateway"],
+                                 "error": "gateway not registered"})
+                continue
+            tracked = None
+            if delivery_tracker is not None:
+                tracked = delivery_tracker.track(
+                    entry["gateway"], entry["recipient"],
+                    {"user_ref": user_ref, "text": text[:500]})
+                delivery_tracker.mark(tracked["delivery_id"], "sending")
+            try:
+                result = gw.send_message(entry["recipient"], text)
+            except Exception as e:
+                result = {"error": str(e)}
+            if "error" in result:
+                attempts.append({"gateway": entry["gateway"],
+                                 "error": result["error"]})
+                if tracked:
+                    delivery_tracker.mark(tracked["delivery_id"], "failed",
+                                          error=result["error"])
+                log.warning(f"[FAILOVER] {user_ref} via {entry['gateway']} "
+                            f"failed: {result['error']}")
+                continue
+            if tracked:
+                delivery_tracker.mark(tracked["delivery_id"], "delivered")
+            attempts.append({"gateway": entry["gateway"], "status": "delivered"})
+            if len(attempts) > 1:
+                log.info(f"[FAILOVER] {user_ref} delivered via fallback "
+                         f"{entry['gateway']} after {len(attempts) - 1} failure(s)")
+            return {"status": "delivered", "delivered_via": entry["gateway"],
+                    "recipient": entry["recipient"], "attempts": attempts,
+                    **({"delivery_id": tracked["delivery_id"]} if tracked else {})}
+        return {"error": "All channels failed", "attempts": attempts}
+
+    def mark_read(self, agent_id: str, gateway: str, message_id: str) -> dict:
+        """Mark a message read, honoring the agent's opt-in setting."""
+        gw = self.get(gateway)
+        if not gw:
+            return {"error": f"Unknown gateway: {gateway}"}
+        if not self.get_agent_settings(agent_id, gateway)["read_receipts"]:
+            return {"skipped": True, "reason": "read receipts disabled for this agent"}
+        return gw.mark_read(message_id)
+
+    def send_typing(self, agent_id: str, gateway: str, message_id: str,
+                    active: bool = True) -> dict:
+        """Show a typing indicator in reply to the inbound message being
+        answered, honoring the agent's opt-in setting."""
+        gw = self.get(gateway)
+        if not gw:
+            return {"error": f"Unknown gateway: {gateway}"}
+        if not self.get_agent_settings(agent_id, gateway)["typing_indicators"]:
+            return {"skipped": True, "reason": "typing indicators disabled for this agent"}
+        return gw.send_typing(message_id, active)
+
+
+class GatewaySupervisor:
+    """
+    Watches gateway health and recovers connections automatically.
+
+    On each pass: healthy gateways reset their counters; an auth-expired
+    gateway gets an automatic re-login with stored credentials; repeated
+    transient failures or a failed re-login escalate once to the
+    notification channel (pairing prompt), after which the supervisor
+    waits for a human instead of hammering the API.
+    """
+
+    TRANSIENT_FAILURES_BEFORE_ESCALATION = 3
+
+    def __init__(self, manager: "GatewayManager", notify=None):
+        self.manager = manager
+        self.notify = notify  # callable(text) → operator channel
+        self.state = {}  # gateway name → {failures, escalated, last_status, ...}
+
+    def _escalate(self, name: str, reason: str):
+        state = self.state[name]
+        if state.get("escalated"):
+            return
+        state["escalated"] = True
+        log.error(f"[SUPERVISOR] Gateway '{name}' needs manual pairing: {reason}")
+        if self.notify:
+            self.notify(f"🔌 Gateway '{name}' is down and automatic recovery "
+                        f"failed ({reason}). Manual re-pairing required.")
+
+    def check_all(self) -> dict:
+        """One supervision pass over every registered gateway."""
+        results = {}
+        for name, gateway in self.manager.gateways.items():
+            state = self.state.setdefault(
+                name, {"failures": 0, "relogins": 0, "escalated": False})
+            health = gateway.health_check()
+            state["last_status"] = health.get("status", "error")
+            state["last_checked_at"] = datetime.now(timezone.utc).isoformat()
+
+            if "error" not in health:
+                state["failures"] = 0
+                state["escalated"] = False
+                results[name] = {"status": "ok"}
+                continue
+
+            state["failures"] += 1
+            if health.get("auth_expired"):
+                log.warning(f"[SUPERVISOR] Gateway '{name}' auth expired — "
+                            f"attempting re-login")
+                relogin = gateway.re_login()
+                if "error" in relogin:
+                    self._escalate(name, f"re-login failed: {relogin['error']}")
+                    results[name] = {"status": "auth_failed", **relogin}
+                else:
+                    state["failures"] = 0
+                    state["relogins"] += 1
+                    results[name] = {"status": "relogged_in"}
+            elif state["failures"] >= self.TRANSIENT_FAILURES_BEFORE_ESCALATION:
+                self._escalate(name, f"{state['failures']} consecutive failures: "
+                                     f"{health['error']}")
+                results[name] = {"status": "escalated", "error": health["error"]}
+            else:
+                results[name] = {"status": "transient_failure",
+                                 "failures": state["failures"],
+                                 "error": health["error"]}
+        return results
+
+    def status(self) -> dict:
+        return {name: dict(state) for name, state in self.state.items()}
+
+
+__all__ = ["Gateway", "WhatsAppGateway", "GatewayManager", "GatewaySupervisor"]
@@ -0,0 +1,193 @@
+#!/usr/bin/env python3
+"""
+Human Handoff for Leviathan Super-Brain
+=======================================
+Escalates a conversation to a configured human operator channel. While a
+session is escalated the agent is paused for it: inbound user messages
+are bridged to the operator, operator replies are bridged back out
+through the user's gateway, and control returns to the agent only on an
+explicit operator command (``/return``).
+
+Escalation happens two ways: an agent calls escalate() itself, or the
+user types a trigger phrase ("human please", "real person", …) which the
+gateway layer detects with wants_human().
+
+Env:
+  HANDOFF_OPERATOR_CHANNEL — Discord channel for operator traffic
+                             (default 'daily-logs')
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import os
+import re
+import uuid
+import logging
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+OPERATOR_CHANNEL = os.environ.get("HANDOFF_OPERATOR_CHANNEL", "daily-logs")
+
+RETURN_COMMAND = "/return"
+
+_TRIGGER_RE = re.compile(
+    r"\b(human please|talk to a (real )?(human|person)|real person|"
+    r"speak (to|with) (a|an) (human|agent|operator)|get me a human)\b",
+    re.IGNORECASE)
+
+log = logging.getLogger("handoff")
+
+
+def wants_human(text: str) -> bool:
+    """True when a user message is asking for a human operator."""
+    return bool(_TRIGGER_RE.search(text or ""))
+
+
+class HandoffManager:
+    """Escalated sessions + the bridged message log, SQLite-backed."""
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS handoff_sessions (
+                    handoff_id TEXT PRIMARY KEY,
+                    session_id TEXT NOT NULL,
+                    user_ref TEXT,
+                    gateway TEXT,
+                    operator_channel TEXT NOT NULL,
+                    escalated_by TEXT,
+                    reason TEXT,
+                    status TEXT NOT NULL DEFAULT 'active',
+                    created_at TEXT NOT NULL,
+                    returned_at TEXT
+                )
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS handoff_messages (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    handoff_id TEXT NOT NULL,
+                    direction TEXT NOT NULL,
+                    text TEXT NOT NULL,
+                    relayed_at TEXT NOT NULL
+                )
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _now() -> str:
+        return datetime.now(timezone.utc).isoformat()
+
+    def active_for(self, session_id: str) -> dict:
+        """The active handoff for a session, or {}. Agents must treat a
+        non-empty result as 'paused for this session'."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            row = conn.execute(
+                "SELECT * FROM handoff_sessions WHERE session_id = ? AND status = 'active'",
+                (session_id,),
+            ).fetchone()
+            return dict(row) if row else {}
+        finally:
+            conn.close()
+
+    def is_escalated(self, session_id: str) -> bool:
+        return bool(self.active_for(session_id))
+
+    def escalate(self, session_id: str, user_ref: str = None, gateway: str = None,
+                 escalated_by: str = None, reason: str = None) -> dict:
+        """Escalate a session to the operator channel. Idempotent — an
+        already-active handoff is returned as-is."""
+        existing = self.active_for(session_id)
+        if existing:
+            return existing
+        handoff_id = f"handoff-{uuid.uuid4().hex[:12]}"
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT INTO handoff_sessions
+                   (handoff_id, session_id, user_ref, gateway, operator_channel,
+                    escalated_by, reason, status, created_at)
+                   VALUES (?, ?, ?, ?, ?, ?, ?, 'active', ?)""",
+                (handoff_id, session_id, user_ref, gateway, OPERATOR_CHANNEL,
+                 escalated_by, reason, self._now()),
+            )
+            conn.commit()
+            log.info(f"[HANDOFF] Session {session_id} escalated "
+                     f"({escalated_by or 'user'}: {reason or 'no reason'})")
+            return self.active_for(session_id)
+        finally:
+            conn.close()
+
+    def log_message(self, handoff_id: str, direction: str, text: str):
+        """direction: 'to_operator' (user → human) or 'to_user'."""
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT INTO handoff_messages (handoff_id, direction, text, relayed_at)
+                   VALUES (?, ?, ?, ?)""",
+                (handoff_id, direction, text[:2000], self._now()),
+            )
+            conn.commit()
+        finally:
+            conn.close()
+
+    def return_control(self, session_id: str, operator: str = None) -> dict:
+        """End the handoff and resume the agent for this session."""
+        active = self.active_for(session_id)
+        if not active:
+            return {"error": f"No active handoff for session {session_id}"}
+        conn = self._connect()
+        try:
+            conn.execute(
+                "UPDATE handoff_sessions SET status = 'returned', returned_at = ? "
+                "WHERE handoff_id = ?",
+                (self._now(), active["handoff_id"]),
+            )
+            conn.commit()
+            log.info(f"[HANDOFF] Session {session_id} returned to agent "
+                     f"by {operator or 'operator'}")
+            return {**active, "status": "returned"}
+        finally:
+            conn.close()
+
+    def list_active(self) -> list:
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            return [dict(r) for r in conn.execute(
+                "SELECT * FROM handoff_sessions WHERE status = 'active' ORDER BY created_at"
+            ).fetchall()]
+        finally:
+            conn.close()
+
+    def transcript(self, handoff_id: str) -> list:
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            return [dict(r) for r in conn.execute(
+                "SELECT * FROM handoff_messages WHERE handoff_id = ? ORDER BY id",
+                (handoff_id,),
+            ).fetchall()]
+        finally:
+            conn.close()
+
+
+__all__ = ["HandoffManager", "wants_human", "RETURN_COMMAND"]
@@ -0,0 +1,108 @@
+#!/usr/bin/env python3
+"""
+Idempotency Keys for Leviathan Super-Brain
+==========================================
+Persisted request dedup for the side-effecting APIs (outbound sends,
+inbound triggers): an upstream system retrying an HTTP call with the
+same Idempotency-Key gets the original response back instead of causing
+a duplicate agent turn and duplicate spend. Keys live in SQLite for
+IDEMPOTENCY_WINDOW_MINUTES (default 60) and are pruned lazily on write.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import logging
+from datetime import datetime, timedelta, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+
+IDEMPOTENCY_WINDOW_MINUTES = int(os.environ.get("IDEMPOTENCY_WINDOW_MINUTES", "60"))
+
+log = logging.getLogger("idempotency")
+
+
+class IdempotencyStore:
+    """First response per (endpoint, key) within the dedup window."""
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS idempotency_keys (
+                    endpoint TEXT NOT NULL,
+                    key TEXT NOT NULL,
+                    response TEXT NOT NULL,
+                    status_code INTEGER NOT NULL DEFAULT 200,
+                    created_at TEXT NOT NULL,
+                    PRIMARY KEY (endpoint, key)
+                )
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _cutoff() -> str:
+        return (datetime.now(timezone.utc)
+                - timedelta(minutes=IDEMPOTENCY_WINDOW_MINUTES)).isoformat()
+
+    def lookup(self, endpoint: str, key: str):
+        """The stored (response_dict, status_code) for a key seen within
+        the window, or None on first sight."""
+        if not key:
+            return None
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                """SELECT response, status_code FROM idempotency_keys
+                   WHERE endpoint = ? AND key = ? AND created_at >= ?""",
+                (endpoint, key, self._cutoff()),
+            ).fetchone()
+        finally:
+            conn.close()
+        if not row:
+            return None
+        log.info(f"[IDEMPOTENCY] Replay for {endpoint} key={key[:32]}")
+        return json.loads(row[0]), row[1]
+
+    def store(self, endpoint: str, key: str, response: dict,
+              status_code: int = 200):
+        """Remember the first response for a key; expired keys get pruned
+        on the same write."""
+        if not key:
+            return
+        conn = self._connect()
+        try:
+            conn.execute(
+                "DELETE FROM idempotency_keys WHERE created_at < ?",
+                (self._cutoff(),),
+            )
+            conn.execute(
+                """INSERT OR REPLACE INTO idempotency_keys
+                   (endpoint, key, response, status_code, created_at)
+                   VALUES (?, ?, ?, ?, ?)""",
+                (endpoint, key, json.dumps(response), status_code,
+                 datetime.now(timezone.utc).isoformat()),
+            )
+            conn.commit()
+        finally:
+            conn.close()
+
+
+__all__ = ["IdempotencyStore"]
@@ -0,0 +1,228 @@
+#!/usr/bin/env python3
+"""
+Delivery Latency SLOs for Leviathan Super-Brain
+===============================================
+End-to-end latency per message — inbound gateway receipt to outbound
+delivery — with per-gateway and per-agent percentile reporting, SLO
+definitions from config, and burn-rate alerts on the event bus. This is
+what lets us promise response times instead of guessing at them.
+
+SLOs come from LATENCY_SLO_JSON, per gateway:
+
+    {"whatsapp": {"target_ms": 5000, "objective_pct": 99.0}}
+
+Burn rate is the observed breach rate divided by the error budget
+(100 - objective). 1.0 means burning the budget exactly; alerts fire
+above LATENCY_BURN_ALERT (default 2.0).
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import time
+import logging
+import threading
+from datetime import datetime, timedelta, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+
+DEFAULT_SLO = {"target_ms": 10000, "objective_pct": 95.0}
+
+try:
+    SLO_CONFIG = json.loads(os.environ.get("LATENCY_SLO_JSON", "{}"))
+except json.JSONDecodeError:
+    SLO_CONFIG = {}
+
+# Burn rate at which an alert event fires, and how long before the same
+# gateway may alert again
+LATENCY_BURN_ALERT = float(os.environ.get("LATENCY_BURN_ALERT", "2.0"))
+BURN_ALERT_COOLDOWN_SECONDS = int(os.environ.get("BURN_ALERT_COOLDOWN_SECONDS", "600"))
+
+log = logging.getLogger("latency_slo")
+
+
+def _percentile(sorted_values: list, pct: float) -> float:
+    """Nearest-rank percentile over a pre-sorted list."""
+    if not sorted_values:
+        return None
+    rank = max(0, min(len(sorted_values) - 1,
+                      int(round(pct / 100.0 * len(sorted_values))) - 1))
+    return sorted_values[rank]
+
+
+class LatencySLOTracker:
+    """Per-message latency samples + SLO evaluation and burn alerts."""
+
+    def __init__(self, db_path: str = DB_PATH, event_bus=None):
+        self.db_path = db_path
+        self.event_bus = event_bus
+        self._open = {}  # message_ref → (gateway, started_monotonic)
+        self._open_lock = threading.Lock()
+        self._last_alert = {}  # gateway → monotonic time of last burn alert
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS message_latency (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    gateway TEXT NOT NULL,
+                    agent_id TEXT,
+                    message_ref TEXT,
+                    latency_ms REAL NOT NULL,
+                    recorded_at TEXT NOT NULL
+                )
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_latency_gateway_time
+                ON message_latency(gateway, recorded_at)
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    # ── Sample collection ────────────────────────
+
+    def start(self, message_ref: str, gateway: str):
+        """Mark inbound receipt; the clock runs until finish()."""
+        with self._open_lock:
+            self._open[message_ref] = (gateway, time.monotonic())
+
+    def finish(self, message_ref: str, agent_id: str = None) -> dict:
+        """Mark outbound delivery and persist the end-to-end sample."""
+        with self._open_lock:
+            entry = self._open.pop(message_ref, None)
+        if entry is None:
+            return {"error": f"No open measurement for: {message_ref}"}
+        gateway, started = entry
+        latency_ms = round((time.monotonic() - started) * 1000, 1)
+        self.observe(gateway, latency_ms, agent_id=agent_id,
+                     message_ref=message_ref)
+        return {"message_ref": message_ref, "gateway": gateway,
+                "latency_ms": latency_ms}
+
+    def observe(self, gateway: str, latency_ms: float, agent_id: str = None,
+                message_ref: str = None):
+        """Record one latency sample directly (gateways that already
+        measured their own end-to-end time)."""
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT INTO message_latency
+                   (gateway, agent_id, message_ref, latency_ms, recorded_at)
+                   VALUES (?, ?, ?, ?, ?)""",
+                (gateway, agent_id, message_ref, float(latency_ms),
+                 datetime.now(timezone.utc).isoformat()),
+            )
+            conn.commit()
+        finally:
+            conn.close()
+
+    # ── Reporting ────────────────────────────────
+
+    @staticmethod
+    def slo_for(gateway: str) -> dict:
+        return {**DEFAULT_SLO, **SLO_CONFIG.get(gateway, {})}
+
+    def _samples(self, window_hours: float, group_by: str) -> dict:
+        since = (datetime.now(timezone.utc)
+                 - timedelta(hours=window_hours)).isoformat()
+        conn = self._connect()
+        try:
+            rows = conn.execute(
+                f"SELECT {group_by}, latency_ms FROM message_latency "
+                f"WHERE recorded_at >= ? AND {group_by} IS NOT NULL",
+                (since,),
+            ).fetchall()
+        finally:
+            conn.close()
+        grouped = {}
+        for key, latency in rows:
+            grouped.setdefault(key, []).append(latency)
+        return grouped
+
+    def report(self, window_hours: float = 24) -> dict:
+        """Latency percentiles per gateway (with SLO compliance) and per
+        agent for the window."""
+        gateways = {}
+        for gateway, samples in self._samples(window_hours, "gateway").items():
+            samples.sort()
+            slo = self.slo_for(gateway)
+            breaches = sum(1 for s in samples if s > slo["target_ms"])
+            gateways[gateway] = {
+                "count": len(samples),
+                "p50_ms": _percentile(samples, 50),
+                "p95_ms": _percentile(samples, 95),
+                "p99_ms": _percentile(samples, 99),
+                "slo": slo,
+                "within_slo_pct": round(100.0 * (len(samples) - breaches)
+                                        / len(samples), 2),
+            }
+        agents = {}
+        for agent_id, samples in self._samples(window_hours, "agent_id").items():
+            samples.sort()
+            agents[agent_id] = {
+                "count": len(samples),
+                "p50_ms": _percentile(samples, 50),
+                "p95_ms": _percentile(samples, 95),
+                "p99_ms": _percentile(samples, 99),
+            }
+        return {"window_hours": window_hours, "gateways": gateways,
+                "agents": agents}
+
+    def burn_rates(self, window_hours: float = 1) -> dict:
+        """Error-budget burn per gateway: breach rate over the window
+        divided by the SLO's error budget."""
+        burns = {}
+        for gateway, samples in self._samples(window_hours, "gateway").items():
+            slo = self.slo_for(gateway)
+            budget = max(0.01, 100.0 - slo["objective_pct"])
+            breach_pct = 100.0 * sum(
+                1 for s in samples if s > slo["target_ms"]) / len(samples)
+            burns[gateway] = {
+                "samples": len(samples),
+                "breach_pct": round(breach_pct, 2),
+                "error_budget_pct": budget,
+                "burn_rate": round(breach_pct / budget, 2),
+                "slo": slo,
+            }
+        return burns
+
+    def check_burn(self, window_hours: float = 1) -> list:
+        """Evaluate burn rates and publish slo.burn events for gateways
+        burning faster than LATENCY_BURN_ALERT (cooldown-limited)."""
+        alerts = []
+        now = time.monotonic()
+        for gateway, burn in self.burn_rates(window_hours).items():
+            if burn["burn_rate"] < LATENCY_BURN_ALERT:
+                continue
+            last = self._last_alert.get(gateway, 0)
+            if now - last < BURN_ALERT_COOLDOWN_SECONDS:
+                continue
+            self._last_alert[gateway] = now
+            alert = {"gateway": gateway, "window_hours": window_hours, **burn}
+            alerts.append(alert)
+            log.warning(f"[SLO] {gateway} burning error budget at "
+                        f"{burn['burn_rate']}x ({burn['breach_pct']}% over "
+                        f"{burn['slo']['target_ms']}ms)")
+            if self.event_bus is not None:
+                try:
+                    self.event_bus.publish("slo.burn", alert)
+                except Exception as e:
+                    log.error(f"[SLO] Burn alert publish failed: {e}")
+        return alerts
+
+
+__all__ = ["LatencySLOTracker"]
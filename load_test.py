@@ -0,0 +1,197 @@
+#!/usr/bin/env python3
+"""
+Synthetic Load Testing for Leviathan Super-Brain
+================================================
+Config-defined synthetic agents driven by a mock provider, generating
+message volume against the real scheduler queues, budget accounting and
+usage stores — with gateways stubbed out — and reporting throughput and
+latency. Run this before onboarding a tenant to see where the current
+box tops out, instead of finding out in production.
+
+A run spec looks like:
+
+    {"agents": [{"agent_id": "load-a", "model": "deepseek-chat"}],
+     "messages": 500, "rate_per_second": 50,
+     "output_tokens": 200}
+
+Synthetic agents register with labels {"synthetic": true} so the normal
+fleet views and bulk operations can find (and clean up) them.
+
+Author: Leviathan DevOps
+"""
+
+import os
+import time
+import logging
+import threading
+import secrets
+from datetime import datetime, timezone
+
+from gateways import Gateway
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+LOAD_TEST_MAX_MESSAGES = int(os.environ.get("LOAD_TEST_MAX_MESSAGES", "100000"))
+LOAD_TEST_MOCK_LATENCY_MS = float(os.environ.get("LOAD_TEST_MOCK_LATENCY_MS", "0"))
+
+log = logging.getLogger("load_test")
+
+
+class LoadTestGateway(Gateway):
+    """Stub gateway: accepts every send instantly, counts them, talks to
+    nobody. Registered under 'loadtest' so outbound paths can be exercised
+    without a real platform behind them."""
+
+    name = "loadtest"
+
+    def __init__(self):
+        self.sent = 0
+
+    def send_message(self, recipient: str, text: str, reply_to: str = None) -> dict:
+        self.sent += 1
+        return {"status": "ok", "stub": True}
+
+    def health_check(self) -> dict:
+        return {"status": "ok"}
+
+
+class MockProvider:
+    """Deterministic stand-in for an LLM provider: fixed token counts,
+    optional simulated latency, zero network."""
+
+    def __init__(self, latency_ms: float = LOAD_TEST_MOCK_LATENCY_MS):
+        self.latency_ms = latency_ms
+
+    def complete(self, prompt: str, output_tokens: int = 200) -> dict:
+        if self.latency_ms > 0:
+            time.sleep(self.latency_ms / 1000.0)
+        return {
+            "reply": "ok " * max(1, output_tokens // 3),
+            "input_tokens": max(1, len(prompt) // 4),
+            "output_tokens": output_tokens,
+        }
+
+
+class LoadTestHarness:
+    """
+    Drives synthetic volume through the real pipeline: classify/enqueue →
+    dequeue → mock provider → usage record. One run at a time; status()
+    reports progress while running and the full report afterwards.
+    """
+
+    def __init__(self, registry, usage_store, enqueue, dequeue,
+                 provider: MockProvider = None):
+        self.registry = registry
+        self.usage_store = usage_store
+        self.enqueue = enqueue  # callable(message: dict, priority: str) -> bool
+        self.dequeue = dequeue  # callable() -> dict | None
+        self.provider = provider or MockProvider()
+        self._lock = threading.Lock()
+        self._running = False
+        self._report = None
+
+    def start(self, spec: dict) -> dict:
+        """Kick off a run in a background thread. Refuses to overlap runs
+        — two load tests at once measure each other, not the kernel."""
+        agents = spec.get("agents") or []
+        if not agents:
+            return {"error": "Spec needs at least one entry in 'agents'"}
+        messages = int(spec.get("messages", 100))
+        if not 0 < messages <= LOAD_TEST_MAX_MESSAGES:
+            return {"error": f"'messages' must be 1..{LOAD_TEST_MAX_MESSAGES}"}
+
+        with self._lock:
+            if self._running:
+                return {"error": "A load test is already running"}
+            self._running = True
+
+        run_id = f"load-{secrets.token_hex(4)}"
+        for agent in agents:
+            agent_id = agent.get("agent_id") or f"{run_id}-agent"
+            self.registry.register(agent_id, f"Synthetic {agent_id}", {
+                "model": agent.get("model", "deepseek-chat"),
+                "labels": {"synthetic": True, "loadtest_run": run_id},
+            })
+
+        worker = threading.Thread(
+            target=self._run, args=(run_id, spec), daemon=True,
+            name=f"LoadTest-{run_id}")
+        worker.start()
+        log.info(f"[LOADTEST] {run_id} started: {messages} messages across "
+                 f"{len(agents)} synthetic agents")
+        return {"run_id": run_id, "messages": messages,
+                "agents": len(agents), "started": True}
+
+    def _run(self, run_id: str, spec: dict):
+        agents = spec["agents"]
+        messages = int(spec.get("messages", 100))
+        rate = float(spec.get("rate_per_second", 0))  # 0 = as fast as possible
+        output_tokens = int(spec.get("output_tokens", 200))
+        interval = 1.0 / rate if rate > 0 else 0.0
+
+        latencies = []
+        sent = done = dropped = 0
+        started = time.monotonic()
+        started_at = datetime.now(timezone.utc).isoformat()
+        try:
+            for i in range(messages):
+                agent = agents[i % len(agents)]
+                agent_id = agent.get("agent_id") or f"{run_id}-agent"
+                text = f"[{run_id}] synthetic message {i}"
+                t0 = time.monotonic()
+                if not self.enqueue({"text": text, "sender": run_id,
+                                     "gateway": "loadtest"}, "normal"):
+                    dropped += 1
+                    continue
+                sent += 1
+                item = self.dequeue()
+                if item is None:
+                    continue
+                completion = self.provider.complete(
+                    item["message"]["text"], output_tokens=output_tokens)
+                self.usage_store.record(
+                    agent_id=agent_id,
+                    model=agent.get("model", "deepseek-chat"),
+                    input_tokens=completion["input_tokens"],
+                    output_tokens=completion["output_tokens"],
+                    purpose="loadtest",
+                    session_id=run_id,
+                )
+                latencies.append((time.monotonic() - t0) * 1000)
+                done += 1
+                if interval > 0:
+                    time.sleep(interval)
+        except Exception as e:
+            log.error(f"[LOADTEST] {run_id} aborted: {e}")
+        elapsed = time.monotonic() - started
+
+        latencies.sort()
+        pick = lambda pct: (round(latencies[min(len(latencies) - 1,
+                            int(pct / 100 * len(latencies)))], 2)
+                            if latencies else None)
+        report = {
+            "run_id": run_id,
+            "started_at": started_at,
+            "finished_at": datetime.now(timezone.utc).isoformat(),
+            "elapsed_seconds": round(elapsed, 2),
+            "requested": int(spec.get("messages", 100)),
+            "enqueued": sent,
+            "completed": done,
+            "dropped": dropped,
+            "throughput_per_second": round(done / elapsed, 2) if elapsed else None,
+            "latency_ms": {"p50": pick(50), "p95": pick(95), "p99": pick(99)},
+            "spend": self.usage_store.query_by_session(run_id)["cost_usd"],
+        }
+        with self._lock:
+            self._report = report
+            self._running = False
+        log.info(f"[LOADTEST] {run_id} finished: {done}/{sent} at "
+                 f"{report['throughput_per_second']}/s")
+
+    def status(self) -> dict:
+        with self._lock:
+            return {"running": self._running, "last_report": self._report}
+
+
+__all__ = ["LoadTestHarness", "LoadTestGateway", "MockProvider"]
@@ -0,0 +1,192 @@
+#!/usr/bin/env python3
+"""
+Runtime Log Control for Leviathan Super-Brain
+=============================================
+Change what gets logged without a restart: the root level, per-module
+directives (every subsystem logs under its own named logger — "quotas",
+"usage_store", "tool_registry", ...), and per-agent debug so ONE
+misbehaving agent can be put under the microscope in production without
+drowning in debug output from the whole fleet.
+
+Module directives are applied straight onto the stdlib loggers, so they
+take effect on the next log call. A directive set with persist=True
+lands in the log_directives table and is re-applied at boot; transient
+ones die with the process. Per-agent debug always expires (TTL) — a
+debugging session forgotten on a Friday must not still be spamming logs
+on Monday.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import os
+import time
+import logging
+import threading
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+
+# How long per-agent debug stays on before expiring on its own
+LOG_AGENT_DEBUG_TTL_MINUTES = int(
+    os.environ.get("LOG_AGENT_DEBUG_TTL_MINUTES", "60"))
+
+log = logging.getLogger("log_control")
+
+VALID_LEVELS = ("DEBUG", "INFO", "WARNING", "ERROR", "CRITICAL")
+
+
+class LogControl:
+    """Live level directives over the stdlib logging tree, plus
+    TTL-bounded per-agent debug flags."""
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self._debug_agents = {}  # agent_id → monotonic expiry
+        self._lock = threading.Lock()
+        self.ensure_schema()
+        self.apply_persisted()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS log_directives (
+                    logger_name TEXT PRIMARY KEY,
+                    level TEXT NOT NULL,
+                    updated_by TEXT,
+                    updated_at TEXT NOT NULL
+                )
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    def apply_persisted(self):
+        """Re-apply persisted directives — called once at boot so a
+        restart keeps the deployment's tuned levels."""
+        conn = self._connect()
+        try:
+            rows = conn.execute(
+                "SELECT logger_name, level FROM log_directives").fetchall()
+        finally:
+            conn.close()
+        for name, level in rows:
+            logging.getLogger(name or None).setLevel(level)
+        if rows:
+            log.info(f"[LOGCTL] Re-applied {len(rows)} persisted "
+                     f"log directives")
+
+    def set_level(self, logger_name: str, level: str,
+                  persist: bool = False, updated_by: str = "api") -> dict:
+        """
+        Set a logger's level, live. Empty logger_name targets the root
+        logger (the global floor). With persist=True the directive
+        survives restarts; without, it lasts until the process exits.
+        """
+        level = (level or "").upper()
+        if level not in VALID_LEVELS:
+            return {"error": f"Unknown level: {level} "
+                             f"(one of {', '.join(VALID_LEVELS)})"}
+        logging.getLogger(logger_name or None).setLevel(level)
+        if persist:
+            conn = self._connect()
+            try:
+                conn.execute(
+                    """INSERT OR REPLACE INTO log_directives
+                       (logger_name, level, updated_by, updated_at)
+                       VALUES (?, ?, ?, ?)""",
+                    (logger_name, level, updated_by,
+                     datetime.now(timezone.utc).isoformat()),
+                )
+                conn.commit()
+            finally:
+                conn.close()
+        log.info(f"[LOGCTL] {logger_name or '<root>'} → {level}"
+                 f"{' (persisted)' if persist else ''} by {updated_by}")
+        return {"logger": logger_name or "<root>", "level": level,
+                "persisted": persist}
+
+    def clear_level(self, logger_name: str) -> dict:
+        """Drop a directive: the logger goes back to inheriting from its
+        parent, and any persisted row is removed."""
+        logging.getLogger(logger_name or None).setLevel(logging.NOTSET)
+        conn = self._connect()
+        try:
+            removed = conn.execute(
+                "DELETE FROM log_directives WHERE logger_name = ?",
+                (logger_name,)).rowcount
+            conn.commit()
+        finally:
+            conn.close()
+        return {"logger": logger_name or "<root>", "cleared": True,
+                "was_persisted": removed > 0}
+
+    # ── Per-agent debug ──
+
+    def enable_agent_debug(self, agent_id: str,
+                           ttl_minutes: int = LOG_AGENT_DEBUG_TTL_MINUTES) -> dict:
+        """Put one agent under debug for ttl_minutes. Hot paths check
+        agent_debug() and emit their verbose detail only for flagged
+        agents — the rest of the fleet stays at normal levels."""
+        with self._lock:
+            self._debug_agents[agent_id] = time.monotonic() + ttl_minutes * 60
+        log.info(f"[LOGCTL] Debug ON for {agent_id} ({ttl_minutes}min)")
+        return {"agent_id": agent_id, "debug": True,
+                "expires_in_minutes": ttl_minutes}
+
+    def disable_agent_debug(self, agent_id: str) -> dict:
+        with self._lock:
+            was_on = self._debug_agents.pop(agent_id, None) is not None
+        if was_on:
+            log.info(f"[LOGCTL] Debug OFF for {agent_id}")
+        return {"agent_id": agent_id, "debug": False, "was_on": was_on}
+
+    def agent_debug(self, agent_id: str) -> bool:
+        """Is this agent currently under debug? Expired flags are
+        dropped on the way through."""
+        now = time.monotonic()
+        with self._lock:
+            expiry = self._debug_agents.get(agent_id)
+            if expiry is None:
+                return False
+            if expiry <= now:
+                del self._debug_agents[agent_id]
+                return False
+            return True
+
+    def status(self) -> dict:
+        """Effective levels of every explicitly-set logger, the persisted
+        directives, and which agents are under debug."""
+        conn = self._connect()
+        try:
+            persisted = {name: level for name, level in conn.execute(
+                "SELECT logger_name, level FROM log_directives").fetchall()}
+        finally:
+            conn.close()
+        levels = {"<root>": logging.getLevelName(
+            logging.getLogger().getEffectiveLevel())}
+        for name in sorted(logging.root.manager.loggerDict):
+            logger = logging.getLogger(name)
+            if logger.level != logging.NOTSET:
+                levels[name] = logging.getLevelName(logger.level)
+        now = time.monotonic()
+        with self._lock:
+            debug_agents = {
+                agent_id: int((expiry - now) / 60)
+                for agent_id, expiry in self._debug_agents.items()
+                if expiry > now}
+        return {"levels": levels, "persisted": persisted,
+                "debug_agents": debug_agents}
+
+
+__all__ = ["LogControl", "VALID_LEVELS"]
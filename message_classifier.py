@@ -0,0 +1,198 @@
+#!/usr/bin/env python3
+"""
+Inbound Message Classifier for Leviathan Super-Brain
+====================================================
+Lightweight classification stage for the gateway layer: every inbound
+message gets a priority (urgent/normal/low) and a kind
+(question/command/smalltalk) before it reaches the scheduler, so urgent
+messages jump ahead of digests in the priority queues.
+
+Rules run first and are free; when they are inconclusive and a cheap
+model is configured (DEEPSEEK_API_KEY), a single short completion breaks
+the tie. Model failures fall back to the rule verdict — classification
+must never block delivery.
+
+Author: Leviathan DevOps
+"""
+
+import os
+import re
+import json
+import time
+import queue
+import logging
+import threading
+
+import requests
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DEEPSEEK_API_KEY = os.environ.get("DEEPSEEK_API_KEY", "")
+CLASSIFIER_MODEL = os.environ.get("MESSAGE_CLASSIFIER_MODEL", "deepseek-chat")
+CLASSIFIER_TIMEOUT_SECONDS = int(os.environ.get("MESSAGE_CLASSIFIER_TIMEOUT_SECONDS", "5"))
+PRIORITY_QUEUE_MAX = int(os.environ.get("PRIORITY_QUEUE_MAX", "5000"))
+
+PRIORITIES = ("urgent", "normal", "low")
+KINDS = ("question", "command", "smalltalk")
+
+log = logging.getLogger("message_classifier")
+
+# Rule tables — keep these cheap and explainable; the model only breaks ties.
+_URGENT_RE = re.compile(
+    r"\b(urgent|asap|emergency|immediately|right now|production (is )?down|"
+    r"outage|on fire|critical|help!)\b", re.IGNORECASE)
+_LOW_RE = re.compile(
+    r"\b(fyi|no rush|whenever|some ?time|newsletter|digest|weekly summary)\b",
+    re.IGNORECASE)
+_COMMAND_RE = re.compile(
+    r"^\s*(/\w+|please\s+\w+|(can|could) you\s+\w+|run|deploy|restart|send|"
+    r"create|delete|update|schedule|cancel|remind)\b", re.IGNORECASE)
+_SMALLTALK_RE = re.compile(
+    r"^\s*(hi|hey|hello|yo|good (morning|evening|night)|thanks?!?|thank you|"
+    r"lol|haha|ok|cool|nice)\W*$", re.IGNORECASE)
+
+
+def classify_by_rules(text: str) -> dict:
+    """Rule-only classification. `confident` is False when the rules had
+    nothing to go on and a model tiebreak would help."""
+    text = (text or "").strip()
+    priority, kind, confident = "normal", "question", False
+
+    if _URGENT_RE.search(text):
+        priority, confident = "urgent", True
+    elif _LOW_RE.search(text):
+        priority, confident = "low", True
+
+    if _SMALLTALK_RE.match(text):
+        kind = "smalltalk"
+        confident = True
+        if priority == "normal":
+            priority = "low"
+    elif _COMMAND_RE.match(text):
+        kind = "command"
+        confident = True
+    elif text.endswith("?"):
+        kind = "question"
+        confident = True
+
+    return {"priority": priority, "kind": kind, "confident": confident,
+            "method": "rules"}
+
+
+def classify_by_model(text: str) -> dict:
+    """One short cheap-model completion. Returns {} on any failure."""
+    if not DEEPSEEK_API_KEY:
+        return {}
+    try:
+        resp = requests.post(
+            "https://api.deepseek.com/v1/chat/completions",
+            headers={"Authorization": f"Bearer {DEEPSEEK_API_KEY}"},
+            json={
+                "model": CLASSIFIER_MODEL,
+                "max_tokens": 30,
+                "messages": [{
+                    "role": "user",
+                    "content": "Classify this chat message. Reply with JSON only: "
+                               '{"priority": "urgent|normal|low", '
+                               '"kind": "question|command|smalltalk"}\n\n'
+                               f"Message: {text[:500]}",
+                }],
+            },
+            timeout=CLASSIFIER_TIMEOUT_SECONDS,
+        )
+        resp.raise_for_status()
+        content = resp.json()["choices"][0]["message"]["content"]
+        parsed = json.loads(re.search(r"\{.*\}", content, re.DOTALL).group(0))
+        if parsed.get("priority") in PRIORITIES and parsed.get("kind") in KINDS:
+            return {"priority": parsed["priority"], "kind": parsed["kind"],
+                    "confident": True, "method": "model"}
+    except Exception as e:
+        log.warning(f"[CLASSIFY] Model tiebreak failed: {e}")
+    return {}
+
+
+def classify(text: str, use_model: bool = True) -> dict:
+    """Rules first; cheap-model tiebreak only when rules are inconclusive."""
+    verdict = classify_by_rules(text)
+    if not verdict["confident"] and use_model:
+        model_verdict = classify_by_model(text)
+        if model_verdict:
+            return model_verdict
+    return verdict
+
+
+class PriorityQueues:
+    """Three bounded inbound queues the scheduler drains urgent-first."""
+
+    def __init__(self):
+        self.queues = {p: queue.Queue(maxsize=PRIORITY_QUEUE_MAX) for p in PRIORITIES}
+        self.lock = threading.Lock()
+        self.enqueued = {p: 0 for p in PRIORITIES}
+        self.dropped = 0
+
+    def enqueue(self, message: dict, priority: str) -> bool:
+        if priority not in PRIORITIES:
+            priority = "normal"
+        try:
+            self.queues[priority].put_nowait((time.time(), message))
+            with self.lock:
+                self.enqueued[priority] += 1
+            return True
+        except queue.Full:
+            with self.lock:
+                self.dropped += 1
+            log.warning(f"[CLASSIFY] '{priority}' queue full — message dropped")
+            return False
+
+    def dequeue_next(self) -> dict:
+        """Highest-priority waiting message, or None."""
+        for priority in PRIORITIES:
+            try:
+                enqueued_ts, message = self.queues[priority].get_nowait()
+                return {"priority": priority, "message": message,
+                        "waited_seconds": round(time.time() - enqueued_ts, 1)}
+            except queue.Empty:
+                continue
+        return None
+
+    def depths(self) -> dict:
+        return {
+            "depths": {p: self.queues[p].qsize() for p in PRIORITIES},
+            "enqueued": dict(self.enqueued),
+            "dropped": self.dropped,
+        }
+
+    def starvation_snapshot(self, threshold_seconds: float) -> dict:
+        """
+        Peek (without popping) the oldest item in each queue and report
+        the ones that have waited past the threshold, with the waiting
+        message's sender/gateway so the blocker can be identified.
+        """
+        starved = []
+        oldest = {}
+        now = time.time()
+        for priority in PRIORITIES:
+            q = self.queues[priority]
+            with q.mutex:
+                head = q.queue[0] if q.queue else None
+            if head is None:
+                oldest[priority] = None
+                continue
+            enqueued_ts, message = head
+            waited = round(now - enqueued_ts, 1)
+            oldest[priority] = waited
+            if waited >= threshold_seconds:
+                starved.append({
+                    "priority": priority,
+                    "waited_seconds": waited,
+                    "sender": message.get("sender"),
+                    "gateway": message.get("gateway"),
+                    "kind": message.get("kind"),
+                })
+        return {"starved": starved, "oldest_wait_seconds": oldest,
+                **self.depths()}
+
+
+__all__ = ["classify", "classify_by_rules", "classify_by_model",
+           "PriorityQueues", "PRIORITIES", "KINDS"]
@@ -0,0 +1,134 @@
+#!/usr/bin/env python3
+"""
+Prometheus Metrics Exporter for Leviathan Super-Brain
+=====================================================
+Renders the kernel's spend counters in the Prometheus text exposition
+format for a /metrics endpoint — no client library dependency, the
+format is just lines. Counters live in memory, primed from the usage
+store at startup and bumped on every recorded LLM call via the store's
+observer hook, so a scrape never touches the database.
+
+Exported series (all labelled by agent):
+  leviathan_llm_calls_total / input_tokens_total / output_tokens_total
+  leviathan_llm_cost_usd_total
+  leviathan_budget_utilization_ratio — today's spend vs the agent's
+  daily quota (cost and tokens, labelled by resource), absent when no
+  quota is set.
+
+Author: Leviathan DevOps
+"""
+
+import threading
+import logging
+from datetime import datetime, timezone
+
+log = logging.getLogger("metrics_exporter")
+
+
+def _escape_label(value: str) -> str:
+    return str(value).replace("\\", "\\\\").replace('"', '\\"').replace("\n", "\\n")
+
+
+class MetricsExporter:
+    """In-memory per-agent counters rendered as Prometheus text format."""
+
+    def __init__(self, usage_store, quota_manager=None):
+        self.usage_store = usage_store
+        self.quota_manager = quota_manager
+        self._lock = threading.Lock()
+        self._totals = {}  # agent_id → {calls, input_tokens, output_tokens, cost_usd}
+        self._daily = {}   # agent_id → {input_tokens, output_tokens, cost_usd}
+        self._daily_day = self._today()
+        self._prime()
+        usage_store.add_observer(self.observe)
+
+    @staticmethod
+    def _today() -> str:
+        return datetime.now(timezone.utc).strftime("%Y-%m-%d")
+
+    def _prime(self):
+        """Seed counters from the store so they stay monotonic across
+        restarts (Prometheus counters must never go backwards)."""
+        for row in self.usage_store.agent_totals():
+            self._totals[row["agent_id"]] = {
+                "calls": row["calls"] or 0,
+                "input_tokens": row["input_tokens"] or 0,
+                "output_tokens": row["output_tokens"] or 0,
+                "cost_usd": row["cost_usd"] or 0.0,
+            }
+        for row in self.usage_store.agent_totals(since=self._daily_day):
+            self._daily[row["agent_id"]] = {
+                "input_tokens": row["input_tokens"] or 0,
+                "output_tokens": row["output_tokens"] or 0,
+                "cost_usd": row["cost_usd"] or 0.0,
+            }
+        log.info(f"[METRICS] Primed counters for {len(self._totals)} agents")
+
+    def observe(self, record: dict):
+        """UsageStore observer — one call per recorded LLM usage row."""
+        agent_id = record.get("agent_id")
+        if not agent_id:
+            return
+        with self._lock:
+            if self._today() != self._daily_day:
+                self._daily = {}
+                self._daily_day = self._today()
+            totals = self._totals.setdefault(agent_id, {
+                "calls": 0, "input_tokens": 0, "output_tokens": 0, "cost_usd": 0.0})
+            daily = self._daily.setdefault(agent_id, {
+                "input_tokens": 0, "output_tokens": 0, "cost_usd": 0.0})
+            totals["calls"] += 1
+            for bucket in (totals, daily):
+                bucket["input_tokens"] += record.get("input_tokens") or 0
+                bucket["output_tokens"] += record.get("output_tokens") or 0
+                bucket["cost_usd"] += record.get("cost_usd") or 0.0
+
+    def _utilization_lines(self) -> list:
+        if self.quota_manager is None:
+            return []
+        lines = [
+            "# HELP leviathan_budget_utilization_ratio Today's spend as a "
+            "fraction of the agent's daily quota.",
+            "# TYPE leviathan_budget_utilization_ratio gauge",
+        ]
+        emitted = False
+        for agent_id, daily in sorted(self._daily.items()):
+            quota = self.quota_manager.get_quota(agent_id)
+            label = _escape_label(agent_id)
+            if quota.max_cost_usd_per_day:
+                ratio = daily["cost_usd"] / quota.max_cost_usd_per_day
+                lines.append(f'leviathan_budget_utilization_ratio'
+                             f'{{agent="{label}",resource="cost_usd"}} {ratio:.6f}')
+                emitted = True
+            if quota.max_tokens_per_day:
+                tokens = daily["input_tokens"] + daily["output_tokens"]
+                ratio = tokens / quota.max_tokens_per_day
+                lines.append(f'leviathan_budget_utilization_ratio'
+                             f'{{agent="{label}",resource="tokens"}} {ratio:.6f}')
+                emitted = True
+        return lines if emitted else []
+
+    def render(self) -> str:
+        """The full scrape payload, newline-terminated."""
+        counters = (
+            ("leviathan_llm_calls_total", "calls", "Recorded LLM calls.", "{v}"),
+            ("leviathan_llm_input_tokens_total", "input_tokens",
+             "Prompt tokens consumed.", "{v}"),
+            ("leviathan_llm_output_tokens_total", "output_tokens",
+             "Completion tokens generated.", "{v}"),
+            ("leviathan_llm_cost_usd_total", "cost_usd",
+             "USD spent at pricing-table rates.", "{v:.6f}"),
+        )
+        with self._lock:
+            lines = []
+            for metric, key, help_text, fmt in counters:
+                lines.append(f"# HELP {metric} {help_text}")
+                lines.append(f"# TYPE {metric} counter")
+                for agent_id, totals in sorted(self._totals.items()):
+                    value = fmt.format(v=totals[key])
+                    lines.append(f'{metric}{{agent="{_escape_label(agent_id)}"}} {value}')
+            lines.extend(self._utilization_lines())
+        return "\n".join(lines) + "\n"
+
+
+__all__ = ["MetricsExporter"]
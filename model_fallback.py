@@ -0,0 +1,286 @@
+#!/usr/bin/env python3
+"""
+Model Fallback Cascade for Leviathan Super-Brain
+================================================
+The old behaviour was "return the first fallback entry and hope". This
+module makes fallback a real cascade: candidates are tried in order,
+recent failures and rate-limits are tracked per provider, providers that
+are currently unhealthy get skipped instead of burning a retry, and the
+usage record says which fallback actually served the call (the
+`fallback_from` column) so cost reports show when the primary was
+degraded.
+
+Health is in-memory by design — it describes the last few minutes, and
+a restart deserves a clean slate.
+
+Author: Leviathan DevOps
+"""
+
+import os
+import json
+import time
+import logging
+import threading
+from collections import deque
+
+from pricing import rates_for
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+# Failures inside this window count against a provider's health
+FALLBACK_HEALTH_WINDOW_SECONDS = int(
+    os.environ.get("FALLBACK_HEALTH_WINDOW_SECONDS", "300"))
+# This many recent failures marks a provider unhealthy
+FALLBACK_FAILURE_THRESHOLD = int(
+    os.environ.get("FALLBACK_FAILURE_THRESHOLD", "3"))
+# How long a 429 sidelines a provider when no Retry-After is given
+FALLBACK_RATE_LIMIT_COOLDOWN = int(
+    os.environ.get("FALLBACK_RATE_LIMIT_COOLDOWN", "60"))
+
+log = logging.getLogger("model_fallback")
+
+# What each model can actually do, matching the pricing table's names.
+# Unknown models get DEFAULT_CAPABILITIES — deliberately minimal, so a
+# request that *requires* a capability never lands on a model we can't
+# vouch for. MODEL_CAPABILITIES_JSON merges deployment overrides, same
+# mechanism as the pricing table.
+MODEL_CAPABILITIES = {
+    'deepseek-chat': {'tools': True, 'vision': False, 'context_window': 128000},
+    'deepseek-reasoner': {'tools': False, 'vision': False, 'context_window': 128000},
+    'claude-opus-4-6': {'tools': True, 'vision': True, 'context_window': 200000},
+    'grok-4-1-fast-reasoning': {'tools': True, 'vision': False, 'context_window': 131072},
+    'gpt-5.3-codex': {'tools': True, 'vision': True, 'context_window': 200000},
+    'google/gemini-2.5-flash-preview-05-20': {'tools': True, 'vision': True,
+                                              'context_window': 1000000},
+    'google/gemini-1.5-pro': {'tools': True, 'vision': True, 'context_window': 2000000},
+    'google/gemma-3-27b-it': {'tools': False, 'vision': False, 'context_window': 128000},
+    'qwen/qwen3-235b-a22b': {'tools': True, 'vision': False, 'context_window': 32768},
+}
+
+DEFAULT_CAPABILITIES = {'tools': False, 'vision': False, 'context_window': 8192}
+
+_raw = os.environ.get("MODEL_CAPABILITIES_JSON")
+if _raw:
+    try:
+        for _model, _caps in json.loads(_raw).items():
+            MODEL_CAPABILITIES[_model] = {
+                **MODEL_CAPABILITIES.get(_model, DEFAULT_CAPABILITIES),
+                **_caps}
+    except json.JSONDecodeError as e:
+        log.warning(f"[FALLBACK] Bad MODEL_CAPABILITIES_JSON: {e}")
+
+
+def capabilities_for(model: str) -> dict:
+    """The capability card for a model (minimal defaults when unknown)."""
+    return MODEL_CAPABILITIES.get(model, DEFAULT_CAPABILITIES)
+
+
+def blended_rate(model: str) -> float:
+    """One comparable price per model: USD per million tokens assuming
+    the typical 3:1 input:output split of agent traffic."""
+    rates = rates_for(model)
+    return (3 * rates['input'] + rates['output']) / 4
+
+
+# Model-name prefixes → provider, matching the pricing table's models.
+_PROVIDER_PREFIXES = (
+    ("deepseek", "deepseek"),
+    ("claude", "anthropic"),
+    ("grok", "xai"),
+    ("gpt", "openai"),
+    ("o1", "openai"),
+    ("google/", "google"),
+    ("gemini", "google"),
+    ("qwen/", "openrouter"),
+)
+
+
+def infer_provider(model: str) -> str:
+    """Best-effort provider from a model name ('unknown' if novel)."""
+    name = (model or "").lower()
+    for prefix, provider in _PROVIDER_PREFIXES:
+        if name.startswith(prefix):
+            return provider
+    return "unknown"
+
+
+class ProviderHealth:
+    """Sliding-window failure and rate-limit tracking per provider."""
+
+    def __init__(self, window: int = FALLBACK_HEALTH_WINDOW_SECONDS,
+                 threshold: int = FALLBACK_FAILURE_THRESHOLD):
+        self.window = window
+        self.threshold = threshold
+        self._failures = {}  # provider → deque of monotonic timestamps
+        self._rate_limited_until = {}  # provider → monotonic deadline
+        self._lock = threading.Lock()
+
+    def _recent(self, provider: str) -> deque:
+        failures = self._failures.setdefault(provider, deque())
+        cutoff = time.monotonic() - self.window
+        while failures and failures[0] < cutoff:
+            failures.popleft()
+        return failures
+
+    def record_failure(self, provider: str):
+        with self._lock:
+            self._recent(provider).append(time.monotonic())
+
+    def record_rate_limit(self, provider: str, retry_after: float = None):
+        cooldown = retry_after or FALLBACK_RATE_LIMIT_COOLDOWN
+        with self._lock:
+            self._rate_limited_until[provider] = time.monotonic() + cooldown
+        log.warning(f"[FALLBACK] {provider} rate-limited, sidelined {cooldown:.0f}s")
+
+    def record_success(self, provider: str):
+        """A success clears the failure streak — flapping providers get
+        credit as soon as they behave."""
+        with self._lock:
+            self._failures.pop(provider, None)
+
+    def is_healthy(self, provider: str) -> bool:
+        with self._lock:
+            if self._rate_limited_until.get(provider, 0) > time.monotonic():
+                return False
+            return len(self._recent(provider)) < self.threshold
+
+    def status(self) -> dict:
+        now = time.monotonic()
+        with self._lock:
+            providers = set(self._failures) | set(self._rate_limited_until)
+            return {p: {
+                "healthy": (self._rate_limited_until.get(p, 0) <= now
+                            and len(self._recent(p)) < self.threshold),
+                "recent_failures": len(self._recent(p)),
+                "rate_limited_for_seconds": max(
+                    0, int(self._rate_limited_until.get(p, 0) - now)),
+            } for p in sorted(providers)}
+
+
+class FallbackCascade:
+    """
+    Try a primary model, then its fallbacks in order, skipping models
+    whose provider is currently unhealthy. attempt_fn(model) makes one
+    call and returns a dict; an 'error' key counts as a failure (a 429
+    or 'rate limit' in the text counts as a rate-limit). When a
+    UsageStore is attached and the caller passes token counts back via
+    the result, the record lands with fallback_from set to the primary.
+    """
+
+    def __init__(self, health: ProviderHealth = None, usage_store=None):
+        self.health = health or ProviderHealth()
+        self.usage_store = usage_store
+        self.cascade_runs = 0
+        self.fallbacks_used = 0
+
+    @staticmethod
+    def _is_rate_limit(error: str) -> bool:
+        text = (error or "").lower()
+        return "429" in text or "rate limit" in text or "rate_limit" in text
+
+    def rank_fallbacks(self, fallbacks: list, require: dict = None) -> list:
+        """
+        Every fallback candidate scored: whether its provider is healthy,
+        whether it meets the required capabilities (require supports
+        'tools', 'vision', 'min_context_window'), and its blended
+        per-token price. Eligible candidates come back cheapest-first;
+        ineligible ones follow with the reason, so the full ranking
+        doubles as the "why wasn't X picked" answer.
+        """
+        require = require or {}
+        ranked = []
+        for model in fallbacks or []:
+            provider = infer_provider(model)
+            caps = capabilities_for(model)
+            entry = {"model": model, "provider": provider,
+                     "blended_rate_per_mtok": round(blended_rate(model), 4),
+                     "capabilities": caps, "eligible": True}
+            if not self.health.is_healthy(provider):
+                entry.update(eligible=False, reason=f"{provider} unhealthy")
+            elif require.get("tools") and not caps["tools"]:
+                entry.update(eligible=False, reason="no tool use")
+            elif require.get("vision") and not caps["vision"]:
+                entry.update(eligible=False, reason="no vision")
+            elif caps["context_window"] < require.get("min_context_window", 0):
+                entry.update(eligible=False,
+                             reason=f"context window {caps['context_window']} "
+                                    f"< {require['min_context_window']}")
+            ranked.append(entry)
+        ranked.sort(key=lambda e: (not e["eligible"],
+                                   e["blended_rate_per_mtok"]))
+        return ranked
+
+    def select_fallback_model(self, primary: str, fallbacks: list,
+                              require: dict = None) -> str:
+        """The cheapest healthy fallback that can actually serve the
+        request (capability requirements honoured) — not merely the
+        first list entry. None when no candidate qualifies."""
+        ranked = self.rank_fallbacks(fallbacks, require=require)
+        for entry in ranked:
+            if entry["eligible"]:
+                if entry["model"] != (fallbacks or [None])[0]:
+                    log.info(f"[FALLBACK] Scored selection for {primary}: "
+                             f"{entry['model']} "
+                             f"(${entry['blended_rate_per_mtok']}/Mtok)")
+                return entry["model"]
+        return None
+
+    def run(self, primary: str, fallbacks: list, attempt_fn,
+            agent_id: str = None, **record_kwargs) -> dict:
+        """
+        Execute the cascade. Returns the winning attempt's result plus
+        'model_used', 'fallback_from' (None when the primary served) and
+        the per-model 'attempts' trail; or {'error': ...} when every
+        candidate failed or was skipped.
+        """
+        self.cascade_runs += 1
+        attempts = []
+        for model in [primary] + list(fallbacks or []):
+            provider = infer_provider(model)
+            if not self.health.is_healthy(provider):
+                attempts.append({"model": model, "skipped": True,
+                                 "reason": f"{provider} unhealthy"})
+                continue
+            result = attempt_fn(model)
+            error = result.get("error") if isinstance(result, dict) else None
+            if error:
+                if self._is_rate_limit(error):
+                    self.health.record_rate_limit(
+                        provider, retry_after=result.get("retry_after_seconds"))
+                else:
+                    self.health.record_failure(provider)
+                attempts.append({"model": model, "error": error})
+                log.warning(f"[FALLBACK] {model} failed: {error}")
+                continue
+            self.health.record_success(provider)
+            fallback_from = primary if model != primary else None
+            if fallback_from:
+                self.fallbacks_used += 1
+                log.info(f"[FALLBACK] {primary} → {model} served the call")
+            if self.usage_store and agent_id and "input_tokens" in result:
+                self.usage_store.record(
+                    agent_id, model,
+                    result.get("input_tokens", 0),
+                    result.get("output_tokens", 0),
+                    provider=provider, fallback_from=fallback_from,
+                    **record_kwargs)
+            result.update({"model_used": model, "fallback_from": fallback_from,
+                           "attempts": attempts + [{"model": model, "ok": True}]})
+            return result
+        return {"error": "All models in the cascade failed or were skipped",
+                "attempts": attempts}
+
+    def status(self) -> dict:
+        return {
+            "cascade_runs": self.cascade_runs,
+            "fallbacks_used": self.fallbacks_used,
+            "providers": self.health.status(),
+            "window_seconds": self.health.window,
+            "failure_threshold": self.health.threshold,
+        }
+
+
+__all__ = ["FallbackCascade", "ProviderHealth", "infer_provider",
+           "capabilities_for", "blended_rate", "MODEL_CAPABILITIES",
+           "FALLBACK_HEALTH_WINDOW_SECONDS", "FALLBACK_FAILURE_THRESHOLD"]
@@ -0,0 +1,215 @@
+#!/usr/bin/env python3
+"""
+Object Storage Backends for Leviathan Super-Brain
+=================================================
+One put/get/delete/list interface with two implementations: the local
+filesystem (default — zero new dependencies, same layout as before) and
+any S3-compatible store (AWS S3, MinIO, R2) for clustered deployments
+where kernel nodes must stay stateless. Blob-writing subsystems (the
+cold store today; artifacts and agent archives can adopt it the same
+way) take a backend instead of opening files themselves, so switching
+a deployment to S3 is configuration, not code.
+
+The S3 backend does multipart upload above a size threshold, passes
+server-side encryption options through, and tags every object so bucket
+lifecycle rules (expire cold transcripts after a year, transition to
+Glacier) can target kernel data by tag instead of by prefix guessing.
+
+Env:
+  OBJECT_STORE_BACKEND        — "local" (default) or "s3"
+  OBJECT_STORE_S3_BUCKET      — bucket name (required for s3)
+  OBJECT_STORE_S3_ENDPOINT    — endpoint URL for MinIO/R2 (optional)
+  OBJECT_STORE_S3_PREFIX      — key prefix, e.g. "leviathan/" (optional)
+  OBJECT_STORE_SSE            — "AES256" or "aws:kms" (optional)
+  OBJECT_STORE_KMS_KEY_ID     — KMS key when SSE is aws:kms
+  OBJECT_STORE_STORAGE_CLASS  — e.g. "STANDARD_IA" (optional)
+
+Author: Leviathan DevOps
+"""
+
+import os
+import logging
+from urllib.parse import quote
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+OBJECT_STORE_BACKEND = os.environ.get("OBJECT_STORE_BACKEND", "local")
+OBJECT_STORE_S3_BUCKET = os.environ.get("OBJECT_STORE_S3_BUCKET", "")
+OBJECT_STORE_S3_ENDPOINT = os.environ.get("OBJECT_STORE_S3_ENDPOINT", "")
+OBJECT_STORE_S3_PREFIX = os.environ.get("OBJECT_STORE_S3_PREFIX", "")
+OBJECT_STORE_SSE = os.environ.get("OBJECT_STORE_SSE", "")
+OBJECT_STORE_KMS_KEY_ID = os.environ.get("OBJECT_STORE_KMS_KEY_ID", "")
+OBJECT_STORE_STORAGE_CLASS = os.environ.get("OBJECT_STORE_STORAGE_CLASS", "")
+
+# Uploads above this go multipart so one huge archive never has to fit
+# in a single PUT (S3 caps those at 5 GB anyway)
+OBJECT_STORE_MULTIPART_THRESHOLD_MB = int(
+    os.environ.get("OBJECT_STORE_MULTIPART_THRESHOLD_MB", "64"))
+OBJECT_STORE_MULTIPART_PART_MB = int(
+    os.environ.get("OBJECT_STORE_MULTIPART_PART_MB", "16"))
+
+log = logging.getLogger("object_storage")
+
+
+class LocalBackend:
+    """Filesystem blobs under a root directory. Keys map to relative
+    paths; tags are accepted but meaningless here — lifecycle is the
+    purge daemon's job on local disk, not the filesystem's."""
+
+    name = "local"
+
+    def __init__(self, root: str):
+        self.root = root
+
+    def _path(self, key: str) -> str:
+        return os.path.join(self.root, key)
+
+    def put(self, key: str, data: bytes, tags: dict = None) -> dict:
+        path = self._path(key)
+        os.makedirs(os.path.dirname(path), exist_ok=True)
+        with open(path, "wb") as f:
+            f.write(data)
+        return {"key": key, "bytes": len(data), "backend": self.name}
+
+    def get(self, key: str) -> bytes:
+        with open(self._path(key), "rb") as f:
+            return f.read()
+
+    def delete(self, key: str) -> bool:
+        try:
+            os.unlink(self._path(key))
+            return True
+        except FileNotFoundError:
+            return False
+
+    def exists(self, key: str) -> bool:
+        return os.path.isfile(self._path(key))
+
+    def list_keys(self, prefix: str = "") -> list:
+        keys = []
+        for dirpath, _, filenames in os.walk(self.root):
+            for name in filenames:
+                key = os.path.relpath(os.path.join(dirpath, name), self.root)
+                if key.startswith(prefix):
+                    keys.append(key)
+        return sorted(keys)
+
+    def describe(self) -> dict:
+        return {"backend": self.name, "root": self.root}
+
+
+class S3Backend:
+    """S3/MinIO blobs. Needs boto3 on the box; the constructor says so
+    if it isn't. Every object is tagged (caller tags plus app=leviathan)
+    so bucket lifecycle rules can target kernel data precisely."""
+
+    name = "s3"
+
+    def __init__(self, bucket: str = OBJECT_STORE_S3_BUCKET,
+                 endpoint_url: str = OBJECT_STORE_S3_ENDPOINT,
+                 prefix: str = OBJECT_STORE_S3_PREFIX):
+        try:
+            import boto3
+        except ImportError:
+            raise RuntimeError("S3 object storage requires boto3 "
+                               "(pip install boto3)")
+        if not bucket:
+            raise RuntimeError("OBJECT_STORE_S3_BUCKET is required for the "
+                               "s3 backend")
+        self.bucket = bucket
+        self.prefix = prefix
+        self.client = boto3.client("s3", endpoint_url=endpoint_url or None)
+
+    def _key(self, key: str) -> str:
+        return self.prefix + key
+
+    def _extra_args(self, tags: dict = None) -> dict:
+        extra = {}
+        if OBJECT_STORE_SSE:
+            extra["ServerSideEncryption"] = OBJECT_STORE_SSE
+            if OBJECT_STORE_SSE == "aws:kms" and OBJECT_STORE_KMS_KEY_ID:
+                extra["SSEKMSKeyId"] = OBJECT_STORE_KMS_KEY_ID
+        if OBJECT_STORE_STORAGE_CLASS:
+            extra["StorageClass"] = OBJECT_STORE_STORAGE_CLASS
+        all_tags = {"app": "leviathan", **(tags or {})}
+        extra["Tagging"] = "&".join(
+            f"{quote(k)}={quote(str(v))}" for k, v in sorted(all_tags.items()))
+        return extra
+
+    def put(self, key: str, data: bytes, tags: dict = None) -> dict:
+        full_key = self._key(key)
+        extra = self._extra_args(tags)
+        threshold = OBJECT_STORE_MULTIPART_THRESHOLD_MB * 1024 * 1024
+        if len(data) > threshold:
+            self._put_multipart(full_key, data, extra)
+        else:
+            self.client.put_object(Bucket=self.bucket, Key=full_key,
+                                   Body=data, **extra)
+        return {"key": key, "bytes": len(data), "backend": self.name,
+                "multipart": len(data) > threshold}
+
+    def _put_multipart(self, full_key: str, data: bytes, extra: dict):
+        part_size = OBJECT_STORE_MULTIPART_PART_MB * 1024 * 1024
+        upload = self.client.create_multipart_upload(
+            Bucket=self.bucket, Key=full_key, **extra)
+        upload_id = upload["UploadId"]
+        try:
+            parts = []
+            for number, offset in enumerate(range(0, len(data), part_size), 1):
+                part = self.client.upload_part(
+                    Bucket=self.bucket, Key=full_key, UploadId=upload_id,
+                    PartNumber=number, Body=data[offset:offset + part_size])
+                parts.append({"PartNumber": number, "ETag": part["ETag"]})
+            self.client.complete_multipart_upload(
+                Bucket=self.bucket, Key=full_key, UploadId=upload_id,
+                MultipartUpload={"Parts": parts})
+            log.info(f"[S3] Multipart upload of {full_key}: "
+                     f"{len(parts)} parts, {len(data)} bytes")
+        except Exception:
+            # Abort so half-uploaded parts don't accrue storage charges
+            self.client.abort_multipart_upload(
+                Bucket=self.bucket, Key=full_key, UploadId=upload_id)
+            raise
+
+    def get(self, key: str) -> bytes:
+        obj = self.client.get_object(Bucket=self.bucket, Key=self._key(key))
+        return obj["Body"].read()
+
+    def delete(self, key: str) -> bool:
+        self.client.delete_object(Bucket=self.bucket, Key=self._key(key))
+        return True
+
+    def exists(self, key: str) -> bool:
+        try:
+            self.client.head_object(Bucket=self.bucket, Key=self._key(key))
+            return True
+        except self.client.exceptions.ClientError:
+            return False
+
+    def list_keys(self, prefix: str = "") -> list:
+        keys = []
+        paginator = self.client.get_paginator("list_objects_v2")
+        for page in paginator.paginate(Bucket=self.bucket,
+                                       Prefix=self._key(prefix)):
+            for obj in page.get("Contents", []):
+                keys.append(obj["Key"][len(self.prefix):])
+        return keys
+
+    def describe(self) -> dict:
+        return {"backend": self.name, "bucket": self.bucket,
+                "endpoint": OBJECT_STORE_S3_ENDPOINT or "aws",
+                "prefix": self.prefix,
+                "sse": OBJECT_STORE_SSE or None,
+                "storage_class": OBJECT_STORE_STORAGE_CLASS or None}
+
+
+def from_env(local_root: str):
+    """The configured backend — local_root is where blobs land when the
+    deployment hasn't opted into S3."""
+    if OBJECT_STORE_BACKEND == "s3":
+        return S3Backend()
+    return LocalBackend(local_root)
+
+
+__all__ = ["LocalBackend", "S3Backend", "from_env"]
@@ -0,0 +1,177 @@
+#!/usr/bin/env python3
+"""
+OpenTelemetry Span Export for Leviathan Super-Brain
+===================================================
+Every recorded usage event becomes an OTLP span with model, token and
+cost attributes, shipped to the observability stack over OTLP/HTTP JSON
+— so agent latency and spend line up in the same traces. Like the
+Prometheus exporter this hangs off the UsageStore observer hook, and
+like everything else here it speaks the wire format directly instead of
+pulling in an SDK.
+
+Export is off unless OTEL_EXPORTER_OTLP_ENDPOINT is set (the standard
+variable, e.g. http://otel-collector:4318). Spans buffer in memory and
+flush in batches from a background thread; a dead collector costs
+dropped spans, never a blocked usage record.
+
+Author: Leviathan DevOps
+"""
+
+import json
+import os
+import time
+import logging
+import secrets
+import threading
+import urllib.request
+import urllib.error
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+OTLP_ENDPOINT = os.environ.get("OTEL_EXPORTER_OTLP_ENDPOINT", "")
+OTEL_SERVICE_NAME = os.environ.get("OTEL_SERVICE_NAME", "leviathan-super-brain")
+
+OTEL_BATCH_SIZE = int(os.environ.get("OTEL_BATCH_SIZE", "50"))
+OTEL_FLUSH_SECONDS = int(os.environ.get("OTEL_FLUSH_SECONDS", "5"))
+OTEL_BUFFER_MAX = int(os.environ.get("OTEL_BUFFER_MAX", "2000"))
+OTEL_TIMEOUT_SECONDS = int(os.environ.get("OTEL_TIMEOUT_SECONDS", "5"))
+
+log = logging.getLogger("otel_export")
+
+
+def _attr(key, value):
+    """One OTLP attribute in the typed wire form."""
+    if isinstance(value, bool):
+        return {"key": key, "value": {"boolValue": value}}
+    if isinstance(value, int):
+        return {"key": key, "value": {"intValue": str(value)}}
+    if isinstance(value, float):
+        return {"key": key, "value": {"doubleValue": value}}
+    return {"key": key, "value": {"stringValue": str(value)}}
+
+
+class OtelSpanExporter:
+    """Usage records → OTLP spans, batched to the collector."""
+
+    def __init__(self, endpoint: str = OTLP_ENDPOINT):
+        self.endpoint = endpoint.rstrip("/") if endpoint else ""
+        self.enabled = bool(self.endpoint)
+        self._buffer = []
+        self._lock = threading.Lock()
+        self.exported = 0
+        self.dropped = 0
+        self.last_error = None
+        if self.enabled:
+            worker = threading.Thread(target=self._flush_loop, daemon=True,
+                                      name="OtelFlush")
+            worker.start()
+            log.info(f"[OTEL] Exporting spans to {self.endpoint} "
+                     f"(batch {OTEL_BATCH_SIZE}, flush {OTEL_FLUSH_SECONDS}s)")
+        else:
+            log.info("[OTEL] OTEL_EXPORTER_OTLP_ENDPOINT not set — "
+                     "span export disabled")
+
+    def observe(self, record: dict):
+        """UsageStore observer: turn one usage record into a buffered span."""
+        if not self.enabled:
+            return
+        span = self._span_from_record(record)
+        with self._lock:
+            if len(self._buffer) >= OTEL_BUFFER_MAX:
+                self._buffer.pop(0)
+                self.dropped += 1
+            self._buffer.append(span)
+
+    @staticmethod
+    def _span_from_record(record: dict) -> dict:
+        """
+        One span per LLM call. The usage record carries no wall-clock
+        duration, so the span is zero-length at the record timestamp —
+        the attributes (model, tokens, cost) are what the traces are for.
+        """
+        try:
+            at = datetime.fromisoformat(record.get("created_at", ""))
+        except (ValueError, TypeError):
+            at = datetime.now(timezone.utc)
+        nanos = str(int(at.timestamp() * 1_000_000_000))
+        attributes = [
+            _attr("llm.agent_id", record.get("agent_id", "")),
+            _attr("llm.model", record.get("model", "")),
+            _attr("llm.input_tokens", int(record.get("input_tokens") or 0)),
+            _attr("llm.output_tokens", int(record.get("output_tokens") or 0)),
+            _attr("llm.cost_usd", float(record.get("cost_usd") or 0.0)),
+        ]
+        if record.get("estimated_cost_usd") is not None:
+            attributes.append(_attr("llm.estimated_cost_usd",
+                                    float(record["estimated_cost_usd"])))
+        return {
+            "traceId": secrets.token_hex(16),
+            "spanId": secrets.token_hex(8),
+            "name": "llm.call",
+            "kind": 1,  # SPAN_KIND_INTERNAL
+            "startTimeUnixNano": nanos,
+            "endTimeUnixNano": nanos,
+            "attributes": attributes,
+        }
+
+    def _flush_loop(self):
+        while True:
+            time.sleep(OTEL_FLUSH_SECONDS)
+            try:
+                self.flush()
+            except Exception as e:
+                log.error(f"[OTEL] Flush loop error: {e}")
+
+    def flush(self) -> int:
+        """Ship everything buffered; returns the number of spans sent.
+        On collector failure the batch is dropped (and counted), so a
+        dead collector can't grow the buffer without bound."""
+        with self._lock:
+            if not self._buffer:
+                return 0
+            batch, self._buffer = self._buffer, []
+
+        payload = {
+            "resourceSpans": [{
+                "resource": {"attributes": [
+                    _attr("service.name", OTEL_SERVICE_NAME),
+                ]},
+                "scopeSpans": [{
+                    "scope": {"name": "leviathan.usage"},
+                    "spans": batch,
+                }],
+            }],
+        }
+        request = urllib.request.Request(
+            f"{self.endpoint}/v1/traces",
+            data=json.dumps(payload).encode(),
+            headers={"Content-Type": "application/json"},
+        )
+        try:
+            with urllib.request.urlopen(request, timeout=OTEL_TIMEOUT_SECONDS):
+                pass
+            self.exported += len(batch)
+            self.last_error = None
+            return len(batch)
+        except (urllib.error.URLError, OSError) as e:
+            self.dropped += len(batch)
+            self.last_error = str(e)
+            log.warning(f"[OTEL] Dropped {len(batch)} spans: {e}")
+            return 0
+
+    def status(self) -> dict:
+        with self._lock:
+            buffered = len(self._buffer)
+        return {
+            "enabled": self.enabled,
+            "endpoint": self.endpoint or None,
+            "buffered": buffered,
+            "exported": self.exported,
+            "dropped": self.dropped,
+            "last_error": self.last_error,
+        }
+
+
+__all__ = ["OtelSpanExporter"]
@@ -0,0 +1,228 @@
+#!/usr/bin/env python3
+"""
+Inter-Kernel Peer Transport for Leviathan Super-Brain
+=====================================================
+Authenticated, encrypted kernel-to-kernel messaging — the transport
+the clustering/federation and event-bridge work rides on. Design, in
+this codebase's zero-dependency spirit:
+
+  - Encryption: TLS, with the peer's certificate pinned by SHA-256
+    fingerprint — a hostile network (or CA) can't insert itself.
+  - Authentication: every message body is HMAC-SHA256 signed with a
+    per-peer shared secret, with a freshness window against replays.
+  - Identity: each kernel holds a node key created on first start and
+    kept under the data directory; its fingerprint is the node ID peers
+    address and log.
+
+Peers come from PEER_NODES_JSON:
+  {"berlin": {"url": "https://berlin.example:8443", "secret": "...",
+              "cert_sha256": "ab12..."}}
+cert_sha256 may be omitted for plain-HTTP lab setups — production peers
+should always pin.
+
+Author: Leviathan DevOps
+"""
+
+import ssl
+import json
+import os
+import time
+import hmac
+import hashlib
+import secrets
+import logging
+import http.client
+from urllib.parse import urlparse
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+NODE_KEY_PATH = os.environ.get("NODE_KEY_PATH", "/data/node-identity.key")
+PEER_NODES_JSON = os.environ.get("PEER_NODES_JSON", "")
+
+PEER_TIMEOUT_SECONDS = int(os.environ.get("PEER_TIMEOUT_SECONDS", "10"))
+# Signed messages older than this are rejected as replays
+PEER_MAX_SKEW_SECONDS = int(os.environ.get("PEER_MAX_SKEW_SECONDS", "300"))
+
+log = logging.getLogger("peer_transport")
+
+
+class NodeIdentity:
+    """This kernel's node key, created on first start and persisted."""
+
+    def __init__(self, key: str):
+        self.key = key
+        self.node_id = hashlib.sha256(key.encode()).hexdigest()[:16]
+
+    @classmethod
+    def load_or_create(cls, path: str = NODE_KEY_PATH) -> "NodeIdentity":
+        try:
+            with open(path) as f:
+                return cls(f.read().strip())
+        except OSError:
+            pass
+        key = secrets.token_hex(32)
+        try:
+            os.makedirs(os.path.dirname(path) or ".", exist_ok=True)
+            fd = os.open(path, os.O_WRONLY | os.O_CREAT | os.O_TRUNC, 0o600)
+            with os.fdopen(fd, "w") as f:
+                f.write(key)
+            log.info(f"[PEER] Node identity created at {path}")
+        except OSError as e:
+            log.warning(f"[PEER] Could not persist node key ({e}) — "
+                        f"identity is ephemeral this run")
+        return cls(key)
+
+
+def _sign(secret: str, body: bytes) -> str:
+    return hmac.new(secret.encode(), body, hashlib.sha256).hexdigest()
+
+
+class PeerTransport:
+    """Pinned-TLS + HMAC message exchange with configured peer kernels."""
+
+    def __init__(self, identity: NodeIdentity = None,
+                 peers_json: str = PEER_NODES_JSON):
+        self.identity = identity or NodeIdentity.load_or_create()
+        self.peers = {}
+        self.sent = 0
+        self.received = 0
+        self.rejected = 0
+        if peers_json:
+            try:
+                self.peers = self._validate(json.loads(peers_json))
+            except (json.JSONDecodeError, ValueError) as e:
+                log.error(f"[PEER] Bad PEER_NODES_JSON ignored: {e}")
+        log.info(f"[PEER] Node {self.identity.node_id} with "
+                 f"{len(self.peers)} configured peer(s)")
+
+    @staticmethod
+    def _validate(raw) -> dict:
+        if not isinstance(raw, dict):
+            raise ValueError("PEER_NODES_JSON must map peer names to configs")
+        peers = {}
+        for name, peer in raw.items():
+            if not peer.get("url") or not peer.get("secret"):
+                raise ValueError(f"Peer '{name}' needs 'url' and 'secret'")
+            peers[name] = {
+                "url": peer["url"].rstrip("/"),
+                "secret": peer["secret"],
+                "cert_sha256": (peer.get("cert_sha256") or "").lower().replace(":", ""),
+            }
+        return peers
+
+    # ── Outbound ──
+
+    def _connect(self, peer: dict):
+        """Open the connection, enforcing the certificate pin before any
+        request bytes leave."""
+        parsed = urlparse(peer["url"])
+        if parsed.scheme == "https":
+            # Pin replaces chain trust: the fingerprint check below is
+            # the authentication, so self-signed peer certs are fine
+            context = ssl.create_default_context()
+            context.check_hostname = False
+            context.verify_mode = ssl.CERT_NONE
+            conn = http.client.HTTPSConnection(
+                parsed.hostname, parsed.port or 443,
+                timeout=PEER_TIMEOUT_SECONDS, context=context)
+            conn.connect()
+            if peer["cert_sha256"]:
+                der = conn.sock.getpeercert(binary_form=True)
+                fingerprint = hashlib.sha256(der).hexdigest()
+                if fingerprint != peer["cert_sha256"]:
+                    conn.close()
+                    raise ssl.SSLError(
+                        f"certificate pin mismatch: got {fingerprint[:16]}…")
+            else:
+                log.warning(f"[PEER] {parsed.hostname}: no cert pin configured")
+        else:
+            conn = http.client.HTTPConnection(
+                parsed.hostname, parsed.port or 80,
+                timeout=PEER_TIMEOUT_SECONDS)
+        return conn, parsed.path or ""
+
+    def send(self, peer_name: str, topic: str, payload: dict) -> dict:
+        """Deliver one signed message to a peer kernel's /peer/message."""
+        peer = self.peers.get(peer_name)
+        if not peer:
+            return {"error": f"Unknown peer: {peer_name}"}
+        body = json.dumps({
+            "from_node": self.identity.node_id,
+            "topic": topic,
+            "payload": payload,
+            "sent_at": datetime.now(timezone.utc).isoformat(),
+            "nonce": secrets.token_hex(8),
+        }).encode()
+        headers = {
+            "Content-Type": "application/json",
+            "X-Leviathan-Node": self.identity.node_id,
+            "X-Leviathan-Signature": _sign(peer["secret"], body),
+        }
+        try:
+            conn, base_path = self._connect(peer)
+            try:
+                conn.request("POST", f"{base_path}/peer/message", body, headers)
+                resp = conn.getresponse()
+                resp_body = resp.read()
+            finally:
+                conn.close()
+            if resp.status >= 300:
+                return {"error": f"Peer returned HTTP {resp.status}"}
+            self.sent += 1
+            return {"status": "delivered", "peer": peer_name,
+                    "response": json.loads(resp_body) if resp_body else None}
+        except (OSError, ssl.SSLError, json.JSONDecodeError) as e:
+            log.warning(f"[PEER] Send to {peer_name} failed: {e}")
+            return {"error": str(e)}
+
+    # ── Inbound ──
+
+    def verify_inbound(self, headers: dict, body: bytes):
+        """
+        Authenticate an inbound peer message: the sender must be a
+        configured peer, the HMAC must verify against that peer's
+        secret, and the timestamp must be inside the freshness window.
+        Returns the parsed message, or an error dict.
+        """
+        node_id = headers.get("X-Leviathan-Node", "")
+        signature = headers.get("X-Leviathan-Signature", "")
+        peer = None
+        for name, candidate in self.peers.items():
+            if hmac.compare_digest(_sign(candidate["secret"], body), signature):
+                peer = name
+                break
+        if peer is None:
+            self.rejected += 1
+            log.warning(f"[PEER] Rejected message claiming node {node_id}: "
+                        f"no peer secret verifies the signature")
+            return {"error": "Signature verification failed"}
+        try:
+            message = json.loads(body)
+            sent_at = datetime.fromisoformat(message["sent_at"])
+        except (json.JSONDecodeError, KeyError, ValueError):
+            self.rejected += 1
+            return {"error": "Malformed peer message"}
+        age = abs((datetime.now(timezone.utc) - sent_at).total_seconds())
+        if age > PEER_MAX_SKEW_SECONDS:
+            self.rejected += 1
+            return {"error": f"Stale message ({int(age)}s old, "
+                             f"window {PEER_MAX_SKEW_SECONDS}s)"}
+        self.received += 1
+        message["peer"] = peer
+        return message
+
+    def status(self) -> dict:
+        return {
+            "node_id": self.identity.node_id,
+            "peers": [{"name": n, "url": p["url"],
+                       "pinned": bool(p["cert_sha256"])}
+                      for n, p in self.peers.items()],
+            "sent": self.sent,
+            "received": self.received,
+            "rejected": self.rejected,
+        }
+
+
+__all__ = ["PeerTransport", "NodeIdentity"]
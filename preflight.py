@@ -0,0 +1,176 @@
+#!/usr/bin/env python3
+"""
+Upgrade Preflight for Leviathan Super-Brain
+===========================================
+Checks an existing data directory against the running kernel version
+*before* startup mutates anything, so blue/green upgrades can bail out
+instead of half-migrating. The check opens the database read-only and
+reports:
+  - tables the new version will create (safe, additive)
+  - columns the new version will add via ALTER (safe, additive)
+  - columns present on disk that the code no longer knows (incompatible —
+    usually a downgrade or a foreign database)
+  - registry contents (agents, tools, connections) for sanity
+  - config file format problems
+
+Run standalone before switching traffic:
+    SUPER_BRAIN_DB_PATH=/data/hydra-brain.db python3 preflight.py
+
+Exit code 0 = compatible, 1 = migrations required, 2 = incompatible.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import sys
+import logging
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+CONFIG_PATH = os.environ.get("LEVIATHAN_CONFIG_PATH", "/data/leviathan.env")
+
+KERNEL_VERSION = "2.1"
+
+# The schema this kernel version expects. Missing tables/columns are
+# additive (ensure_schema creates them); unknown extra columns are not.
+EXPECTED_SCHEMA = {
+    "tool_declarations": ["name", "description", "schema_json", "capability",
+                          "timeout_seconds", "cache_ttl_seconds",
+                          "declared_at", "updated_at"],
+    "agent_tool_bindings": ["agent_id", "tool_name", "bound_at"],
+    "cron_entries": ["entry_id", "owner", "kind", "fire_at", "payload_json",
+                     "status", "created_at", "fired_at"],
+    "handoff_sessions": ["handoff_id", "session_id", "user_ref", "gateway",
+                         "operator_channel", "escalated_by", "reason",
+                         "status", "created_at", "returned_at"],
+    "broadcast_recipients": ["user_ref", "gateway", "tenant_id", "labels",
+                             "opted_out", "registered_at"],
+    "turn_transcripts": None,   # presence checked, columns owned by transcripts.py
+    "agent_quotas": None,
+    "shell_policies": None,
+    "sql_connections": None,
+}
+
+log = logging.getLogger("preflight")
+
+
+def _table_columns(conn: sqlite3.Connection, table: str) -> list:
+    return [row[1] for row in conn.execute(f"PRAGMA table_info({table})").fetchall()]
+
+
+def check_database(db_path: str = DB_PATH) -> dict:
+    """Schema diff between the on-disk database and this kernel version."""
+    if not os.path.exists(db_path):
+        return {"exists": False, "note": "fresh install — schema created on startup",
+                "required_migrations": [], "incompatibilities": []}
+
+    try:
+        conn = sqlite3.connect(f"file:{db_path}?mode=ro", uri=True, timeout=10)
+    except sqlite3.OperationalError as e:
+        return {"exists": True, "required_migrations": [],
+                "incompatibilities": [f"cannot open database read-only: {e}"]}
+
+    try:
+        on_disk = {row[0] for row in conn.execute(
+            "SELECT name FROM sqlite_master WHERE type = 'table'"
+        ).fetchall()}
+
+        migrations = []
+        incompatibilities = []
+        for table, expected_columns in EXPECTED_SCHEMA.items():
+            if table not in on_disk:
+                migrations.append(f"create table {table}")
+                continue
+            if expected_columns is None:
+                continue
+            actual = _table_columns(conn, table)
+            for column in expected_columns:
+                if column not in actual:
+                    migrations.append(f"add column {table}.{column}")
+            for column in actual:
+                if column not in expected_columns:
+                    incompatibilities.append(
+                        f"unknown column {table}.{column} — data directory is "
+                        f"newer than kernel {KERNEL_VERSION}?")
+        return {"exists": True, "tables_on_disk": len(on_disk),
+                "required_migrations": migrations,
+                "incompatibilities": incompatibilities}
+    finally:
+        conn.close()
+
+
+def check_registry(db_path: str = DB_PATH) -> dict:
+    """Row counts for the registries a restarted kernel will load."""
+    if not os.path.exists(db_path):
+        return {}
+    counts = {}
+    try:
+        conn = sqlite3.connect(f"file:{db_path}?mode=ro", uri=True, timeout=10)
+    except sqlite3.OperationalError:
+        return {}
+    try:
+        for label, table in (("agents", "agent_manifests"),
+                             ("tools", "tool_declarations"),
+                             ("sql_connections", "sql_connections"),
+                             ("scheduled_entries", "cron_entries")):
+            try:
+                counts[label] = conn.execute(
+                    f"SELECT COUNT(*) FROM {table}").fetchone()[0]
+            except sqlite3.OperationalError:
+                counts[label] = None
+        return counts
+    finally:
+        conn.close()
+
+
+def check_config(config_path: str = CONFIG_PATH) -> dict:
+    """Validate the env-format config file written by the setup wizard."""
+    if not os.path.exists(config_path):
+        return {"exists": False}
+    problems = []
+    with open(config_path) as f:
+        for lineno, line in enumerate(f, 1):
+            line = line.strip()
+            if not line or line.startswith("#"):
+                continue
+            if "=" not in line:
+                problems.append(f"line {lineno}: not KEY=VALUE format")
+    return {"exists": True, "problems": problems}
+
+
+def run_preflight(db_path: str = DB_PATH, config_path: str = CONFIG_PATH) -> dict:
+    """Full preflight report. Never mutates the data directory."""
+    database = check_database(db_path)
+    config = check_config(config_path)
+    report = {
+        "kernel_version": KERNEL_VERSION,
+        "db_path": db_path,
+        "database": database,
+        "registry": check_registry(db_path),
+        "config": config,
+    }
+    if database.get("incompatibilities") or config.get("problems"):
+        report["verdict"] = "incompatible"
+    elif database.get("required_migrations"):
+        report["verdict"] = "migrations_required"
+    else:
+        report["verdict"] = "compatible"
+    return report
+
+
+def main():
+    report = run_preflight()
+    print(json.dumps(report, indent=2))
+    return {"compatible": 0, "migrations_required": 1, "incompatible": 2}[report["verdict"]]
+
+
+if __name__ == "__main__":
+    sys.exit(main())
+
+
+__all__ = ["run_preflight", "check_database", "check_registry", "check_config",
+           "KERNEL_VERSION"]
@@ -0,0 +1,107 @@
+#!/usr/bin/env python3
+"""
+Model Pricing Table for Leviathan Super-Brain
+=============================================
+The one place cost math lives. Per-model USD rates per million tokens —
+input, output, and cached input (prompt-cache hits bill cheaper) — with
+deployment overrides so a price change doesn't need a deploy:
+
+  MODEL_PRICING_JSON    — JSON dict merged over the built-in table
+  LEVIATHAN_PRICING_PATH — path to a JSON file of the same shape
+
+Everything that turns token counts into dollars (UsageStore, budget
+trackers, cost previews) should go through compute_cost() rather than
+keeping its own copy of the table.
+
+Author: Leviathan DevOps
+"""
+
+import os
+import json
+import logging
+
+log = logging.getLogger("pricing")
+
+# Cost per million tokens (approximate, from provider pricing).
+# 'cached_input' is the rate for prompt-cache reads; 'cache_write' is
+# the surcharge rate for writing a prompt into the cache (Anthropic and
+# OpenAI bill both sides separately). Models without an entry fall back
+# to the full input rate for reads and 1.25× input for writes.
+PRICING = {
+    'deepseek-chat': {'input': 0.27, 'output': 1.10, 'cached_input': 0.07},
+    'deepseek-reasoner': {'input': 0.55, 'output': 2.19, 'cached_input': 0.14},
+    'claude-opus-4-6': {'input': 15.00, 'output': 75.00, 'cached_input': 1.50,
+                        'cache_write': 18.75},
+    'grok-4-1-fast-reasoning': {'input': 3.00, 'output': 15.00, 'cached_input': 0.75},
+    'gpt-5.3-codex': {'input': 2.00, 'output': 8.00, 'cached_input': 0.50,
+                      'cache_write': 2.50},
+    'google/gemini-2.5-flash-preview-05-20': {'input': 0.15, 'output': 0.60,
+                                              'cached_input': 0.0375},
+    'google/gemini-1.5-pro': {'input': 1.25, 'output': 5.00, 'cached_input': 0.3125},
+    'google/gemma-3-27b-it': {'input': 0.00, 'output': 0.00},  # FREE
+    'qwen/qwen3-235b-a22b': {'input': 0.00, 'output': 0.00},  # FREE via OpenRouter
+}
+
+# Cache-write surcharge for models without an explicit 'cache_write'
+# rate (the common provider default is 25% over fresh input)
+CACHE_WRITE_MULTIPLIER = 1.25
+
+# Fallback rates for unknown models — deliberately pessimistic
+DEFAULT_RATES = {'input': 1.0, 'output': 3.0}
+
+
+def _load_overrides():
+    """Merge deployment overrides into the built-in table, per model."""
+    overrides = {}
+    path = os.environ.get("LEVIATHAN_PRICING_PATH")
+    if path:
+        try:
+            with open(path) as f:
+                overrides.update(json.load(f))
+        except (OSError, json.JSONDecodeError) as e:
+            log.warning(f"[PRICING] Could not load {path}: {e}")
+    raw = os.environ.get("MODEL_PRICING_JSON")
+    if raw:
+        try:
+            overrides.update(json.loads(raw))
+        except json.JSONDecodeError as e:
+            log.warning(f"[PRICING] Bad MODEL_PRICING_JSON: {e}")
+    for model, rates in overrides.items():
+        if isinstance(rates, dict):
+            PRICING[model] = {**PRICING.get(model, {}), **rates}
+            log.info(f"[PRICING] Override applied for {model}")
+
+
+_load_overrides()
+
+
+def rates_for(model: str) -> dict:
+    """The rate card for a model (DEFAULT_RATES when unknown)."""
+    return PRICING.get(model, DEFAULT_RATES)
+
+
+def compute_cost(model: str, input_tokens: int, output_tokens: int,
+                 cached_input_tokens: int = 0,
+                 cache_write_tokens: int = 0) -> float:
+    """
+    USD cost for a call. cached_input_tokens is the portion of
+    input_tokens served from the prompt cache, billed at the cached
+    (read) rate; cache_write_tokens is the portion written INTO the
+    cache this call, billed at the cache_write rate (1.25× input when
+    the model has no explicit rate). Both default to the full input
+    rate falling out of the math when the model prices neither.
+    """
+    rates = rates_for(model)
+    cached = min(cached_input_tokens or 0, input_tokens)
+    written = min(cache_write_tokens or 0, input_tokens - cached)
+    fresh = input_tokens - cached - written
+    cached_rate = rates.get('cached_input', rates['input'])
+    write_rate = rates.get('cache_write',
+                           rates['input'] * CACHE_WRITE_MULTIPLIER)
+    return (fresh * rates['input'] + cached * cached_rate
+            + written * write_rate
+            + output_tokens * rates['output']) / 1_000_000
+
+
+__all__ = ["PRICING", "DEFAULT_RATES", "CACHE_WRITE_MULTIPLIER",
+           "rates_for", "compute_cost"]
@@ -0,0 +1,449 @@
+#!/usr/bin/env python3
+"""
+Resource Quotas for Leviathan Super-Brain
+=========================================
+Per-agent resource quotas, persisted and enforced at the point of use.
+First enforcement target: outbound messages — a misbehaving agent must
+not be able to spam a paired user's phone hundreds of times a day, so
+`max_outbound_messages_per_day` is checked in the gateway layer with a
+warning emitted at 80% of the limit.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import logging
+from dataclasses import dataclass, asdict
+from datetime import datetime, timedelta, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+
+WARNING_THRESHOLD = 0.8  # warn when a counter crosses 80% of its quota
+
+log = logging.getLogger("quotas")
+
+
+@dataclass
+class ResourceQuota:
+    """Per-agent resource limits. None means unlimited.
+
+    per_model constrains individual models within the overall budget —
+    e.g. {"anthropic/claude-opus-4": {"max_tokens_per_day": 200000}}
+    caps the expensive model without starving cheap ones. Models not
+    listed inherit only the agent-wide limits."""
+    max_tokens_per_day: int = None
+    max_tokens_per_month: int = None
+    max_cost_usd_per_hour: float = None
+    max_cost_usd_per_day: float = None
+    max_cost_usd_per_month: float = None
+    max_outbound_messages_per_day: int = None
+    max_cpu_seconds_per_day: float = None
+    max_tool_rss_kb: int = None
+    per_model: dict = None
+
+    def to_dict(self) -> dict:
+        return asdict(self)
+
+    @classmethod
+    def from_dict(cls, data: dict) -> "ResourceQuota":
+        known = {f: data.get(f) for f in cls.__dataclass_fields__}
+        return cls(**known)
+
+
+class QuotaManager:
+    """
+    SQLite-backed quota storage and per-day outbound counters.
+
+    Tables:
+      agent_quotas       — one quota record per agent (JSON)
+      outbound_counters  — per-agent per-day outbound message counts
+    """
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS agent_quotas (
+                    agent_id TEXT PRIMARY KEY,
+                    quota TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                )
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS outbound_counters (
+                    agent_id TEXT NOT NULL,
+                    day TEXT NOT NULL,
+                    count INTEGER NOT NULL DEFAULT 0,
+                    warned INTEGER NOT NULL DEFAULT 0,
+                    PRIMARY KEY (agent_id, day)
+                )
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS budget_overrides (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    agent_id TEXT NOT NULL,
+                    multiplier REAL NOT NULL,
+                    granted_by TEXT NOT NULL,
+                    reason TEXT,
+                    granted_at TEXT NOT NULL,
+                    expires_at TEXT NOT NULL,
+                    revoked_at TEXT
+                )
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS quota_audit_log (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    agent_id TEXT NOT NULL,
+                    old_quota TEXT,
+                    new_quota TEXT NOT NULL,
+                    changed_by TEXT,
+                    reason TEXT,
+                    changed_at TEXT NOT NULL
+                )
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _now() -> str:
+        return datetime.now(timezone.utc).isoformat()
+
+    @staticmethod
+    def _today() -> str:
+        return datetime.now(timezone.utc).strftime("%Y-%m-%d")
+
+    @staticmethod
+    def _window_resets_at(now: datetime, window: str) -> datetime:
+        """UTC boundary where a quota window's counters start over —
+        the answer to "when can I use it again"."""
+        if window == "hour":
+            return (now.replace(minute=0, second=0, microsecond=0)
+                    + timedelta(hours=1))
+        if window == "day":
+            return (now.replace(hour=0, minute=0, second=0, microsecond=0)
+                    + timedelta(days=1))
+        # month: first instant of the next calendar month
+        first = now.replace(day=1, hour=0, minute=0, second=0, microsecond=0)
+        return (first + timedelta(days=32)).replace(day=1)
+
+    def get_quota(self, agent_id: str) -> ResourceQuota:
+        """Fetch an agent's quota; all-unlimited if none is set."""
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                "SELECT quota FROM agent_quotas WHERE agent_id = ?", (agent_id,)
+            ).fetchone()
+            if not row:
+                return ResourceQuota()
+            return ResourceQuota.from_dict(json.loads(row[0]))
+        finally:
+            conn.close()
+
+    def set_quota(self, agent_id: str, quota: ResourceQuota,
+                  changed_by: str = "system", reason: str = None) -> dict:
+        """
+        Persist an agent's quota. Takes effect immediately — the next
+        budget/outbound check reads the new values. Every change lands
+        in quota_audit_log with the old and new quota side by side.
+        """
+        conn = self._connect()
+        try:
+            old_row = conn.execute(
+                "SELECT quota FROM agent_quotas WHERE agent_id = ?", (agent_id,)
+            ).fetchone()
+            old_quota = old_row[0] if old_row else None
+            new_quota = json.dumps(quota.to_dict())
+            now = self._now()
+
+            conn.execute(
+                "INSERT OR REPLACE INTO agent_quotas (agent_id, quota, updated_at) VALUES (?, ?, ?)",
+                (agent_id, new_quota, now),
+            )
+            conn.execute(
+                """INSERT INTO quota_audit_log
+                   (agent_id, old_quota, new_quota, changed_by, reason, changed_at)
+                   VALUES (?, ?, ?, ?, ?, ?)""",
+                (agent_id, old_quota, new_quota, changed_by, reason, now),
+            )
+            conn.commit()
+            log.info(f"[QUOTA] {agent_id} quota updated by {changed_by}")
+            return {"agent_id": agent_id, "quota": quota.to_dict(), "changed_by": changed_by}
+        finally:
+            conn.close()
+
+    def grant_override(self, agent_id: str, multiplier: float,
+                       hours: float = 6.0, granted_by: str = "operator",
+                       reason: str = None) -> dict:
+        """
+        Temporarily scale every limit in an agent's quota (e.g. 2.0 for
+        the next 6 hours) — the mid-incident unblock that doesn't need a
+        config edit and restart. The grant is its own audit record and
+        expires on its own; one active override per agent (a new grant
+        supersedes the old one).
+        """
+        if multiplier <= 0:
+            return {"error": "multiplier must be positive"}
+        now = datetime.now(timezone.utc)
+        expires_at = (now + timedelta(hours=hours)).isoformat()
+        conn = self._connect()
+        try:
+            conn.execute(
+                "UPDATE budget_overrides SET revoked_at = ? "
+                "WHERE agent_id = ? AND revoked_at IS NULL",
+                (now.isoformat(), agent_id),
+            )
+            cursor = conn.execute(
+                """INSERT INTO budget_overrides
+                   (agent_id, multiplier, granted_by, reason, granted_at, expires_at)
+                   VALUES (?, ?, ?, ?, ?, ?)""",
+                (agent_id, multiplier, granted_by, reason,
+                 now.isoformat(), expires_at),
+            )
+            conn.commit()
+            override_id = cursor.lastrowid
+        finally:
+            conn.close()
+        log.warning(f"[QUOTA] {agent_id} budget override: ×{multiplier} "
+                    f"until {expires_at} (by {granted_by}"
+                    + (f", {reason}" if reason else "") + ")")
+        return {"override_id": override_id, "agent_id": agent_id,
+                "multiplier": multiplier, "expires_at": expires_at,
+                "granted_by": granted_by, "reason": reason}
+
+    def active_override(self, agent_id: str) -> dict:
+        """The unexpired, unrevoked override for an agent, or None."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            row = conn.execute(
+                """SELECT * FROM budget_overrides
+                   WHERE agent_id = ? AND revoked_at IS NULL AND expires_at > ?
+                   ORDER BY id DESC LIMIT 1""",
+                (agent_id, self._now()),
+            ).fetchone()
+            return dict(row) if row else None
+        finally:
+            conn.close()
+
+    def revoke_override(self, agent_id: str, revoked_by: str = "operator") -> dict:
+        """End an agent's active override early."""
+        conn = self._connect()
+        try:
+            revoked = conn.execute(
+                "UPDATE budget_overrides SET revoked_at = ? "
+                "WHERE agent_id = ? AND revoked_at IS NULL AND expires_at > ?",
+                (self._now(), agent_id, self._now()),
+            ).rowcount
+            conn.commit()
+        finally:
+            conn.close()
+        if revoked:
+            log.info(f"[QUOTA] {agent_id} budget override revoked by {revoked_by}")
+        return {"agent_id": agent_id, "revoked": bool(revoked)}
+
+    def override_history(self, agent_id: str = None, limit: int = 50) -> list:
+        """Override grants (the audit trail), newest first."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            query = "SELECT * FROM budget_overrides WHERE 1=1"
+            params = []
+            if agent_id:
+                query += " AND agent_id = ?"
+                params.append(agent_id)
+            query += " ORDER BY id DESC LIMIT ?"
+            params.append(limit)
+            return [dict(r) for r in conn.execute(query, params).fetchall()]
+        finally:
+            conn.close()
+
+    def check_budget(self, agent_id: str, usage_store,
+                     model: str = None) -> dict:
+        """
+        Evaluate every token and USD limit in the agent's quota against
+        current spend (served from the usage rollups), returning each
+        check plus whichever is most constrained — the number a scheduler
+        should throttle on. Every check carries remaining headroom and
+        when its window resets, so the same structure answers both "may
+        I" and "when can I again". All-unlimited quotas come back
+        allowed with no checks. With a target `model`, any per-model
+        caps for it are evaluated too — so routing to the capped model
+        can be denied while the agent's cheap-model traffic continues.
+        """
+        quota = self.get_quota(agent_id)
+        override = self.active_override(agent_id)
+        multiplier = override["multiplier"] if override else 1.0
+        hour = datetime.now(timezone.utc).strftime("%Y-%m-%dT%H")
+
+        hourly = usage_store.query_daily_breakdown(
+            agent_id=agent_id, since=hour, granularity="hourly", limit=1)
+        daily = usage_store.query_agent_daily(agent_id)
+        monthly = usage_store.query_agent_monthly(agent_id)
+        spent_hour_usd = hourly[0]["cost_usd"] if hourly else 0.0
+
+        now = datetime.now(timezone.utc)
+        dimensions = [
+            ("tokens", "day", daily["tokens"], quota.max_tokens_per_day, None),
+            ("tokens", "month", monthly["tokens"], quota.max_tokens_per_month, None),
+            ("cost_usd", "hour", spent_hour_usd, quota.max_cost_usd_per_hour, None),
+            ("cost_usd", "day", daily["cost_usd"], quota.max_cost_usd_per_day, None),
+            ("cost_usd", "month", monthly["cost_usd"], quota.max_cost_usd_per_month, None),
+        ]
+        model_caps = (quota.per_model or {}).get(model) if model else None
+        if model_caps:
+            model_daily = usage_store.query_agent_model_daily(agent_id, model)
+            dimensions.extend([
+                ("tokens", "day", model_daily["tokens"],
+                 model_caps.get("max_tokens_per_day"), model),
+                ("cost_usd", "day", model_daily["cost_usd"],
+                 model_caps.get("max_cost_usd_per_day"), model),
+            ])
+
+        checks = []
+        for dimension, window, spent, limit, capped_model in dimensions:
+            if limit is None:
+                continue
+            limit = limit * multiplier  # temporary override, if any
+            resets_at = self._window_resets_at(now, window)
+            check = {
+                "dimension": dimension,
+                "window": window,
+                "spent": spent,
+                "limit": limit,
+                "remaining": max(limit - spent, 0),
+                "used_pct": round(100.0 * spent / limit, 1) if limit else 100.0,
+                "resets_at": resets_at.isoformat(),
+                "seconds_to_reset": int((resets_at - now).total_seconds()),
+            }
+            if capped_model:
+                check["model"] = capped_model
+            checks.append(check)
+
+        most_constrained = max(checks, key=lambda c: c["used_pct"], default=None)
+        result = {
+            "agent_id": agent_id,
+            "allowed": all(c["used_pct"] < 100.0 for c in checks),
+            "checks": checks,
+            "most_constrained": most_constrained,
+            "unlimited": not checks,
+        }
+        if override:
+            result["override"] = {
+                "multiplier": multiplier,
+                "expires_at": override["expires_at"],
+                "granted_by": override["granted_by"],
+            }
+        return result
+
+    def agents_with_quotas(self) -> list:
+        """Every agent that has a quota record — the set worth watching
+        for exhaustion and recovery."""
+        conn = self._connect()
+        try:
+            return [r[0] for r in conn.execute(
+                "SELECT agent_id FROM agent_quotas ORDER BY agent_id"
+            ).fetchall()]
+        finally:
+            conn.close()
+
+    def quota_audit(self, agent_id: str = None, limit: int = 100) -> list:
+        """Quota change history, newest first."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            query = "SELECT * FROM quota_audit_log WHERE 1=1"
+            params = []
+            if agent_id:
+                query += " AND agent_id = ?"
+                params.append(agent_id)
+            query += " ORDER BY id DESC LIMIT ?"
+            params.append(limit)
+            entries = [dict(r) for r in conn.execute(query, params).fetchall()]
+            for entry in entries:
+                entry["old_quota"] = json.loads(entry["old_quota"]) if entry["old_quota"] else None
+                entry["new_quota"] = json.loads(entry["new_quota"])
+            return entries
+        finally:
+            conn.close()
+
+    def check_and_record_outbound(self, agent_id: str) -> dict:
+        """
+        Enforce max_outbound_messages_per_day for one send attempt.
+
+        Increments the counter when allowed. Returns:
+          {"allowed": bool, "count": int, "limit": int|None, "warning": bool}
+        `warning` is True exactly once, when crossing 80% of the quota.
+        """
+        quota = self.get_quota(agent_id)
+        limit = quota.max_outbound_messages_per_day
+        today = self._today()
+        conn = self._connect()
+        try:
+            conn.execute("BEGIN IMMEDIATE")
+            row = conn.execute(
+                "SELECT count, warned FROM outbound_counters WHERE agent_id = ? AND day = ?",
+                (agent_id, today),
+            ).fetchone()
+            count, warned = row if row else (0, 0)
+
+            if limit is not None and count >= limit:
+                conn.execute("COMMIT")
+                log.warning(f"[QUOTA] {agent_id} outbound BLOCKED: {count}/{limit} today")
+                return {"allowed": False, "count": count, "limit": limit, "warning": False}
+
+            count += 1
+            warning = False
+            if limit is not None and not warned and count >= limit * WARNING_THRESHOLD:
+                warning = True
+                warned = 1
+                log.warning(f"[QUOTA] {agent_id} outbound at {count}/{limit} (80% warning)")
+
+            conn.execute(
+                """INSERT OR REPLACE INTO outbound_counters (agent_id, day, count, warned)
+                   VALUES (?, ?, ?, ?)""",
+                (agent_id, today, count, warned),
+            )
+            conn.execute("COMMIT")
+            return {"allowed": True, "count": count, "limit": limit, "warning": warning}
+        except Exception:
+            conn.execute("ROLLBACK")
+            raise
+        finally:
+            conn.close()
+
+    def outbound_status(self, agent_id: str) -> dict:
+        """Today's outbound counter vs quota for an agent."""
+        quota = self.get_quota(agent_id)
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                "SELECT count FROM outbound_counters WHERE agent_id = ? AND day = ?",
+                (agent_id, self._today()),
+            ).fetchone()
+            return {
+                "agent_id": agent_id,
+                "day": self._today(),
+                "outbound_today": row[0] if row else 0,
+                "max_outbound_messages_per_day": quota.max_outbound_messages_per_day,
+            }
+        finally:
+            conn.close()
+
+
+__all__ = ["ResourceQuota", "QuotaManager", "WARNING_THRESHOLD"]
@@ -0,0 +1,202 @@
+#!/usr/bin/env python3
+"""
+Per-Agent Resource Telemetry for Leviathan Super-Brain
+======================================================
+Attributes OS resource usage — CPU time and peak memory of tool
+subprocesses — to the agent that spawned them, so "the kernel is using
+8GB" has a name attached. Tool handlers measure each child via
+rusage deltas and record a sample here; the aggregates answer "which
+agent burned the CPU this week".
+
+Optional enforcement hooks off ResourceQuota:
+  max_cpu_seconds_per_day — further tool subprocesses are denied once an
+      agent's children have consumed this much CPU today
+  max_tool_rss_kb — applied as an address-space rlimit on the child
+      before exec, so a runaway process is killed by the kernel instead
+      of taking the host down
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import resource
+import os
+import logging
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+
+# Samples older than this are pruned opportunistically on write
+RESOURCE_SAMPLE_RETENTION_DAYS = int(
+    os.environ.get("RESOURCE_SAMPLE_RETENTION_DAYS", "14"))
+
+log = logging.getLogger("resource_monitor")
+
+
+def child_rusage() -> tuple:
+    """
+    Snapshot of cumulative child-process usage for this process:
+    (cpu_seconds, max_rss_kb). Take one before and one after a blocking
+    subprocess call; the CPU delta is attributable to that child.
+    ru_maxrss is a high-water mark over ALL children, so per-call it is
+    an upper bound, not an exact figure.
+    """
+    ru = resource.getrusage(resource.RUSAGE_CHILDREN)
+    return (ru.ru_utime + ru.ru_stime, ru.ru_maxrss)
+
+
+def rss_limiter(max_rss_kb: int):
+    """
+    A preexec_fn that caps the child's address space. RLIMIT_AS
+    overshoots RSS (it counts mappings, not resident pages) but it is
+    the limit the kernel will actually enforce without a cgroup.
+    """
+    def _apply():
+        limit = max_rss_kb * 1024
+        resource.setrlimit(resource.RLIMIT_AS, (limit, limit))
+    return _apply
+
+
+class ResourceMonitor:
+    """SQLite-backed per-agent resource samples and aggregates."""
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self._writes = 0
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS resource_samples (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    agent_id TEXT NOT NULL,
+                    source TEXT NOT NULL DEFAULT 'shell',
+                    cpu_seconds REAL NOT NULL DEFAULT 0,
+                    peak_rss_kb INTEGER NOT NULL DEFAULT 0,
+                    duration_ms INTEGER,
+                    created_at TEXT NOT NULL
+                )
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_resource_agent_time
+                ON resource_samples (agent_id, created_at)
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _now() -> str:
+        return datetime.now(timezone.utc).isoformat()
+
+    def record_sample(self, agent_id: str, cpu_seconds: float,
+                      peak_rss_kb: int, source: str = "shell",
+                      duration_ms: int = None):
+        """Persist one attributed sample (typically one tool subprocess)."""
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT INTO resource_samples
+                   (agent_id, source, cpu_seconds, peak_rss_kb, duration_ms, created_at)
+                   VALUES (?, ?, ?, ?, ?, ?)""",
+                (agent_id, source, round(cpu_seconds, 4), int(peak_rss_kb),
+                 duration_ms, self._now()),
+            )
+            conn.commit()
+        finally:
+            conn.close()
+        self._writes += 1
+        if self._writes % 500 == 0:
+            self.prune()
+
+    def agent_usage(self, agent_id: str, since: str = None) -> dict:
+        """CPU/memory totals for one agent (?since= ISO cutoff)."""
+        conn = self._connect()
+        try:
+            query = """SELECT COUNT(*) AS samples,
+                              SUM(cpu_seconds) AS cpu_seconds,
+                              MAX(peak_rss_kb) AS peak_rss_kb
+                       FROM resource_samples WHERE agent_id = ?"""
+            params = [agent_id]
+            if since:
+                query += " AND created_at >= ?"
+                params.append(since)
+            row = conn.execute(query, params).fetchone()
+            return {
+                "agent_id": agent_id,
+                "since": since,
+                "samples": row[0],
+                "cpu_seconds": round(row[1] or 0, 2),
+                "peak_rss_kb": row[2] or 0,
+            }
+        finally:
+            conn.close()
+
+    def summary(self, since: str = None) -> list:
+        """Per-agent resource totals, heaviest CPU consumer first."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            query = """SELECT agent_id, COUNT(*) AS samples,
+                              SUM(cpu_seconds) AS cpu_seconds,
+                              MAX(peak_rss_kb) AS peak_rss_kb
+                       FROM resource_samples WHERE 1=1"""
+            params = []
+            if since:
+                query += " AND created_at >= ?"
+                params.append(since)
+            query += " GROUP BY agent_id ORDER BY cpu_seconds DESC"
+            rows = [dict(r) for r in conn.execute(query, params).fetchall()]
+            for row in rows:
+                row["cpu_seconds"] = round(row["cpu_seconds"] or 0, 2)
+            return rows
+        finally:
+            conn.close()
+
+    def check_cpu_quota(self, agent_id: str, quota) -> dict:
+        """
+        Evaluate max_cpu_seconds_per_day against the agent's CPU burn
+        since UTC midnight. Same shape as the gateway outbound check so
+        tool handlers can deny uniformly.
+        """
+        limit = getattr(quota, "max_cpu_seconds_per_day", None)
+        if limit is None:
+            return {"allowed": True, "spent": None, "limit": None}
+        today = datetime.now(timezone.utc).strftime("%Y-%m-%d")
+        spent = self.agent_usage(agent_id, since=today)["cpu_seconds"]
+        allowed = spent < limit
+        if not allowed:
+            log.warning(f"[RESOURCE] {agent_id} CPU quota exhausted: "
+                        f"{spent:.1f}s / {limit}s today")
+        return {"allowed": allowed, "spent": spent, "limit": limit}
+
+    def prune(self, days: int = RESOURCE_SAMPLE_RETENTION_DAYS) -> dict:
+        """Drop samples past the retention window."""
+        cutoff = datetime.now(timezone.utc).strftime("%Y-%m-%d")
+        conn = self._connect()
+        try:
+            deleted = conn.execute(
+                "DELETE FROM resource_samples WHERE created_at < date(?, ?)",
+                (cutoff, f"-{days} days"),
+            ).rowcount
+            conn.commit()
+        finally:
+            conn.close()
+        if deleted:
+            log.info(f"[RESOURCE] Pruned {deleted} samples older than {days}d")
+        return {"deleted": deleted, "retention_days": days}
+
+
+__all__ = ["ResourceMonitor", "child_rusage", "rss_limiter",
+           "RESOURCE_SAMPLE_RETENTION_DAYS"]
@@ -0,0 +1,231 @@
+#!/usr/bin/env python3
+"""
+Declarative Scenario Runner for Leviathan Super-Brain
+=====================================================
+End-to-end agent tests described in YAML instead of code: an inbound
+message, a scripted provider (the model's turns are canned, so no API
+spend and no flakiness), the tool calls the agent is expected to make,
+and a regex the final reply must match. Each scenario runs against an
+ephemeral kernel — a throwaway SQLite database with its own
+ToolRegistry — so runs never touch production data.
+
+Scenario file shape (one scenario per file, *.yaml under SCENARIOS_DIR):
+
+  name: weather-lookup
+  agent_id: scout
+  tools:
+    - name: get_weather
+      result: {temp_c: 21}
+  message: "what's the weather in Berlin?"
+  provider_script:
+    - tool_calls:
+        - {tool: get_weather, args: {city: Berlin}}
+    - reply: "It's 21°C in Berlin right now."
+  expect:
+    tool_calls: [get_weather]
+    reply_pattern: "21°C.*Berlin"
+
+Run from the API (/scenarios/run) or the command line:
+  python3 scenario_runner.py [scenarios_dir]
+
+Author: Leviathan DevOps
+"""
+
+import os
+import re
+import json
+import time
+import logging
+import tempfile
+from datetime import datetime, timezone
+
+from tool_registry import ToolRegistry
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+SCENARIOS_DIR = os.environ.get("SCENARIOS_DIR", "/data/scenarios")
+
+# Safety valve against a provider script that loops tool calls forever
+SCENARIO_MAX_TURNS = int(os.environ.get("SCENARIO_MAX_TURNS", "20"))
+
+log = logging.getLogger("scenario_runner")
+
+
+def _load_yaml(path: str) -> dict:
+    try:
+        import yaml
+    except ImportError:
+        raise RuntimeError("Scenario files require PyYAML (pip install pyyaml)")
+    with open(path) as f:
+        return yaml.safe_load(f)
+
+
+class ScriptedProvider:
+    """
+    Stands in for the model: plays back the scenario's provider_script
+    one turn at a time. A turn is either {'tool_calls': [...]} — the
+    "model" wants tools run — or {'reply': "..."} — the final answer.
+    """
+
+    def __init__(self, script: list):
+        self.script = list(script or [])
+        self.cursor = 0
+
+    def next_turn(self) -> dict:
+        if self.cursor >= len(self.script):
+            return {"reply": ""}  # script exhausted: empty final reply
+        turn = self.script[self.cursor]
+        self.cursor += 1
+        return turn
+
+
+class EphemeralKernel:
+    """
+    The minimal kernel a scenario needs: a fresh ToolRegistry on a
+    throwaway database, with the scenario's tools declared, bound and
+    backed by canned-result handlers that log every invocation.
+    """
+
+    def __init__(self, scenario: dict):
+        fd, self.db_path = tempfile.mkstemp(suffix=".db", prefix="scenario-")
+        os.close(fd)
+        self.agent_id = scenario.get("agent_id", "scenario-agent")
+        self.tool_calls = []  # (tool, args) in invocation order
+        self.tool_registry = ToolRegistry(self.db_path)
+        names = []
+        for tool in scenario.get("tools") or []:
+            name = tool["name"]
+            names.append(name)
+            self.tool_registry.declare(
+                name, description=tool.get("description", "scenario mock"))
+            self.tool_registry.register_handler(
+                name, self._mock_handler(name, tool.get("result", {}),
+                                         tool.get("error")))
+        if names:
+            self.tool_registry.bind(self.agent_id, names)
+
+    def _mock_handler(self, name: str, result, error):
+        def handler(args: dict, context: dict) -> dict:
+            self.tool_calls.append({"tool": name, "args": args})
+            if error:
+                return {"error": error}
+            return result if isinstance(result, dict) else {"value": result}
+        return handler
+
+    def close(self):
+        for suffix in ("", "-wal", "-shm"):
+            try:
+                os.unlink(self.db_path + suffix)
+            except OSError:
+                pass
+
+
+class ScenarioRunner:
+    """Loads YAML scenarios and runs each against an ephemeral kernel."""
+
+    def __init__(self, scenarios_dir: str = SCENARIOS_DIR):
+        self.scenarios_dir = scenarios_dir
+
+    def load_scenarios(self) -> list:
+        """Every *.yaml / *.yml under scenarios_dir, sorted by filename."""
+        scenarios = []
+        if not os.path.isdir(self.scenarios_dir):
+            return scenarios
+        for fname in sorted(os.listdir(self.scenarios_dir)):
+            if not fname.endswith((".yaml", ".yml")):
+                continue
+            path = os.path.join(self.scenarios_dir, fname)
+            try:
+                scenario = _load_yaml(path)
+                scenario.setdefault("name", os.path.splitext(fname)[0])
+                scenarios.append(scenario)
+            except Exception as e:
+                scenarios.append({"name": fname, "load_error": str(e)})
+        return scenarios
+
+    def run_scenario(self, scenario: dict) -> dict:
+        """
+        Execute one scenario: feed the message to the scripted provider,
+        run each requested tool call through the registry (binding checks
+        and breakers apply, same as production), then check the expected
+        tool-call sequence and reply pattern.
+        """
+        name = scenario.get("name", "unnamed")
+        if "load_error" in scenario:
+            return {"name": name, "passed": False,
+                    "failures": [f"could not load: {scenario['load_error']}"]}
+        started = time.monotonic()
+        kernel = EphemeralKernel(scenario)
+        provider = ScriptedProvider(scenario.get("provider_script"))
+        reply = ""
+        failures = []
+        try:
+            # The inbound message opens the exchange; scripted turns then
+            # alternate tool rounds until the provider replies.
+            for _ in range(SCENARIO_MAX_TURNS):
+                turn = provider.next_turn()
+                if "reply" in turn:
+                    reply = turn["reply"]
+                    break
+                for call in turn.get("tool_calls", []):
+                    outcome = kernel.tool_registry.execute(
+                        kernel.agent_id, call["tool"], call.get("args") or {})
+                    if "error" in outcome:
+                        failures.append(f"tool '{call['tool']}' failed: "
+                                        f"{outcome['error']}")
+            else:
+                failures.append(f"no reply after {SCENARIO_MAX_TURNS} turns")
+
+            expect = scenario.get("expect") or {}
+            expected_calls = expect.get("tool_calls")
+            if expected_calls is not None:
+                actual = [c["tool"] for c in kernel.tool_calls]
+                if actual != expected_calls:
+                    failures.append(f"tool calls {actual} != expected "
+                                    f"{expected_calls}")
+            pattern = expect.get("reply_pattern")
+            if pattern and not re.search(pattern, reply):
+                failures.append(f"reply {reply!r} does not match "
+                                f"/{pattern}/")
+        finally:
+            kernel.close()
+        duration_ms = int((time.monotonic() - started) * 1000)
+        result = {
+            "name": name,
+            "passed": not failures,
+            "failures": failures,
+            "tool_calls": kernel.tool_calls,
+            "reply": reply,
+            "duration_ms": duration_ms,
+        }
+        log.info(f"[SCENARIO] {name}: "
+                 f"{'PASS' if result['passed'] else 'FAIL ' + '; '.join(failures)}")
+        return result
+
+    def run_all(self) -> dict:
+        """Run every scenario in the directory; the summary a CI job or
+        the /scenarios/run endpoint reports."""
+        results = [self.run_scenario(s) for s in self.load_scenarios()]
+        passed = sum(1 for r in results if r["passed"])
+        return {
+            "scenarios_dir": self.scenarios_dir,
+            "total": len(results),
+            "passed": passed,
+            "failed": len(results) - passed,
+            "ran_at": datetime.now(timezone.utc).isoformat(),
+            "results": results,
+        }
+
+
+if __name__ == "__main__":
+    import sys
+    logging.basicConfig(level=logging.INFO)
+    runner = ScenarioRunner(sys.argv[1] if len(sys.argv) > 1 else SCENARIOS_DIR)
+    report = runner.run_all()
+    print(json.dumps(report, indent=2))
+    sys.exit(0 if report["failed"] == 0 and report["total"] > 0 else 1)
+
+
+__all__ = ["ScenarioRunner", "EphemeralKernel", "ScriptedProvider",
+           "SCENARIOS_DIR"]
@@ -0,0 +1,111 @@
+#!/usr/bin/env python3
+"""
+Schedule Pause Control for Leviathan Super-Brain
+================================================
+Pauses an agent's *scheduled* activity (cron firings, trigger-driven
+automation, reminders) without disabling the agent — interactive gateway
+conversations keep working. Separate from a full disable: we often want
+to silence the noisy automation while still being able to chat with the
+agent.
+
+Every scheduler/daemon that fires work on behalf of an agent must call
+`is_paused(agent_id)` before firing.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import os
+import logging
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+
+log = logging.getLogger("schedule_control")
+
+
+class SchedulePauseManager:
+    """SQLite-backed registry of agents whose schedules are paused."""
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS paused_schedules (
+                    agent_id TEXT PRIMARY KEY,
+                    paused_by TEXT,
+                    reason TEXT,
+                    paused_at TEXT NOT NULL
+                )
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    def pause_schedules(self, agent_id: str, paused_by: str = "owner", reason: str = None) -> dict:
+        """Stop cron and trigger firings for an agent. Interactive chat unaffected."""
+        now = datetime.now(timezone.utc).isoformat()
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT OR REPLACE INTO paused_schedules (agent_id, paused_by, reason, paused_at)
+                   VALUES (?, ?, ?, ?)""",
+                (agent_id, paused_by, reason, now),
+            )
+            conn.commit()
+            log.info(f"[PAUSE] Schedules paused for {agent_id} by {paused_by}")
+            return {"agent_id": agent_id, "schedules_paused": True, "paused_at": now}
+        finally:
+            conn.close()
+
+    def resume_schedules(self, agent_id: str, resumed_by: str = "owner") -> dict:
+        """Resume cron and trigger firings for an agent."""
+        conn = self._connect()
+        try:
+            removed = conn.execute(
+                "DELETE FROM paused_schedules WHERE agent_id = ?", (agent_id,)
+            ).rowcount
+            conn.commit()
+            if removed:
+                log.info(f"[PAUSE] Schedules resumed for {agent_id} by {resumed_by}")
+            return {"agent_id": agent_id, "schedules_paused": False, "was_paused": removed > 0}
+        finally:
+            conn.close()
+
+    def is_paused(self, agent_id: str) -> bool:
+        """Checked by schedulers before firing anything for this agent."""
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                "SELECT 1 FROM paused_schedules WHERE agent_id = ?", (agent_id,)
+            ).fetchone()
+            return row is not None
+        finally:
+            conn.close()
+
+    def list_paused(self) -> list:
+        """All agents with paused schedules."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            return [dict(r) for r in conn.execute(
+                "SELECT * FROM paused_schedules ORDER BY paused_at"
+            ).fetchall()]
+        finally:
+            conn.close()
+
+
+__all__ = ["SchedulePauseManager"]
@@ -0,0 +1,191 @@
+#!/usr/bin/env python3
+"""
+Embedding-Based Skill Router for Leviathan Super-Brain
+======================================================
+Routes inbound messages on shared channels to the best-matching agent by
+comparing the message against each agent's declared skill descriptions
+in vector space. Dispatch goes to the top agent when its cosine score
+clears the confidence threshold; anything mushier lands on the fallback
+agent instead of a random specialist.
+
+Embeddings follow the kernel's zero-dependency bias: a hashed
+bag-of-words vector by default, upgraded transparently to the Gemini
+embedding API when GEMINI_API_KEY is set (same rules-first/model-second
+split as the message classifier — remote failures degrade to local).
+
+Author: Leviathan DevOps
+"""
+
+import os
+import re
+import math
+import time
+import hashlib
+import logging
+import threading
+
+import requests
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+GEMINI_API_KEY = os.environ.get("GEMINI_API_KEY", "")
+GEMINI_EMBED_URL = ("https://generativelanguage.googleapis.com/v1beta/"
+                    "models/text-embedding-004:embedContent")
+
+# Minimum cosine score for a confident dispatch; below this the fallback
+# agent gets the message. Hashed bag-of-words scores run lower than API
+# embeddings, so the default tracks which mode is active.
+ROUTER_CONFIDENCE_THRESHOLD = float(os.environ.get(
+    "ROUTER_CONFIDENCE_THRESHOLD", "0.35" if GEMINI_API_KEY else "0.25"))
+ROUTER_FALLBACK_AGENT = os.environ.get("ROUTER_FALLBACK_AGENT", "")
+
+# How long cached agent vectors stay fresh before re-reading the registry
+ROUTER_REFRESH_SECONDS = int(os.environ.get("ROUTER_REFRESH_SECONDS", "300"))
+
+# Wide enough that unrelated words rarely share a hash bucket
+LOCAL_EMBED_DIM = 2048
+
+log = logging.getLogger("skill_router")
+
+_WORD_RE = re.compile(r"[a-z0-9']+")
+
+# Words too common to carry routing signal
+_STOPWORDS = frozenset(
+    "a an and are as at be by can could do for from has have how i in is it "
+    "me my of on or please so that the this to was we what when will with "
+    "would you your".split()
+)
+
+
+def _stem(word: str) -> str:
+    """Crude suffix stripping so 'meetings' matches 'meeting' — enough
+    for routing, nowhere near a real stemmer."""
+    for suffix in ("ings", "ing", "ers", "er", "es", "s", "ed"):
+        if word.endswith(suffix) and len(word) - len(suffix) >= 3:
+            return word[: -len(suffix)]
+    return word
+
+
+def local_embed(text: str) -> list:
+    """Hashed bag-of-words vector — no model, no dependency, stable."""
+    vec = [0.0] * LOCAL_EMBED_DIM
+    for word in _WORD_RE.findall((text or "").lower()):
+        if word in _STOPWORDS or len(word) < 2:
+            continue
+        word = _stem(word)
+        bucket = int(hashlib.sha1(word.encode()).hexdigest(), 16) % LOCAL_EMBED_DIM
+        vec[bucket] += 1.0
+    norm = math.sqrt(sum(v * v for v in vec))
+    return [v / norm for v in vec] if norm else vec
+
+
+def gemini_embed(text: str) -> list:
+    """Gemini embedding API; raises on any failure so callers can degrade."""
+    resp = requests.post(
+        f"{GEMINI_EMBED_URL}?key={GEMINI_API_KEY}",
+        json={"model": "models/text-embedding-004",
+              "content": {"parts": [{"text": text[:8000]}]}},
+        timeout=10,
+    )
+    resp.raise_for_status()
+    return resp.json()["embedding"]["values"]
+
+
+def cosine(a: list, b: list) -> float:
+    if len(a) != len(b):
+        return 0.0
+    dot = sum(x * y for x, y in zip(a, b))
+    na = math.sqrt(sum(x * x for x in a))
+    nb = math.sqrt(sum(y * y for y in b))
+    return dot / (na * nb) if na and nb else 0.0
+
+
+class SkillRouter:
+    """Matches messages to registered agents via skill-description vectors."""
+
+    def __init__(self, registry, fallback_agent: str = ROUTER_FALLBACK_AGENT,
+                 confidence_threshold: float = ROUTER_CONFIDENCE_THRESHOLD):
+        self.registry = registry
+        self.fallback_agent = fallback_agent
+        self.confidence_threshold = confidence_threshold
+        self._lock = threading.Lock()
+        self._vectors = {}  # agent_id → {"vector": [...], "doc": str, "name": str}
+        self._refreshed_at = 0.0
+
+    def _embed(self, text: str) -> list:
+        if GEMINI_API_KEY:
+            try:
+                return gemini_embed(text)
+            except Exception as e:
+                log.warning(f"[ROUTER] Remote embedding failed, using local: {e}")
+        return local_embed(text)
+
+    @staticmethod
+    def _skill_docs(agent: dict) -> list:
+        """One routing document per skill: the name (dots split into
+        words) plus its description. Per-skill vectors keep a broad agent
+        from drowning out its own best match."""
+        docs = []
+        for skill in agent.get("skills", []):
+            doc = skill["skill"].replace(".", " ").replace("_", " ")
+            if skill.get("description"):
+                doc += " " + skill["description"]
+            docs.append(doc)
+        return docs
+
+    def refresh(self, force: bool = False) -> int:
+        """Re-embed every registered agent's skill descriptions. Cheap to
+        call — a no-op while the cache is fresh."""
+        with self._lock:
+            if not force and time.time() - self._refreshed_at < ROUTER_REFRESH_SECONDS:
+                return len(self._vectors)
+            vectors = {}
+            for listing in self.registry.list_agents():
+                agent = self.registry.get_agent(listing["agent_id"])
+                if "error" in agent:
+                    continue
+                docs = self._skill_docs(agent)
+                if not docs:
+                    continue
+                vectors[agent["agent_id"]] = {
+                    "vectors": [self._embed(doc) for doc in docs],
+                    "name": agent["name"],
+                }
+            self._vectors = vectors
+            self._refreshed_at = time.time()
+            log.info(f"[ROUTER] Indexed {len(vectors)} agents' skill descriptions")
+            return len(vectors)
+
+    def route(self, message: str) -> dict:
+        """
+        Pick the agent for a message. Returns the full decision —
+        scores per candidate, the threshold, and whether the fallback
+        was used — so routing stays debuggable from the API.
+        """
+        self.refresh()
+        query = self._embed(message or "")
+        with self._lock:
+            scored = sorted(
+                ({"agent_id": agent_id, "name": entry["name"],
+                  "score": round(max(cosine(query, v) for v in entry["vectors"]), 4)}
+                 for agent_id, entry in self._vectors.items()),
+                key=lambda c: c["score"], reverse=True,
+            )
+        decision = {
+            "threshold": self.confidence_threshold,
+            "candidates": scored[:5],
+            "fallback_used": False,
+            "agent_id": None,
+        }
+        if scored and scored[0]["score"] >= self.confidence_threshold:
+            decision["agent_id"] = scored[0]["agent_id"]
+            decision["confidence"] = scored[0]["score"]
+        elif self.fallback_agent:
+            decision["agent_id"] = self.fallback_agent
+            decision["confidence"] = scored[0]["score"] if scored else 0.0
+            decision["fallback_used"] = True
+        return decision
+
+
+__all__ = ["SkillRouter", "local_embed", "cosine"]
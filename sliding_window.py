@@ -0,0 +1,183 @@
+#!/usr/bin/env python3
+"""
+Sliding-Window Rate Limiter for Leviathan Super-Brain
+=====================================================
+The old hourly budget reset all counters the moment the window turned
+over, so every daemon that had been blocked fired at once (thundering
+herd), and it disagreed with the calendar-hour queries the quota store
+runs. This limiter keeps a timestamped event log and always answers
+against "the last N seconds", so capacity frees up gradually as old
+events age out — no reset cliff — and calls, tokens and cost all share
+the exact same window semantics.
+
+Everything is in memory: the window describes the last hour of this
+process, so a restart starting from a clean slate is the correct
+behaviour (durable accounting lives in the usage store).
+
+Author: Leviathan DevOps
+"""
+
+import threading
+import time
+import logging
+from collections import deque
+
+log = logging.getLogger("sliding_window")
+
+
+class SlidingWindowLimiter:
+    """
+    Thread-safe limiter over a rolling window. Each recorded event
+    carries its tokens and cost; a check sums live events plus the
+    proposed call and denies if any configured dimension would go over.
+    A denial reports how long until enough old events expire for the
+    answer to change — callers sleep that long instead of polling the
+    top of the hour.
+    """
+
+    def __init__(self, window_seconds: int = 3600, max_calls: int = None,
+                 max_tokens: int = None, max_cost_usd: float = None):
+        self.window_seconds = window_seconds
+        self.max_calls = max_calls
+        self.max_tokens = max_tokens
+        self.max_cost_usd = max_cost_usd
+        self._events = deque()  # (monotonic_ts, tokens, cost_usd)
+        self._lock = threading.Lock()
+        self.denied_total = 0
+
+    def _prune_locked(self, now: float):
+        cutoff = now - self.window_seconds
+        while self._events and self._events[0][0] <= cutoff:
+            self._events.popleft()
+
+    def _totals_locked(self) -> dict:
+        return {
+            "calls": len(self._events),
+            "tokens": sum(e[1] for e in self._events),
+            "cost_usd": round(sum(e[2] for e in self._events), 6),
+        }
+
+    def _retry_after_locked(self, now: float, over_by: dict) -> float:
+        """Seconds until enough of the oldest events expire to clear
+        every exceeded dimension. Over an empty window this is 0 — the
+        proposed call alone is too big and waiting won't help."""
+        need_calls = over_by.get("calls", 0)
+        need_tokens = over_by.get("tokens", 0)
+        need_cost = over_by.get("cost_usd", 0.0)
+        freed_calls, freed_tokens, freed_cost = 0, 0, 0.0
+        for ts, tokens, cost in self._events:
+            if (freed_calls >= need_calls and freed_tokens >= need_tokens
+                    and freed_cost >= need_cost):
+                break
+            freed_calls += 1
+            freed_tokens += tokens
+            freed_cost += cost
+            expires_in = ts + self.window_seconds - now
+        else:
+            if (freed_calls < need_calls or freed_tokens < need_tokens
+                    or freed_cost < need_cost):
+                return 0.0
+            if not self._events:
+                return 0.0
+        return round(max(expires_in, 0.0), 1)
+
+    def _evaluate(self, tokens: int, cost_usd: float) -> dict:
+        with self._lock:
+            now = time.monotonic()
+            self._prune_locked(now)
+            totals = self._totals_locked()
+            over_by = {}
+            if self.max_calls is not None and totals["calls"] + 1 > self.max_calls:
+                over_by["calls"] = totals["calls"] + 1 - self.max_calls
+            if self.max_tokens is not None and totals["tokens"] + tokens > self.max_tokens:
+                over_by["tokens"] = totals["tokens"] + tokens - self.max_tokens
+            if self.max_cost_usd is not None and totals["cost_usd"] + cost_usd > self.max_cost_usd:
+                over_by["cost_usd"] = totals["cost_usd"] + cost_usd - self.max_cost_usd
+            if not over_by:
+                return {"allowed": True, "window": totals}
+            retry_after = self._retry_after_locked(now, over_by)
+            return {
+                "allowed": False,
+                "reason": "window limit: " + ", ".join(sorted(over_by)),
+                "window": totals,
+                "retry_after_seconds": retry_after,
+            }
+
+    def check(self, tokens: int = 0, cost_usd: float = 0.0) -> dict:
+        """Would a call of this size fit right now? Read-only — pair
+        with record() after the call actually happens."""
+        verdict = self._evaluate(tokens, cost_usd)
+        if not verdict["allowed"]:
+            self.denied_total += 1
+        return verdict
+
+    def throttle_delay(self, tokens: int = 0, cost_usd: float = 0.0,
+                       threshold: float = 0.5,
+                       max_delay_seconds: float = 60.0) -> float:
+        """
+        Burn-rate smoothing: how long to delay this call so consumption
+        spreads across the window instead of front-loading it. Under
+        `threshold` utilization the answer is 0; past it the delay ramps
+        linearly, reaching max_delay_seconds at a full window. Fully
+        over the limit, the delay is however long until enough old
+        events age out (capped at max_delay_seconds) — a slowed call,
+        not a refused one.
+        """
+        verdict = self._evaluate(tokens, cost_usd)
+        if not verdict["allowed"]:
+            retry = verdict["retry_after_seconds"]
+            return min(retry if retry > 0 else max_delay_seconds,
+                       max_delay_seconds)
+        u = self.utilization()
+        if u <= threshold:
+            return 0.0
+        return round((u - threshold) / (1.0 - threshold) * max_delay_seconds, 1)
+
+    def record(self, tokens: int = 0, cost_usd: float = 0.0):
+        """Log a completed call; it counts against the window for the
+        next window_seconds and then silently ages out."""
+        with self._lock:
+            now = time.monotonic()
+            self._prune_locked(now)
+            self._events.append((now, tokens, cost_usd))
+
+    def utilization(self) -> float:
+        """Worst-dimension usage as a fraction of its limit (0.0–1.0+),
+        for 'only do optional work under 50% budget' style gates."""
+        with self._lock:
+            self._prune_locked(time.monotonic())
+            totals = self._totals_locked()
+        worst = 0.0
+        if self.max_calls:
+            worst = max(worst, totals["calls"] / self.max_calls)
+        if self.max_tokens:
+            worst = max(worst, totals["tokens"] / self.max_tokens)
+        if self.max_cost_usd:
+            worst = max(worst, totals["cost_usd"] / self.max_cost_usd)
+        return worst
+
+    def status(self) -> dict:
+        with self._lock:
+            now = time.monotonic()
+            self._prune_locked(now)
+            totals = self._totals_locked()
+            oldest_expires_in = (
+                round(self._events[0][0] + self.window_seconds - now, 1)
+                if self._events else None)
+        return {
+            "window_seconds": self.window_seconds,
+            "calls": totals["calls"],
+            "tokens": totals["tokens"],
+            "cost_usd": totals["cost_usd"],
+            "limits": {
+                "max_calls": self.max_calls,
+                "max_tokens": self.max_tokens,
+                "max_cost_usd": self.max_cost_usd,
+            },
+            "utilization": round(self.utilization(), 3),
+            "oldest_event_expires_in_seconds": oldest_expires_in,
+            "denied_total": self.denied_total,
+        }
+
+
+__all__ = ["SlidingWindowLimiter"]
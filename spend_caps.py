@@ -0,0 +1,202 @@
+#!/usr/bin/env python3
+"""
+Hard Monthly Spend Caps for Leviathan Super-Brain
+=================================================
+Beyond the soft warnings and the manual freeze switch: hard monthly caps
+at the kernel (global) and tenant levels. Checks run against the usage
+store's monthly rollups (query_global_monthly / query_tenant_monthly);
+once a cap is reached new LLM calls are blocked until an admin issues an
+explicit unlock for that month, which is recorded with who and why.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import os
+import logging
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+
+VALID_SCOPES = ("global", "tenant")
+
+log = logging.getLogger("spend_caps")
+
+
+class SpendCapManager:
+    """Monthly cap configuration + per-month admin unlocks."""
+
+    def __init__(self, usage_store, db_path: str = DB_PATH):
+        self.usage_store = usage_store
+        self.db_path = db_path
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS spend_caps (
+                    scope_type TEXT NOT NULL,
+                    scope_id TEXT NOT NULL DEFAULT '',
+                    monthly_cap_usd REAL NOT NULL,
+                    set_by TEXT,
+                    updated_at TEXT NOT NULL,
+                    PRIMARY KEY (scope_type, scope_id)
+                )
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS spend_cap_unlocks (
+                    scope_type TEXT NOT NULL,
+                    scope_id TEXT NOT NULL DEFAULT '',
+                    month TEXT NOT NULL,
+                    unlocked_by TEXT,
+                    reason TEXT,
+                    unlocked_at TEXT NOT NULL,
+                    PRIMARY KEY (scope_type, scope_id, month)
+                )
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _now() -> str:
+        return datetime.now(timezone.utc).isoformat()
+
+    @staticmethod
+    def _month() -> str:
+        return datetime.now(timezone.utc).strftime("%Y-%m")
+
+    def set_cap(self, scope_type: str, monthly_cap_usd: float,
+                scope_id: str = "", set_by: str = None) -> dict:
+        if scope_type not in VALID_SCOPES:
+            return {"error": f"scope must be one of {', '.join(VALID_SCOPES)}"}
+        if scope_type == "tenant" and not scope_id:
+            return {"error": "tenant scope requires 'scope_id'"}
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT OR REPLACE INTO spend_caps
+                   (scope_type, scope_id, monthly_cap_usd, set_by, updated_at)
+                   VALUES (?, ?, ?, ?, ?)""",
+                (scope_type, scope_id, float(monthly_cap_usd), set_by, self._now()),
+            )
+            conn.commit()
+            log.info(f"[CAPS] {scope_type}{':' + scope_id if scope_id else ''} "
+                     f"capped at ${monthly_cap_usd:.2f}/month")
+            return {"scope_type": scope_type, "scope_id": scope_id,
+                    "monthly_cap_usd": float(monthly_cap_usd)}
+        finally:
+            conn.close()
+
+    def _cap_for(self, scope_type: str, scope_id: str = "") -> float:
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                "SELECT monthly_cap_usd FROM spend_caps "
+                "WHERE scope_type = ? AND scope_id = ?",
+                (scope_type, scope_id),
+            ).fetchone()
+            return row[0] if row else None
+        finally:
+            conn.close()
+
+    def _unlocked(self, scope_type: str, scope_id: str = "") -> bool:
+        conn = self._connect()
+        try:
+            return conn.execute(
+                "SELECT 1 FROM spend_cap_unlocks "
+                "WHERE scope_type = ? AND scope_id = ? AND month = ?",
+                (scope_type, scope_id, self._month()),
+            ).fetchone() is not None
+        finally:
+            conn.close()
+
+    def admin_unlock(self, scope_type: str, scope_id: str = "",
+                     unlocked_by: str = None, reason: str = None) -> dict:
+        """Lift a reached cap for the current month only — the cap applies
+        again when the month rolls over."""
+        if scope_type not in VALID_SCOPES:
+            return {"error": f"scope must be one of {', '.join(VALID_SCOPES)}"}
+        month = self._month()
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT OR REPLACE INTO spend_cap_unlocks
+                   (scope_type, scope_id, month, unlocked_by, reason, unlocked_at)
+                   VALUES (?, ?, ?, ?, ?, ?)""",
+                (scope_type, scope_id, month, unlocked_by, reason, self._now()),
+            )
+            conn.commit()
+            log.warning(f"[CAPS] {scope_type}{':' + scope_id if scope_id else ''} "
+                        f"unlocked for {month} by {unlocked_by} ({reason})")
+            return {"scope_type": scope_type, "scope_id": scope_id,
+                    "month": month, "unlocked_by": unlocked_by}
+        finally:
+            conn.close()
+
+    def check(self, tenant_id: str = None) -> dict:
+        """
+        Enforcement check for a new LLM call. Returns
+        {"blocked": bool, "reason": ..., "global": {...}, "tenant": {...}}.
+        """
+        result = {"blocked": False, "reason": None}
+
+        global_cap = self._cap_for("global")
+        if global_cap is not None:
+            spent = self.usage_store.query_global_monthly()
+            result["global"] = {"cap_usd": global_cap,
+                               "spent_usd": round(spent["cost_usd"], 4),
+                               "unlocked": self._unlocked("global")}
+            if spent["cost_usd"] >= global_cap and not result["global"]["unlocked"]:
+                result["blocked"] = True
+                result["reason"] = (f"global monthly cap reached "
+                                    f"(${spent['cost_usd']:.2f}/${global_cap:.2f})")
+                return result
+
+        if tenant_id:
+            tenant_cap = self._cap_for("tenant", tenant_id)
+            if tenant_cap is not None:
+                spent = self.usage_store.query_tenant_monthly(tenant_id)
+                result["tenant"] = {"cap_usd": tenant_cap,
+                                    "spent_usd": round(spent["cost_usd"], 4),
+                                    "unlocked": self._unlocked("tenant", tenant_id)}
+                if spent["cost_usd"] >= tenant_cap and not result["tenant"]["unlocked"]:
+                    result["blocked"] = True
+                    result["reason"] = (f"tenant '{tenant_id}' monthly cap reached "
+                                        f"(${spent['cost_usd']:.2f}/${tenant_cap:.2f})")
+        return result
+
+    def status(self) -> dict:
+        """All configured caps with current-month spend, for health/metrics."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            caps = [dict(r) for r in conn.execute(
+                "SELECT * FROM spend_caps ORDER BY scope_type, scope_id"
+            ).fetchall()]
+        finally:
+            conn.close()
+        any_blocked = False
+        for cap in caps:
+            if cap["scope_type"] == "global":
+                spent = self.usage_store.query_global_monthly()["cost_usd"]
+            else:
+                spent = self.usage_store.query_tenant_monthly(cap["scope_id"])["cost_usd"]
+            cap["spent_usd"] = round(spent, 4)
+            cap["unlocked_this_month"] = self._unlocked(cap["scope_type"], cap["scope_id"])
+            cap["blocked"] = spent >= cap["monthly_cap_usd"] and not cap["unlocked_this_month"]
+            any_blocked = any_blocked or cap["blocked"]
+        return {"month": self._month(), "any_blocked": any_blocked, "caps": caps}
+
+
+__all__ = ["SpendCapManager"]
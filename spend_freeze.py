@@ -0,0 +1,178 @@
+#!/usr/bin/env python3
+"""
+Spending Freeze Switch for Leviathan Super-Brain
+================================================
+Kill switch that immediately blocks all new paid LLM calls — globally,
+per tenant, or per agent — while free-of-charge operations (queueing,
+auto-replies, free-tier models) keep working. Freeze state is persisted
+in SQLite so it survives restarts, and is surfaced in health checks.
+
+Usage:
+  freeze_spending("global")                  — stop everything
+  freeze_spending("agent", "agent-123")      — stop one agent
+  freeze_spending("tenant", "acme")          — stop a tenant
+  is_frozen(agent_id=.., tenant_id=..)       — checked before every paid call
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import os
+import logging
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+
+VALID_SCOPES = ("global", "tenant", "agent")
+
+log = logging.getLogger("spend_freeze")
+
+
+class SpendFreeze:
+    """
+    SQLite-backed spending freeze state.
+
+    Table: spend_freezes — one active row per frozen scope. Unfreezing
+    deletes the row; history is kept in spend_freeze_log.
+    """
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        """Create freeze tables if they don't exist."""
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS spend_freezes (
+                    scope_type TEXT NOT NULL,
+                    scope_id TEXT NOT NULL DEFAULT '',
+                    reason TEXT,
+                    frozen_by TEXT,
+                    frozen_at TEXT NOT NULL,
+                    PRIMARY KEY (scope_type, scope_id)
+                )
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS spend_freeze_log (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    action TEXT NOT NULL,
+                    scope_type TEXT NOT NULL,
+                    scope_id TEXT,
+                    reason TEXT,
+                    actor TEXT,
+                    at TEXT NOT NULL
+                )
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _now() -> str:
+        return datetime.now(timezone.utc).isoformat()
+
+    def freeze_spending(self, scope_type: str, scope_id: str = "", reason: str = None,
+                        frozen_by: str = "owner") -> dict:
+        """Freeze a scope. scope_type ∈ {global, tenant, agent}."""
+        if scope_type not in VALID_SCOPES:
+            return {"error": f"Invalid scope: {scope_type}. Use global/tenant/agent"}
+        if scope_type != "global" and not scope_id:
+            return {"error": f"scope_id required for scope '{scope_type}'"}
+
+        now = self._now()
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT OR REPLACE INTO spend_freezes
+                   (scope_type, scope_id, reason, frozen_by, frozen_at)
+                   VALUES (?, ?, ?, ?, ?)""",
+                (scope_type, scope_id, reason, frozen_by, now),
+            )
+            conn.execute(
+                """INSERT INTO spend_freeze_log (action, scope_type, scope_id, reason, actor, at)
+                   VALUES ('freeze', ?, ?, ?, ?, ?)""",
+                (scope_type, scope_id, reason, frozen_by, now),
+            )
+            conn.commit()
+            log.warning(f"[FREEZE] Spending FROZEN for {scope_type}:{scope_id or '*'} by {frozen_by}")
+            return {"frozen": True, "scope_type": scope_type, "scope_id": scope_id,
+                    "reason": reason, "frozen_at": now}
+        finally:
+            conn.close()
+
+    def unfreeze_spending(self, scope_type: str, scope_id: str = "", actor: str = "owner") -> dict:
+        """Lift a freeze on a scope."""
+        if scope_type not in VALID_SCOPES:
+            return {"error": f"Invalid scope: {scope_type}. Use global/tenant/agent"}
+        conn = self._connect()
+        try:
+            removed = conn.execute(
+                "DELETE FROM spend_freezes WHERE scope_type = ? AND scope_id = ?",
+                (scope_type, scope_id),
+            ).rowcount
+            conn.execute(
+                """INSERT INTO spend_freeze_log (action, scope_type, scope_id, actor, at)
+                   VALUES ('unfreeze', ?, ?, ?, ?)""",
+                (scope_type, scope_id, actor, self._now()),
+            )
+            conn.commit()
+            if removed:
+                log.warning(f"[FREEZE] Spending unfrozen for {scope_type}:{scope_id or '*'} by {actor}")
+            return {"frozen": False, "scope_type": scope_type, "scope_id": scope_id,
+                    "was_frozen": removed > 0}
+        finally:
+            conn.close()
+
+    def is_frozen(self, agent_id: str = None, tenant_id: str = None) -> dict:
+        """
+        Check whether a paid call for this agent/tenant is blocked.
+        Global freeze wins, then tenant, then agent.
+        """
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            checks = [("global", "")]
+            if tenant_id:
+                checks.append(("tenant", tenant_id))
+            if agent_id:
+                checks.append(("agent", agent_id))
+            for scope_type, scope_id in checks:
+                row = conn.execute(
+                    "SELECT * FROM spend_freezes WHERE scope_type = ? AND scope_id = ?",
+                    (scope_type, scope_id),
+                ).fetchone()
+                if row:
+                    return {"frozen": True, **dict(row)}
+            return {"frozen": False}
+        finally:
+            conn.close()
+
+    def status(self) -> dict:
+        """All active freezes, for health checks and dashboards."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            active = [dict(r) for r in conn.execute(
+                "SELECT * FROM spend_freezes ORDER BY frozen_at"
+            ).fetchall()]
+            return {
+                "any_frozen": len(active) > 0,
+                "global_frozen": any(f["scope_type"] == "global" for f in active),
+                "active_freezes": active,
+            }
+        finally:
+            conn.close()
+
+
+__all__ = ["SpendFreeze", "VALID_SCOPES"]
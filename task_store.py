@@ -0,0 +1,411 @@
+#!/usr/bin/env python3
+"""
+Task Delegation Store for Leviathan Super-Brain
+===============================================
+Durable task objects that agents can create and hand to other agents.
+"Agent A asked agent B to do X" becomes a tracked artifact with status
+transitions, ownership history, cron-driven reminders and completion
+events — instead of a message that gets lost in a channel.
+
+Features:
+  1. delegate_task(creator, target, spec, due) — durable handoff
+  2. Enforced status transitions (pending → accepted → in_progress → completed)
+  3. Full ownership history (every reassignment recorded)
+  4. Reminder passes for due/overdue tasks (run from a cron-style daemon)
+  5. Completion events persisted to the task event log
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import uuid
+import logging
+from datetime import datetime, timedelta, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+REMINDER_LEAD_MINUTES = int(os.environ.get("TASK_REMINDER_LEAD_MINUTES", "60"))
+
+# Allowed status transitions. Anything not listed here is rejected.
+VALID_TRANSITIONS = {
+    "pending": ["accepted", "declined", "cancelled"],
+    "accepted": ["in_progress", "cancelled"],
+    "in_progress": ["completed", "blocked", "cancelled"],
+    "blocked": ["in_progress", "cancelled"],
+}
+
+TERMINAL_STATUSES = {"completed", "declined", "cancelled"}
+
+# Agent/task priority levels, best first — same ladder as the message
+# classifier's queues so one vocabulary covers both.
+PRIORITY_RANK = {"urgent": 0, "normal": 1, "low": 2}
+
+log = logging.getLogger("task_store")
+
+
+class TaskStore:
+    """
+    SQLite-backed store for delegated tasks.
+
+    Tables:
+      delegated_tasks       — one row per task (current state)
+      task_ownership_history — every handoff, in order
+      task_events           — created/handoff/status/reminder/completed events
+    """
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        """Create task tables if they don't exist."""
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS delegated_tasks (
+                    task_id TEXT PRIMARY KEY,
+                    spec TEXT NOT NULL,
+                    created_by TEXT NOT NULL,
+                    owner TEXT NOT NULL,
+                    status TEXT NOT NULL DEFAULT 'pending',
+                    due_at TEXT,
+                    reminded_at TEXT,
+                    result TEXT,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    completed_at TEXT
+                )
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS task_ownership_history (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    task_id TEXT NOT NULL,
+                    from_agent TEXT,
+                    to_agent TEXT NOT NULL,
+                    changed_at TEXT NOT NULL,
+                    note TEXT
+                )
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS task_events (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    task_id TEXT NOT NULL,
+                    event TEXT NOT NULL,
+                    agent TEXT,
+                    detail TEXT,
+                    created_at TEXT NOT NULL
+                )
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_tasks_owner_status
+                ON delegated_tasks(owner, status)
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_task_events_task
+                ON task_events(task_id)
+            """)
+            # Additive migration: tasks carry the delegator's priority so
+            # urgent work doesn't queue behind batch jobs
+            try:
+                conn.execute("ALTER TABLE delegated_tasks "
+                             "ADD COLUMN priority TEXT NOT NULL DEFAULT 'normal'")
+            except sqlite3.OperationalError:
+                pass  # column already exists
+            # Priority inheritance: while a high-priority delegation is
+            # open, its lower-priority owner runs boosted (anti-inversion)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS task_priority_boosts (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    task_id TEXT NOT NULL,
+                    agent_id TEXT NOT NULL,
+                    boosted_to TEXT NOT NULL,
+                    inherited_from TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    released_at TEXT
+                )
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_priority_boosts_agent
+                ON task_priority_boosts(agent_id, released_at)
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _now() -> str:
+        return datetime.now(timezone.utc).isoformat()
+
+    def _record_event(self, conn, task_id: str, event: str, agent: str = None, detail: str = None):
+        conn.execute(
+            "INSERT INTO task_events (task_id, event, agent, detail, created_at) VALUES (?, ?, ?, ?, ?)",
+            (task_id, event, agent, detail, self._now()),
+        )
+
+    def delegate_task(self, creator: str, target: str, spec: str, due: str = None,
+                      priority: str = "normal", target_priority: str = "normal") -> dict:
+        """
+        Create a durable task and hand it to `target`. The task carries
+        the delegator's priority; when that outranks the target's own
+        (`target_priority`), the target inherits the higher priority for
+        as long as the task is open — the classic fix for priority
+        inversion, so an urgent agent's delegated work doesn't wait
+        behind the specialist's batch queue.
+
+        Returns the stored task dict.
+        """
+        if priority not in PRIORITY_RANK:
+            priority = "normal"
+        task_id = uuid.uuid4().hex[:16]
+        now = self._now()
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT INTO delegated_tasks
+                   (task_id, spec, created_by, owner, status, due_at, priority, created_at, updated_at)
+                   VALUES (?, ?, ?, ?, 'pending', ?, ?, ?, ?)""",
+                (task_id, spec, creator, target, due, priority, now, now),
+            )
+            conn.execute(
+                """INSERT INTO task_ownership_history (task_id, from_agent, to_agent, changed_at, note)
+                   VALUES (?, NULL, ?, ?, 'initial delegation')""",
+                (task_id, target, now),
+            )
+            self._record_event(conn, task_id, "created", creator, f"delegated to {target}")
+            if PRIORITY_RANK[priority] < PRIORITY_RANK.get(target_priority, 1):
+                conn.execute(
+                    """INSERT INTO task_priority_boosts
+                       (task_id, agent_id, boosted_to, inherited_from, created_at)
+                       VALUES (?, ?, ?, ?, ?)""",
+                    (task_id, target, priority, creator, now),
+                )
+                self._record_event(conn, task_id, "priority_boost", target,
+                                   f"{target_priority} → {priority} (inherited from {creator})")
+                log.info(f"[DELEGATE] {target} boosted to '{priority}' for "
+                         f"task {task_id} (inherited from {creator})")
+            conn.commit()
+            log.info(f"[DELEGATE] {creator} → {target}: task {task_id} ({spec[:60]})")
+            return self.get_task(task_id)
+        finally:
+            conn.close()
+
+    def effective_priority(self, agent_id: str, base: str = "normal") -> dict:
+        """
+        The priority an agent's work (including its LLM calls) should run
+        at right now: its own base, or the best unreleased boost from an
+        open high-priority delegation — whichever ranks higher.
+        """
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            boosts = [dict(r) for r in conn.execute(
+                """SELECT task_id, boosted_to, inherited_from, created_at
+                   FROM task_priority_boosts
+                   WHERE agent_id = ? AND released_at IS NULL
+                   ORDER BY id""", (agent_id,)).fetchall()]
+        finally:
+            conn.close()
+        effective = base if base in PRIORITY_RANK else "normal"
+        for boost in boosts:
+            if PRIORITY_RANK[boost["boosted_to"]] < PRIORITY_RANK[effective]:
+                effective = boost["boosted_to"]
+        return {
+            "agent_id": agent_id,
+            "base_priority": base,
+            "effective_priority": effective,
+            "boosted": effective != base,
+            "active_boosts": boosts,
+        }
+
+    def _release_boosts(self, conn, task_id: str):
+        """Boosts end with the task — terminal status drops the owner
+        back to base priority."""
+        released = conn.execute(
+            "UPDATE task_priority_boosts SET released_at = ? "
+            "WHERE task_id = ? AND released_at IS NULL",
+            (self._now(), task_id),
+        ).rowcount
+        if released:
+            log.info(f"[DELEGATE] Released {released} priority boost(s) "
+                     f"for task {task_id}")
+
+    def transition(self, task_id: str, new_status: str, agent: str, note: str = None, result: str = None) -> dict:
+        """
+        Move a task to a new status, enforcing VALID_TRANSITIONS.
+
+        Returns the updated task, or a dict with 'error' on invalid transition.
+        """
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                "SELECT status FROM delegated_tasks WHERE task_id = ?", (task_id,)
+            ).fetchone()
+            if not row:
+                return {"error": f"Unknown task: {task_id}"}
+
+            current = row[0]
+            if new_status not in VALID_TRANSITIONS.get(current, []):
+                return {
+                    "error": f"Invalid transition: {current} → {new_status}",
+                    "allowed": VALID_TRANSITIONS.get(current, []),
+                }
+
+            now = self._now()
+            completed_at = now if new_status == "completed" else None
+            conn.execute(
+                """UPDATE delegated_tasks
+                   SET status = ?, updated_at = ?, result = COALESCE(?, result),
+                       completed_at = COALESCE(?, completed_at)
+                   WHERE task_id = ?""",
+                (new_status, now, result, completed_at, task_id),
+            )
+            self._record_event(conn, task_id, "status", agent, f"{current} → {new_status}" + (f" ({note})" if note else ""))
+            if new_status == "completed":
+                self._record_event(conn, task_id, "completed", agent, result)
+                log.info(f"[COMPLETE] Task {task_id} completed by {agent}")
+            if new_status in TERMINAL_STATUSES:
+                self._release_boosts(conn, task_id)
+            conn.commit()
+            return self.get_task(task_id)
+        finally:
+            conn.close()
+
+    def reassign(self, task_id: str, new_owner: str, agent: str, note: str = None) -> dict:
+        """Hand a task to a new owner, recording the change in ownership history."""
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                "SELECT owner, status FROM delegated_tasks WHERE task_id = ?", (task_id,)
+            ).fetchone()
+            if not row:
+                return {"error": f"Unknown task: {task_id}"}
+            old_owner, status = row
+            if status in TERMINAL_STATUSES:
+                return {"error": f"Cannot reassign task in terminal status '{status}'"}
+
+            now = self._now()
+            conn.execute(
+                "UPDATE delegated_tasks SET owner = ?, updated_at = ? WHERE task_id = ?",
+                (new_owner, now, task_id),
+            )
+            conn.execute(
+                """INSERT INTO task_ownership_history (task_id, from_agent, to_agent, changed_at, note)
+                   VALUES (?, ?, ?, ?, ?)""",
+                (task_id, old_owner, new_owner, now, note),
+            )
+            self._record_event(conn, task_id, "handoff", agent, f"{old_owner} → {new_owner}")
+            # An inherited boost follows the task to whoever now owns it
+            conn.execute(
+                "UPDATE task_priority_boosts SET agent_id = ? "
+                "WHERE task_id = ? AND released_at IS NULL",
+                (new_owner, task_id),
+            )
+            conn.commit()
+            log.info(f"[HANDOFF] Task {task_id}: {old_owner} → {new_owner}")
+            return self.get_task(task_id)
+        finally:
+            conn.close()
+
+    def get_task(self, task_id: str) -> dict:
+        """Fetch a task with its ownership history and event log."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            row = conn.execute(
+                "SELECT * FROM delegated_tasks WHERE task_id = ?", (task_id,)
+            ).fetchone()
+            if not row:
+                return {"error": f"Unknown task: {task_id}"}
+            task = dict(row)
+            task["ownership_history"] = [
+                dict(r) for r in conn.execute(
+                    "SELECT from_agent, to_agent, changed_at, note FROM task_ownership_history "
+                    "WHERE task_id = ? ORDER BY id", (task_id,)
+                ).fetchall()
+            ]
+            task["events"] = [
+                dict(r) for r in conn.execute(
+                    "SELECT event, agent, detail, created_at FROM task_events "
+                    "WHERE task_id = ? ORDER BY id", (task_id,)
+                ).fetchall()
+            ]
+            return task
+        finally:
+            conn.close()
+
+    def list_tasks(self, owner: str = None, status: str = None, limit: int = 100) -> list:
+        """List tasks, optionally filtered by owner and/or status."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            query = "SELECT * FROM delegated_tasks WHERE 1=1"
+            params = []
+            if owner:
+                query += " AND owner = ?"
+                params.append(owner)
+            if status:
+                query += " AND status = ?"
+                params.append(status)
+            query += " ORDER BY created_at DESC LIMIT ?"
+            params.append(limit)
+            return [dict(r) for r in conn.execute(query, params).fetchall()]
+        finally:
+            conn.close()
+
+    def run_reminder_pass(self) -> list:
+        """
+        Find open tasks that are due within REMINDER_LEAD_MINUTES (or overdue)
+        and haven't been reminded yet. Marks them reminded and records a
+        'reminder' event. Called from the cron daemon.
+
+        Returns the list of tasks reminded this pass.
+        """
+        now_dt = datetime.now(timezone.utc)
+        horizon = (now_dt + timedelta(minutes=REMINDER_LEAD_MINUTES)).isoformat()
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            due = conn.execute(
+                """SELECT * FROM delegated_tasks
+                   WHERE due_at IS NOT NULL AND due_at <= ?
+                   AND reminded_at IS NULL
+                   AND status NOT IN ('completed', 'declined', 'cancelled')""",
+                (horizon,),
+            ).fetchall()
+
+            reminded = []
+            now = now_dt.isoformat()
+            for row in due:
+                task = dict(row)
+                overdue = task["due_at"] < now
+                conn.execute(
+                    "UPDATE delegated_tasks SET reminded_at = ? WHERE task_id = ?",
+                    (now, task["task_id"]),
+                )
+                self._record_event(
+                    conn, task["task_id"], "reminder", None,
+                    "overdue" if overdue else f"due by {task['due_at']}",
+                )
+                reminded.append(task)
+                log.info(
+                    f"[REMIND] Task {task['task_id']} ({task['owner']}) "
+                    f"{'OVERDUE' if overdue else 'due soon'}: {task['spec'][:60]}"
+                )
+            conn.commit()
+            return reminded
+        finally:
+            conn.close()
+
+
+__all__ = ["TaskStore", "VALID_TRANSITIONS", "TERMINAL_STATUSES",
+           "PRIORITY_RANK"]
@@ -48,6 +48,11 @@ try:
 except ImportError:
     HAS_DMM = False
 
+from workflow_budget import TokenBudgetTracker
+from artifacts import ArtifactStore
+from event_bus import bus as event_bus
+from pricing import PRICING as MODEL_PRICING
+
 logging.basicConfig(level=logging.INFO, format='%(asctime)s [BRAIN] %(levelname)s - %(message)s')
 logger = logging.getLogger(__name__)
 
@@ -1081,16 +1086,8 @@ class T2MemoryAuditor:
 class TokenBudget:
     """Thread-safe token + cost tracker. Prevents runaway builds."""
 
-    # Cost per million tokens (approximate, from provider pricing)
-    COST_PER_M = {
-        'deepseek-chat': {'input': 0.27, 'output': 1.10},
-        'deepseek-reasoner': {'input': 0.55, 'output': 2.19},
-        'claude-opus-4-6': {'input': 15.00, 'output': 75.00},
-        'grok-4-1-fast-reasoning': {'input': 3.00, 'output': 15.00},
-        'gpt-5.3-codex': {'input': 2.00, 'output': 8.00},
-        'google/gemma-3-27b-it': {'input': 0.00, 'output': 0.00},  # FREE
-        'qwen/qwen3-235b-a22b': {'input': 0.00, 'output': 0.00},  # FREE via OpenRouter
-    }
+    # Rates come from the shared pricing module (config-overridable)
+    COST_PER_M = MODEL_PRICING
 
     def __init__(self, daily_cap_usd=20.0, build_cap_usd=15.0):
         self.daily_cap = daily_cap_usd
@@ -1662,6 +1659,10 @@ def call_model(model_key, system_prompt, user_message, max_tokens=None, inject_s
 
 executor = ThreadPoolExecutor(max_workers=5)
 
+# Build outputs persist as named artifacts with run/step lineage — the
+# digest references them instead of being their only copy
+artifact_store = ArtifactStore()
+
 # ─── BUILD GATE — SLASH COMMAND ONLY ─────────────────────────
 # Build pipeline ONLY triggers if the message starts with /build.
 # Everything else defaults to Gemma → DeepSeek fast-path (cheap, low latency).
@@ -1734,6 +1735,11 @@ def run_pipeline(user_message, channel_id=None):
         'stage_detail': [],
     }
 
+    # Step-level budget allocation — each pipeline stage debits its own
+    # allowance so one runaway stage can't starve the rest of the run.
+    # Threshold crossings surface as budget.alert events on the bus.
+    budget_tracker = TokenBudgetTracker(event_bus=event_bus)
+
     def _timed_call(label, model_key, system_prompt, user_msg, max_tok=None):
         """Call a model and record timing + token telemetry.
         Triple-layer injection (SO#9):
@@ -1741,6 +1747,28 @@ def run_pipeline(user_message, channel_id=None):
           Layer 3: Subagent guardrail (injected inside call_model)
           Layer 4: Memory context (injected here)
         All 3 layers fire on EVERY pipeline call regardless of spawn source."""
+        # Step budget gate — skip the call outright if this step (or the
+        # whole run) has exhausted its allocation
+        gate = budget_tracker.check_budget(label)
+        allowed, reason = gate['allowed'], gate['reason']
+        if allowed and gate['throttle_advised']:
+            # Not blocked yet, but the burn rate says we will be soon —
+            # surface it so the pipeline report shows the near-miss
+            logger.warning(f"[BUDGET] {label}: exhaustion forecast in "
+                           f"{gate['forecast']['seconds_to_exhaustion']}s — throttle advised")
+        if not allowed:
+            budget_tracker.mark_blocked(label, reason)
+            result['stage_detail'].append({
+                'agent': label,
+                'model': MODELS[model_key]['model'],
+                'time': '0.00s',
+                'chars': 0,
+                'tokens': {},
+                'preview': f'SKIPPED ({reason})',
+            })
+            result['budget'] = budget_tracker.status_with_forecast()
+            return '', {}
+
         # Layer 2: Pod-level enforcement — auto-derives pod context from label + model_key
         system_prompt = _inject_layer2(system_prompt, label, model_key)
 
@@ -1754,6 +1782,10 @@ def run_pipeline(user_message, channel_id=None):
         text, tok = call_model(model_key, system_prompt, user_msg, max_tok)
         elapsed = time.time() - t0
         _track(result, label, text, tok)
+        if isinstance(tok, dict):
+            budget_tracker.record(label, MODELS[model_key]['model'],
+                                  tok.get('input', 0), tok.get('output', 0))
+        result['budget'] = budget_tracker.status_with_forecast()
         result['stage_detail'].append({
             'agent': label,
             'model': MODELS[model_key]['model'],
@@ -2070,6 +2102,8 @@ def run_pipeline(user_message, channel_id=None):
     # ONE-SHOT MILITARY-GRADE. Real API spend. No second chances.
     # ═══════════════════════════════════════════════════════════════
     result['task_type'] = 'build_heavy'
+    run_id = f"build-{uuid.uuid4().hex[:12]}"
+    result['run_id'] = run_id
     budget.reset_build()  # Fresh build budget counter
     logger.info(f"[BUILD-HEAVY] *** FULL AGI DEVTEAM PIPELINE TRIGGERED *** for: {user_message[:100]}...")
 
@@ -2338,6 +2372,23 @@ def run_pipeline(user_message, channel_id=None):
         else:
             break  # Either approved or max rounds hit
 
+    # ── ARTIFACTS: Persist stage outputs with run/step lineage ──
+    try:
+        if master_prompt:
+            artifact_store.store(run_id, 'master_prompt', 'master-prompt.md', master_prompt)
+        if final_arch:
+            artifact_store.store(run_id, 'architecture', 'architecture.md', final_arch)
+        for part_name, code in production_parts.items():
+            artifact_store.store(run_id, 'production', f'{part_name}-code.md', code)
+        if not production_parts:
+            for part_name, code in prototype_parts.items():
+                artifact_store.store(run_id, 'prototype', f'{part_name}-code.md', code)
+        if verify_text:
+            artifact_store.store(run_id, 'verification', 'verification.md', verify_text)
+        result['artifacts'] = artifact_store.list_for_run(run_id)
+    except Exception as e:
+        logger.warning(f"[ARTIFACT] Failed to persist build outputs for {run_id}: {e}")
+
     # ── MEMORY: Log build to persistent storage ──
     total_tokens = result['tokens']['input'] + result['tokens']['output']
     build_cost = budget.current_build_spend
@@ -2398,6 +2449,12 @@ def run_pipeline(user_message, channel_id=None):
         max_tokens=1500)
 
     result['response'] = delivery_text or production_text
+    # Digest references the persisted copies — the chat message is a view,
+    # not the only home of the build output
+    refs = artifact_store.manifest_lines(run_id)
+    if refs:
+        result['response'] += ("\n\n📦 Artifacts (run " + run_id + "):\n"
+                               + "\n".join(f"  • {line}" for line in refs))
     result['processing_time'] = f"{time.time() - start:.2f}s"
     result['fix_rounds'] = fix_round if 'fix_round' in dir() else 0
     result['budget'] = budget.status()
@@ -0,0 +1,502 @@
+#!/usr/bin/env python3
+"""
+Tool Registry for Leviathan Super-Brain
+=======================================
+Kernel-level registry where tools are declared once — name, input
+schema, required capability, timeout — and agents bind the subset they
+are allowed to use. Bindings are enforced at call time, replacing the
+per-agent copy-pasted tool configs that kept drifting apart.
+
+Declarations persist in SQLite; the Python handler for each tool is
+registered in-process at boot (see server startup), so a declaration
+without a handler is visible but not executable.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import time
+import hashlib
+import logging
+import threading
+from concurrent.futures import ThreadPoolExecutor, TimeoutError as FutureTimeout
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+DEFAULT_TOOL_TIMEOUT_SECONDS = int(os.environ.get("DEFAULT_TOOL_TIMEOUT_SECONDS", "30"))
+DEFAULT_TOOL_PARALLELISM = int(os.environ.get("DEFAULT_TOOL_PARALLELISM", "4"))
+MAX_TOOL_PARALLELISM = int(os.environ.get("MAX_TOOL_PARALLELISM", "16"))
+BREAKER_FAILURE_THRESHOLD = int(os.environ.get("TOOL_BREAKER_FAILURE_THRESHOLD", "5"))
+BREAKER_OPEN_SECONDS = int(os.environ.get("TOOL_BREAKER_OPEN_SECONDS", "60"))
+
+log = logging.getLogger("tool_registry")
+
+
+class CircuitBreaker:
+    """
+    Per-tool circuit breaker. After N consecutive failures/timeouts the
+    breaker opens and calls are short-circuited with a structured
+    "tool unavailable" result; after the cool-off one probe call is let
+    through (half-open) and its outcome closes or re-opens the breaker.
+    """
+
+    def __init__(self, failure_threshold: int = BREAKER_FAILURE_THRESHOLD,
+                 open_seconds: int = BREAKER_OPEN_SECONDS):
+        self.failure_threshold = failure_threshold
+        self.open_seconds = open_seconds
+        self.lock = threading.Lock()
+        self.consecutive_failures = 0
+        self.opened_at = None
+        self.probing = False
+
+    def allow(self):
+        """Returns (allowed: bool, retry_after_seconds: float)."""
+        with self.lock:
+            if self.opened_at is None:
+                return True, 0
+            elapsed = time.time() - self.opened_at
+            if elapsed < self.open_seconds:
+                return False, round(self.open_seconds - elapsed, 1)
+            if self.probing:
+                return False, round(self.open_seconds, 1)
+            self.probing = True  # half-open: one probe at a time
+            return True, 0
+
+    def record(self, success: bool):
+        with self.lock:
+            self.probing = False
+            if success:
+                self.consecutive_failures = 0
+                self.opened_at = None
+            else:
+                self.consecutive_failures += 1
+                if self.consecutive_failures >= self.failure_threshold:
+                    self.opened_at = time.time()
+
+    def state(self) -> dict:
+        with self.lock:
+            if self.opened_at is None:
+                name = "closed"
+            elif time.time() - self.opened_at < self.open_seconds:
+                name = "open"
+            else:
+                name = "half_open"
+            return {"state": name,
+                    "consecutive_failures": self.consecutive_failures,
+                    "failure_threshold": self.failure_threshold,
+                    "opened_at": self.opened_at}
+
+
+class ToolRegistry:
+    """
+    SQLite-backed tool declarations + per-agent bindings, with in-process
+    handler dispatch.
+
+    Tables:
+      tool_declarations   — one row per declared tool
+      agent_tool_bindings — (agent, tool) pairs an agent may call
+    """
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self.handlers = {}  # tool name → callable(args: dict, context: dict) -> dict
+        self.breakers = {}  # tool name → CircuitBreaker
+        self.env_resolver = None  # callable(agent_id) -> dict, set by the kernel
+        self.usage_recorder = None  # callable(tool, agent_id, duration_ms, ok), set by the kernel
+        self.decision_tracer = None  # DecisionTracer, set by the kernel
+        self._breakers_lock = threading.Lock()
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS tool_declarations (
+                    name TEXT PRIMARY KEY,
+                    description TEXT,
+                    schema_json TEXT,
+                    capability TEXT,
+                    timeout_seconds INTEGER NOT NULL DEFAULT 30,
+                    declared_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                )
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS agent_tool_bindings (
+                    agent_id TEXT NOT NULL,
+                    tool_name TEXT NOT NULL,
+                    bound_at TEXT NOT NULL,
+                    PRIMARY KEY (agent_id, tool_name)
+                )
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS agent_tool_settings (
+                    agent_id TEXT PRIMARY KEY,
+                    max_parallel INTEGER NOT NULL DEFAULT 4,
+                    updated_at TEXT NOT NULL
+                )
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS tool_result_cache (
+                    tool_name TEXT NOT NULL,
+                    args_hash TEXT NOT NULL,
+                    result_json TEXT NOT NULL,
+                    cached_at REAL NOT NULL,
+                    PRIMARY KEY (tool_name, args_hash)
+                )
+            """)
+            try:
+                conn.execute(
+                    "ALTER TABLE tool_declarations ADD COLUMN cache_ttl_seconds INTEGER NOT NULL DEFAULT 0"
+                )
+            except sqlite3.OperationalError:
+                pass  # column already present
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _now() -> str:
+        return datetime.now(timezone.utc).isoformat()
+
+    def declare(self, name: str, description: str = None, schema: dict = None,
+                capability: str = None, timeout_seconds: int = DEFAULT_TOOL_TIMEOUT_SECONDS,
+                cache_ttl_seconds: int = 0) -> dict:
+        """Declare (or update) a tool once, for all agents. A non-zero
+        `cache_ttl_seconds` marks the tool idempotent: results are cached
+        by tool+arguments for that long."""
+        now = self._now()
+        conn = self._connect()
+        try:
+            existing = conn.execute(
+                "SELECT declared_at FROM tool_declarations WHERE name = ?", (name,)
+            ).fetchone()
+            declared_at = existing[0] if existing else now
+            conn.execute(
+                """INSERT OR REPLACE INTO tool_declarations
+                   (name, description, schema_json, capability, timeout_seconds,
+                    cache_ttl_seconds, declared_at, updated_at)
+                   VALUES (?, ?, ?, ?, ?, ?, ?, ?)""",
+                (name, description, json.dumps(schema) if schema else None,
+                 capability, timeout_seconds, cache_ttl_seconds, declared_at, now),
+            )
+            conn.commit()
+            log.info(f"[TOOLS] Declared tool '{name}' (capability={capability})")
+            return self.get_tool(name)
+        finally:
+            conn.close()
+
+    def register_handler(self, name: str, handler):
+        """Attach the in-process handler for a declared tool."""
+        self.handlers[name] = handler
+
+    def get_tool(self, name: str) -> dict:
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            row = conn.execute(
+                "SELECT * FROM tool_declarations WHERE name = ?", (name,)
+            ).fetchone()
+            if not row:
+                return {"error": f"Unknown tool: {name}"}
+            tool = dict(row)
+            tool["schema"] = json.loads(tool.pop("schema_json")) if tool.get("schema_json") else None
+            tool["has_handler"] = name in self.handlers
+            return tool
+        finally:
+            conn.close()
+
+    def list_tools(self) -> list:
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            tools = []
+            for row in conn.execute("SELECT * FROM tool_declarations ORDER BY name").fetchall():
+                tool = dict(row)
+                tool["schema"] = json.loads(tool.pop("schema_json")) if tool.get("schema_json") else None
+                tool["has_handler"] = tool["name"] in self.handlers
+                tools.append(tool)
+            return tools
+        finally:
+            conn.close()
+
+    # ── Per-agent bindings ──
+
+    def bind(self, agent_id: str, tool_names: list) -> dict:
+        """Replace an agent's tool bindings with the given subset."""
+        conn = self._connect()
+        try:
+            known = {r[0] for r in conn.execute("SELECT name FROM tool_declarations").fetchall()}
+            unknown = [t for t in tool_names if t not in known]
+            if unknown:
+                return {"error": f"Unknown tools: {', '.join(unknown)}"}
+            now = self._now()
+            conn.execute("DELETE FROM agent_tool_bindings WHERE agent_id = ?", (agent_id,))
+            for tool_name in tool_names:
+                conn.execute(
+                    "INSERT INTO agent_tool_bindings (agent_id, tool_name, bound_at) VALUES (?, ?, ?)",
+                    (agent_id, tool_name, now),
+                )
+            conn.commit()
+            log.info(f"[TOOLS] Bound {len(tool_names)} tools to {agent_id}")
+            return {"agent_id": agent_id, "tools": tool_names}
+        finally:
+            conn.close()
+
+    def bindings(self, agent_id: str) -> list:
+        conn = self._connect()
+        try:
+            return [r[0] for r in conn.execute(
+                "SELECT tool_name FROM agent_tool_bindings WHERE agent_id = ? ORDER BY tool_name",
+                (agent_id,),
+            ).fetchall()]
+        finally:
+            conn.close()
+
+    def is_bound(self, agent_id: str, tool_name: str) -> bool:
+        conn = self._connect()
+        try:
+            return conn.execute(
+                "SELECT 1 FROM agent_tool_bindings WHERE agent_id = ? AND tool_name = ?",
+                (agent_id, tool_name),
+            ).fetchone() is not None
+        finally:
+            conn.close()
+
+    # ── Per-agent execution settings ──
+
+    def set_parallelism(self, agent_id: str, max_parallel: int) -> dict:
+        """Set how many tool calls an agent may run concurrently in a turn."""
+        max_parallel = max(1, min(int(max_parallel), MAX_TOOL_PARALLELISM))
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT OR REPLACE INTO agent_tool_settings
+                   (agent_id, max_parallel, updated_at) VALUES (?, ?, ?)""",
+                (agent_id, max_parallel, self._now()),
+            )
+            conn.commit()
+            return {"agent_id": agent_id, "max_parallel": max_parallel}
+        finally:
+            conn.close()
+
+    def get_parallelism(self, agent_id: str) -> int:
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                "SELECT max_parallel FROM agent_tool_settings WHERE agent_id = ?",
+                (agent_id,),
+            ).fetchone()
+            return row[0] if row else DEFAULT_TOOL_PARALLELISM
+        finally:
+            conn.close()
+
+    # ── Result cache (idempotent tools only) ──
+
+    @staticmethod
+    def _args_hash(args: dict) -> str:
+        return hashlib.sha256(
+            json.dumps(args or {}, sort_keys=True).encode()
+        ).hexdigest()
+
+    def _cache_get(self, tool_name: str, args_hash: str, ttl: int) -> dict:
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                "SELECT result_json, cached_at FROM tool_result_cache "
+                "WHERE tool_name = ? AND args_hash = ?",
+                (tool_name, args_hash),
+            ).fetchone()
+            if row and time.time() - row[1] < ttl:
+                return json.loads(row[0])
+            return None
+        finally:
+            conn.close()
+
+    def _cache_put(self, tool_name: str, args_hash: str, result: dict):
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT OR REPLACE INTO tool_result_cache
+                   (tool_name, args_hash, result_json, cached_at) VALUES (?, ?, ?, ?)""",
+                (tool_name, args_hash, json.dumps(result), time.time()),
+            )
+            conn.commit()
+        except (sqlite3.Error, TypeError) as e:
+            log.warning(f"[TOOLS] Cache write for '{tool_name}' failed: {e}")
+        finally:
+            conn.close()
+
+    # ── Execution (binding enforcement happens here) ──
+
+    def _breaker_for(self, tool_name: str) -> CircuitBreaker:
+        with self._breakers_lock:
+            breaker = self.breakers.get(tool_name)
+            if breaker is None:
+                breaker = self.breakers[tool_name] = CircuitBreaker()
+            return breaker
+
+    def breaker_states(self) -> dict:
+        with self._breakers_lock:
+            breakers = dict(self.breakers)
+        return {name: b.state() for name, b in breakers.items()}
+
+    def _record_usage(self, tool_name: str, agent_id: str, started: float,
+                      ok: bool):
+        """Report one execution (duration, outcome) to the kernel's usage
+        recorder; recorder failures never affect the tool result."""
+        if self.usage_recorder is None:
+            return
+        try:
+            duration_ms = (time.monotonic() - started) * 1000
+            self.usage_recorder(tool_name, agent_id, duration_ms, ok)
+        except Exception as e:
+            log.warning(f"[TOOLS] Usage recorder failed for '{tool_name}': {e}")
+
+    def execute(self, agent_id: str, tool_name: str, args: dict = None) -> dict:
+        """
+        Execute a tool on behalf of an agent. The binding check is the
+        capability enforcement point — an unbound tool is refused no
+        matter what the model asked for. The declared timeout is enforced
+        here, and repeated failures trip the tool's circuit breaker.
+        Each call leaves a decision trace (if a tracer is attached) so
+        "why was this refused" is answerable by trace id.
+        """
+        steps = []
+
+        def traced(outcome: str, result: dict) -> dict:
+            if self.decision_tracer is not None:
+                try:
+                    result["trace_id"] = self.decision_tracer.record(
+                        agent_id, "tool_call", outcome, steps)
+                except Exception as e:
+                    log.warning(f"[TOOLS] Trace write failed for '{tool_name}': {e}")
+            return result
+
+        tool = self.get_tool(tool_name)
+        if "error" in tool:
+            steps.append({"subsystem": "registry", "decision": "unknown tool",
+                          "detail": {"tool": tool_name}})
+            return traced("refused", {"error": tool["error"], "code": "tool_unknown"})
+        if not self.is_bound(agent_id, tool_name):
+            log.warning(f"[TOOLS] {agent_id} tried unbound tool '{tool_name}'")
+            steps.append({"subsystem": "bindings", "decision": "not bound",
+                          "detail": {"tool": tool_name}})
+            return traced("refused",
+                          {"error": f"Tool '{tool_name}' is not bound for this agent",
+                           "code": "tool_not_bound"})
+        steps.append({"subsystem": "bindings", "decision": "bound",
+                      "detail": {"tool": tool_name}})
+        handler = self.handlers.get(tool_name)
+        if handler is None:
+            steps.append({"subsystem": "registry", "decision": "no handler"})
+            return traced("refused",
+                          {"error": f"Tool '{tool_name}' has no handler registered",
+                           "code": "tool_no_handler"})
+
+        ttl = tool.get("cache_ttl_seconds") or 0
+        args_hash = self._args_hash(args) if ttl > 0 else None
+        if ttl > 0:
+            cached = self._cache_get(tool_name, args_hash, ttl)
+            if cached is not None:
+                log.info(f"[TOOLS] Cache hit for '{tool_name}' (ttl {ttl}s)")
+                steps.append({"subsystem": "cache", "decision": "hit",
+                              "detail": {"ttl_seconds": ttl}})
+                return traced("cached", {"tool": tool_name, "result": cached,
+                                         "from_cache": True})
+
+        breaker = self._breaker_for(tool_name)
+        allowed, retry_after = breaker.allow()
+        if not allowed:
+            # Structured fallback the model can reason about instead of
+            # hanging on a tool that keeps timing out.
+            steps.append({"subsystem": "breaker", "decision": "circuit open",
+                          "detail": {"retry_after_seconds": retry_after}})
+            return traced("refused",
+                          {"error": f"Tool '{tool_name}' is temporarily unavailable "
+                                    f"(circuit open, retry in {retry_after}s)",
+                           "code": "tool_unavailable", "tool": tool_name,
+                           "retry_after_seconds": retry_after})
+        steps.append({"subsystem": "breaker", "decision": "closed"})
+
+        timeout = tool.get("timeout_seconds") or DEFAULT_TOOL_TIMEOUT_SECONDS
+        context = {"agent_id": agent_id, "tool": tool}
+        if self.env_resolver is not None:
+            # Per-agent env/secrets — resolved against the calling agent
+            # only, so a shared handler never sees another agent's values.
+            try:
+                context["env"] = self.env_resolver(agent_id) or {}
+            except Exception as e:
+                log.warning(f"[TOOLS] env resolution failed for {agent_id}: {e}")
+                context["env"] = {}
+        pool = ThreadPoolExecutor(max_workers=1, thread_name_prefix=f"tool-{tool_name}")
+        started = time.monotonic()
+        try:
+            future = pool.submit(handler, args or {}, context)
+            result = future.result(timeout=timeout)
+        except FutureTimeout:
+            breaker.record(False)
+            self._record_usage(tool_name, agent_id, started, ok=False)
+            log.warning(f"[TOOLS] '{tool_name}' timed out after {timeout}s for {agent_id}")
+            steps.append({"subsystem": "executor", "decision": "timed out",
+                          "detail": {"timeout_seconds": timeout}})
+            return traced("failed",
+                          {"error": f"Tool '{tool_name}' timed out after {timeout}s",
+                           "code": "tool_timeout", "tool": tool_name})
+        except Exception as e:
+            breaker.record(False)
+            self._record_usage(tool_name, agent_id, started, ok=False)
+            log.error(f"[TOOLS] '{tool_name}' failed for {agent_id}: {e}")
+            steps.append({"subsystem": "executor", "decision": "raised",
+                          "detail": {"error": str(e)}})
+            return traced("failed", {"error": str(e), "code": "tool_failed",
+                                     "tool": tool_name})
+        finally:
+            pool.shutdown(wait=False)
+
+        ok = not (isinstance(result, dict) and "error" in result)
+        breaker.record(ok)
+        self._record_usage(tool_name, agent_id, started, ok=ok)
+        if ttl > 0 and isinstance(result, dict) and "error" not in result:
+            self._cache_put(tool_name, args_hash, result)
+        steps.append({"subsystem": "executor",
+                      "decision": "completed" if ok else "handler error"})
+        return traced("ok" if ok else "failed",
+                      {"tool": tool_name, "result": result})
+
+    def execute_batch(self, agent_id: str, calls: list) -> list:
+        """
+        Execute independent tool calls concurrently, bounded by the
+        agent's parallelism setting. Results come back in call order so
+        the follow-up prompt lines up with what the model asked for.
+
+        `calls` is a list of {"tool": name, "args": {...}} dicts.
+        """
+        if not calls:
+            return []
+        if len(calls) == 1:
+            call = calls[0]
+            return [self.execute(agent_id, call.get("tool", ""), call.get("args"))]
+        workers = min(self.get_parallelism(agent_id), len(calls))
+        log.info(f"[TOOLS] Batch of {len(calls)} calls for {agent_id} "
+                 f"({workers} workers)")
+        with ThreadPoolExecutor(max_workers=workers,
+                                thread_name_prefix=f"tool-{agent_id}") as pool:
+            futures = [pool.submit(self.execute, agent_id,
+                                   call.get("tool", ""), call.get("args"))
+                       for call in calls]
+            return [f.result() for f in futures]
+
+
+__all__ = ["ToolRegistry", "CircuitBreaker"]
@@ -0,0 +1,9 @@
+"""
+Built-in tools for Leviathan Super-Brain
+========================================
+Kernel-provided tool handlers registered into the ToolRegistry at boot.
+Each module exposes a `register(tool_registry)` helper that declares the
+tool and attaches its handler, so server startup stays one-liner-per-tool.
+
+Author: Leviathan DevOps
+"""
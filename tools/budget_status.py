@@ -0,0 +1,181 @@
+#!/usr/bin/env python3
+"""
+Budget Status Tool for Leviathan Super-Brain
+============================================
+Built-in tool surfacing the calling agent's own budget state as
+structured context — percent of the daily quota used (cost and tokens),
+seconds until the window resets, whether spending is frozen, and the
+fallback model that kicks in near the limit — so agents can
+self-moderate ("shorter answer, I'm near my hourly limit") instead of
+discovering the wall by hitting it.
+
+The tool reads only the calling agent's numbers (agent_id comes from the
+execution context, never from args), so it leaks nothing across agents.
+
+Author: Leviathan DevOps
+"""
+
+import os
+import logging
+from datetime import datetime, timedelta, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+# Utilization at which the manifest's fallback model becomes active
+BUDGET_FALLBACK_PCT = float(os.environ.get("BUDGET_FALLBACK_PCT", "90"))
+
+# Adaptive mode: shift the fallback threshold earlier in hours where the
+# agent historically spikes, so the cheap model kicks in before a
+# predictable burst hard-exhausts the budget. The shift is cap-bounded —
+# the threshold never drops below BUDGET_FALLBACK_MIN_PCT.
+BUDGET_FALLBACK_ADAPTIVE = os.environ.get(
+    "BUDGET_FALLBACK_ADAPTIVE", "false").lower() == "true"
+BUDGET_FALLBACK_MIN_PCT = float(os.environ.get("BUDGET_FALLBACK_MIN_PCT", "70"))
+BUDGET_ADAPTIVE_LOOKBACK_DAYS = int(
+    os.environ.get("BUDGET_ADAPTIVE_LOOKBACK_DAYS", "7"))
+
+log = logging.getLogger("tools.budget_status")
+
+BUDGET_STATUS_SCHEMA = {
+    "type": "object",
+    "properties": {},
+    "additionalProperties": False,
+}
+
+
+def _seconds_until_daily_reset() -> int:
+    now = datetime.now(timezone.utc)
+    tomorrow = (now + timedelta(days=1)).replace(hour=0, minute=0,
+                                                 second=0, microsecond=0)
+    return int((tomorrow - now).total_seconds())
+
+
+def adaptive_fallback_pct(usage_store, agent_id: str,
+                          base_pct: float = BUDGET_FALLBACK_PCT,
+                          now: datetime = None) -> dict:
+    """
+    The effective fallback threshold for this agent right now. From the
+    hourly rollups over the lookback window we compare the agent's
+    typical spend in the current hour-of-day against its average hour;
+    an hour that historically burns 2× the average pulls the threshold
+    down proportionally, bounded at BUDGET_FALLBACK_MIN_PCT. Agents
+    with flat or unknown patterns keep the base threshold.
+    """
+    now = now or datetime.now(timezone.utc)
+    since = (now - timedelta(days=BUDGET_ADAPTIVE_LOOKBACK_DAYS)).strftime(
+        "%Y-%m-%dT%H")
+    buckets = usage_store.query_daily_breakdown(
+        agent_id=agent_id, since=since, granularity="hourly",
+        limit=24 * BUDGET_ADAPTIVE_LOOKBACK_DAYS)
+    if len(buckets) < 24:  # not enough history to call anything a spike
+        return {"effective_pct": base_pct, "adapted": False}
+    this_hour = now.strftime("%H")
+    hour_costs = [b["cost_usd"] or 0 for b in buckets
+                  if b["bucket"][11:13] == this_hour]
+    overall_avg = sum(b["cost_usd"] or 0 for b in buckets) / len(buckets)
+    if not hour_costs or overall_avg <= 0:
+        return {"effective_pct": base_pct, "adapted": False}
+    spike_factor = (sum(hour_costs) / len(hour_costs)) / overall_avg
+    if spike_factor <= 1.0:
+        return {"effective_pct": base_pct, "adapted": False,
+                "spike_factor": round(spike_factor, 2)}
+    # A 2× hour uses the full allowed shift; anything beyond is capped
+    shift = min(spike_factor - 1.0, 1.0) * (base_pct - BUDGET_FALLBACK_MIN_PCT)
+    effective = round(max(base_pct - shift, BUDGET_FALLBACK_MIN_PCT), 1)
+    log.info(f"[BUDGET] {agent_id} adaptive fallback: hour {this_hour}h "
+             f"spikes ×{spike_factor:.2f}, threshold {base_pct}% → {effective}%")
+    return {"effective_pct": effective, "adapted": True,
+            "spike_factor": round(spike_factor, 2),
+            "base_pct": base_pct, "floor_pct": BUDGET_FALLBACK_MIN_PCT}
+
+
+def make_handler(quota_manager, usage_store, registry=None, spend_freeze=None,
+                 adaptive: bool = None):
+    """Build the budget_status handler over the kernel's budget stores.
+    `adaptive` overrides BUDGET_FALLBACK_ADAPTIVE (mainly for tests)."""
+    adaptive = BUDGET_FALLBACK_ADAPTIVE if adaptive is None else adaptive
+
+    def handler(args: dict, context: dict) -> dict:
+        agent_id = context.get("agent_id", "")
+        today = datetime.now(timezone.utc).strftime("%Y-%m-%d")
+        spent = {"cost_usd": 0.0, "tokens": 0, "calls": 0}
+        for row in usage_store.agent_totals(agent_id=agent_id, since=today):
+            spent = {
+                "cost_usd": round(row["cost_usd"] or 0.0, 6),
+                "tokens": (row["input_tokens"] or 0) + (row["output_tokens"] or 0),
+                "calls": row["calls"] or 0,
+            }
+
+        quota = quota_manager.get_quota(agent_id)
+        usage = {}
+        worst_pct = 0.0
+        if quota.max_cost_usd_per_day:
+            pct = round(100.0 * spent["cost_usd"] / quota.max_cost_usd_per_day, 1)
+            usage["cost"] = {"spent_usd": spent["cost_usd"],
+                             "cap_usd": quota.max_cost_usd_per_day,
+                             "used_pct": pct}
+            worst_pct = max(worst_pct, pct)
+        if quota.max_tokens_per_day:
+            pct = round(100.0 * spent["tokens"] / quota.max_tokens_per_day, 1)
+            usage["tokens"] = {"spent": spent["tokens"],
+                               "cap": quota.max_tokens_per_day,
+                               "used_pct": pct}
+            worst_pct = max(worst_pct, pct)
+
+        status = {
+            "agent_id": agent_id,
+            "window": "daily",
+            "window_resets_in_seconds": _seconds_until_daily_reset(),
+            "calls_today": spent["calls"],
+            "usage": usage,
+            "used_pct": worst_pct,
+            "unlimited": not usage,
+        }
+
+        if spend_freeze is not None:
+            frozen = spend_freeze.is_frozen(agent_id=agent_id)
+            status["frozen"] = bool(frozen.get("frozen"))
+
+        # Near the cap, the manifest's fallback model (if declared) is
+        # what the scheduler will route new calls to
+        fallback = None
+        if registry is not None:
+            agent = registry.get_agent(agent_id)
+            if "error" not in agent:
+                fallback = agent["manifest"].get("fallback_model")
+        status["fallback_model"] = fallback
+        fallback_pct = BUDGET_FALLBACK_PCT
+        if adaptive:
+            adaptation = adaptive_fallback_pct(usage_store, agent_id)
+            fallback_pct = adaptation["effective_pct"]
+            status["adaptive_fallback"] = adaptation
+        status["fallback_pct"] = fallback_pct
+        status["fallback_active"] = bool(fallback) and worst_pct >= fallback_pct
+        return status
+
+    return handler
+
+
+def register(tool_registry, quota_manager, usage_store,
+             registry=None, spend_freeze=None):
+    """Declare budget_status and attach its handler."""
+    tool_registry.declare(
+        "budget_status",
+        "Your current budget: percent of the daily quota used, time until "
+        "the window resets, freeze state, and whether the fallback model "
+        "is active. Takes no arguments.",
+        BUDGET_STATUS_SCHEMA,
+        capability="budget.read",
+        timeout_seconds=10,
+    )
+    tool_registry.register_handler(
+        "budget_status",
+        make_handler(quota_manager, usage_store,
+                     registry=registry, spend_freeze=spend_freeze),
+    )
+    log.info("[TOOLS] budget_status registered")
+
+
+__all__ = ["register", "make_handler", "adaptive_fallback_pct",
+           "BUDGET_STATUS_SCHEMA"]
@@ -0,0 +1,325 @@
+#!/usr/bin/env python3
+"""
+HTTP Fetch Tool for Leviathan Super-Brain
+=========================================
+Capability-gated web fetch so every agent stops reimplementing fetching
+differently. Features:
+  - GET/POST with egress policy enforcement (host allow/deny patterns),
+    re-checked on every redirect hop; the IP vetted at check time is
+    pinned for the actual connection so DNS rebinding can't swap in a
+    private address between check and connect
+  - Response size caps (streamed, aborted past the cap)
+  - HTML → text extraction for model consumption
+  - On-disk cache keyed by URL+ETag (conditional revalidation)
+  - Per-agent rate limits (sliding one-minute window)
+
+Env:
+  HTTP_FETCH_ALLOW_HOSTS    — comma-separated fnmatch patterns ("" = allow all)
+  HTTP_FETCH_DENY_HOSTS     — comma-separated fnmatch patterns (checked first)
+  HTTP_FETCH_MAX_BYTES      — response size cap (default 1 MiB)
+  HTTP_FETCH_CACHE_DIR      — cache directory (default /data/fetch-cache)
+  HTTP_FETCH_RPM_PER_AGENT  — requests per minute per agent (default 30)
+
+Author: Leviathan DevOps
+"""
+
+import os
+import re
+import json
+import time
+import hashlib
+import fnmatch
+import logging
+import threading
+import ipaddress
+import urllib.parse
+import urllib.request
+import urllib.error
+import socket
+import http.client
+from html.parser import HTMLParser
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+ALLOW_HOSTS = [p.strip() for p in os.environ.get("HTTP_FETCH_ALLOW_HOSTS", "").split(",") if p.strip()]
+DENY_HOSTS = [p.strip() for p in os.environ.get("HTTP_FETCH_DENY_HOSTS", "").split(",") if p.strip()]
+MAX_BYTES = int(os.environ.get("HTTP_FETCH_MAX_BYTES", str(1024 * 1024)))
+CACHE_DIR = os.environ.get("HTTP_FETCH_CACHE_DIR", "/data/fetch-cache")
+RPM_PER_AGENT = int(os.environ.get("HTTP_FETCH_RPM_PER_AGENT", "30"))
+FETCH_TIMEOUT_SECONDS = int(os.environ.get("HTTP_FETCH_TIMEOUT_SECONDS", "20"))
+
+log = logging.getLogger("http_fetch")
+
+_rate_lock = threading.Lock()
+_rate_windows = {}  # agent_id → list of request timestamps (last 60s)
+
+
+class _TextExtractor(HTMLParser):
+    """Strip tags, scripts and styles; keep readable text."""
+
+    SKIP = {"script", "style", "noscript", "template"}
+
+    def __init__(self):
+        super().__init__()
+        self.chunks = []
+        self._skip_depth = 0
+
+    def handle_starttag(self, tag, attrs):
+        if tag in self.SKIP:
+            self._skip_depth += 1
+
+    def handle_endtag(self, tag):
+        if tag in self.SKIP and self._skip_depth:
+            self._skip_depth -= 1
+
+    def handle_data(self, data):
+        if not self._skip_depth and data.strip():
+            self.chunks.append(data.strip())
+
+
+def html_to_text(html: str) -> str:
+    parser = _TextExtractor()
+    try:
+        parser.feed(html)
+    except Exception:
+        pass
+    return re.sub(r"\n{3,}", "\n\n", "\n".join(parser.chunks))
+
+
+def _vet_url(url: str) -> tuple:
+    """Egress-check a URL. Returns (error, pinned_ip): a denial reason
+    and None, or '' and the vetted address the connection must use —
+    connecting to the checked IP (not a fresh resolution) is what makes
+    the check rebinding-proof."""
+    parsed = urllib.parse.urlparse(url)
+    if parsed.scheme not in ("http", "https"):
+        return f"Scheme '{parsed.scheme}' not allowed", None
+    host = parsed.hostname or ""
+    if not host:
+        return "URL has no host", None
+    for pattern in DENY_HOSTS:
+        if fnmatch.fnmatch(host, pattern):
+            return f"Host '{host}' denied by egress policy", None
+    if ALLOW_HOSTS and not any(fnmatch.fnmatch(host, p) for p in ALLOW_HOSTS):
+        return f"Host '{host}' not in egress allowlist", None
+    # Block requests that resolve into private/loopback ranges (SSRF guard)
+    pinned = None
+    try:
+        for info in socket.getaddrinfo(host, None, proto=socket.IPPROTO_TCP):
+            addr = ipaddress.ip_address(info[4][0])
+            if addr.is_private or addr.is_loopback or addr.is_link_local:
+                return f"Host '{host}' resolves to a private address", None
+            if pinned is None:
+                pinned = str(addr)
+    except (socket.gaierror, ValueError):
+        return f"Host '{host}' did not resolve", None
+    if pinned is None:
+        return f"Host '{host}' did not resolve", None
+    return "", pinned
+
+
+def check_egress(url: str) -> str:
+    """Return an error string if the URL violates egress policy, else ''."""
+    error, _ = _vet_url(url)
+    return error
+
+
+class _PinnedHTTPConnection(http.client.HTTPConnection):
+    """HTTPConnection that connects to a pre-vetted IP instead of
+    re-resolving the hostname."""
+
+    def __init__(self, host, pinned_ip=None, **kwargs):
+        super().__init__(host, **kwargs)
+        self._pinned_ip = pinned_ip
+
+    def connect(self):
+        self.sock = socket.create_connection(
+            (self._pinned_ip or self.host, self.port), self.timeout)
+
+
+class _PinnedHTTPSConnection(http.client.HTTPSConnection):
+    """As above for TLS — the socket goes to the pinned IP while SNI and
+    certificate validation still use the real hostname."""
+
+    def __init__(self, host, pinned_ip=None, **kwargs):
+        super().__init__(host, **kwargs)
+        self._pinned_ip = pinned_ip
+
+    def connect(self):
+        sock = socket.create_connection(
+            (self._pinned_ip or self.host, self.port), self.timeout)
+        self.sock = self._context.wrap_socket(sock, server_hostname=self.host)
+
+
+class _PinnedHTTPHandler(urllib.request.HTTPHandler):
+    def __init__(self, pins: dict):
+        super().__init__()
+        self._pins = pins
+
+    def http_open(self, req):
+        host = urllib.parse.urlparse(req.full_url).hostname
+        return self.do_open(
+            lambda h, **kw: _PinnedHTTPConnection(
+                h, pinned_ip=self._pins.get(host), **kw), req)
+
+
+class _PinnedHTTPSHandler(urllib.request.HTTPSHandler):
+    def __init__(self, pins: dict):
+        super().__init__()
+        self._pins = pins
+
+    def https_open(self, req):
+        host = urllib.parse.urlparse(req.full_url).hostname
+        return self.do_open(
+            lambda h, **kw: _PinnedHTTPSConnection(
+                h, pinned_ip=self._pins.get(host), **kw),
+            req, context=self._context)
+
+
+class _EgressRedirectHandler(urllib.request.HTTPRedirectHandler):
+    """Re-run the egress policy on every redirect hop — an allowlisted
+    public host must not be able to 302 us into the metadata service or
+    an internal network."""
+
+    def __init__(self, pins: dict):
+        super().__init__()
+        self._pins = pins
+
+    def redirect_request(self, req, fp, code, msg, headers, newurl):
+        error, pinned_ip = _vet_url(newurl)
+        if error:
+            log.warning(f"[FETCH] Redirect blocked: {newurl} ({error})")
+            raise urllib.error.URLError(
+                f"Redirect blocked by egress policy: {error}")
+        self._pins[urllib.parse.urlparse(newurl).hostname] = pinned_ip
+        return super().redirect_request(req, fp, code, msg, headers, newurl)
+
+
+def _build_opener(url: str, pinned_ip: str) -> urllib.request.OpenerDirector:
+    """An opener whose connections go to vetted IPs only and whose
+    redirects re-enter the egress check."""
+    pins = {urllib.parse.urlparse(url).hostname: pinned_ip}
+    return urllib.request.build_opener(
+        _PinnedHTTPHandler(pins), _PinnedHTTPSHandler(pins),
+        _EgressRedirectHandler(pins))
+
+
+def check_rate_limit(agent_id: str) -> bool:
+    """True if the agent is within its per-minute budget."""
+    now = time.time()
+    with _rate_lock:
+        window = [t for t in _rate_windows.get(agent_id, []) if now - t < 60]
+        if len(window) >= RPM_PER_AGENT:
+            _rate_windows[agent_id] = window
+            return False
+        window.append(now)
+        _rate_windows[agent_id] = window
+        return True
+
+
+def _cache_path(url: str) -> str:
+    return os.path.join(CACHE_DIR, hashlib.sha256(url.encode()).hexdigest() + ".json")
+
+
+def _cache_load(url: str) -> dict:
+    try:
+        with open(_cache_path(url)) as f:
+            return json.load(f)
+    except (OSError, json.JSONDecodeError):
+        return {}
+
+
+def _cache_store(url: str, etag: str, body: str, content_type: str):
+    try:
+        os.makedirs(CACHE_DIR, exist_ok=True)
+        with open(_cache_path(url), "w") as f:
+            json.dump({"url": url, "etag": etag, "body": body,
+                       "content_type": content_type, "cached_at": time.time()}, f)
+    except OSError as e:
+        log.warning(f"[FETCH] Cache write failed for {url}: {e}")
+
+
+def fetch(args: dict, context: dict) -> dict:
+    """
+    Tool handler. args: url (required), method (GET/POST), body, headers,
+    extract_text (default true for HTML).
+    """
+    agent_id = context.get("agent_id", "unknown")
+    url = args.get("url", "")
+    if not url:
+        return {"error": "Missing 'url' argument"}
+
+    policy_error, pinned_ip = _vet_url(url)
+    if policy_error:
+        log.warning(f"[FETCH] Egress denied for {agent_id}: {policy_error}")
+        return {"error": policy_error, "code": "egress_denied"}
+
+    if not check_rate_limit(agent_id):
+        return {"error": f"Rate limit exceeded ({RPM_PER_AGENT}/min)", "code": "rate_limited"}
+
+    method = args.get("method", "GET").upper()
+    if method not in ("GET", "POST"):
+        return {"error": f"Method '{method}' not allowed"}
+
+    headers = {"User-Agent": "Leviathan-Fetch/1.0", **(args.get("headers") or {})}
+    cached = _cache_load(url) if method == "GET" else {}
+    if cached.get("etag"):
+        headers["If-None-Match"] = cached["etag"]
+
+    body = args.get("body")
+    data = body.encode() if isinstance(body, str) else (json.dumps(body).encode() if body else None)
+    req = urllib.request.Request(url, data=data, headers=headers, method=method)
+
+    try:
+        opener = _build_opener(url, pinned_ip)
+        with opener.open(req, timeout=FETCH_TIMEOUT_SECONDS) as resp:
+            raw = resp.read(MAX_BYTES + 1)
+            if len(raw) > MAX_BYTES:
+                return {"error": f"Response exceeds size cap ({MAX_BYTES} bytes)",
+                        "code": "size_cap"}
+            content_type = resp.headers.get("Content-Type", "")
+            text = raw.decode(resp.headers.get_content_charset() or "utf-8", errors="replace")
+            etag = resp.headers.get("ETag", "")
+            status = resp.status
+    except urllib.error.HTTPError as e:
+        if e.code == 304 and cached:
+            log.info(f"[FETCH] Cache hit (304) for {url}")
+            return {"url": url, "status": 200, "from_cache": True,
+                    "content_type": cached.get("content_type", ""),
+                    "body": cached["body"]}
+        return {"error": f"HTTP {e.code}", "status": e.code, "url": url}
+    except (urllib.error.URLError, socket.timeout) as e:
+        return {"error": f"Fetch failed: {e}", "url": url}
+
+    extract = args.get("extract_text", "html" in content_type)
+    if extract and "html" in content_type:
+        text = html_to_text(text)
+
+    if method == "GET" and etag:
+        _cache_store(url, etag, text, content_type)
+
+    return {"url": url, "status": status, "from_cache": False,
+            "content_type": content_type, "bytes": len(raw), "body": text}
+
+
+def register(tool_registry):
+    """Declare the http_fetch tool and attach its handler."""
+    tool_registry.declare(
+        "http_fetch",
+        description="Fetch a URL (GET/POST) with egress policy, size caps, "
+                    "caching and HTML text extraction",
+        schema={"type": "object",
+                "properties": {"url": {"type": "string"},
+                               "method": {"type": "string", "enum": ["GET", "POST"]},
+                               "body": {},
+                               "headers": {"type": "object"},
+                               "extract_text": {"type": "boolean"}},
+                "required": ["url"]},
+        capability="net.fetch",
+        timeout_seconds=FETCH_TIMEOUT_SECONDS,
+    )
+    tool_registry.register_handler("http_fetch", fetch)
+
+
+__all__ = ["fetch", "register", "html_to_text", "check_egress"]
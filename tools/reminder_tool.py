@@ -0,0 +1,121 @@
+#!/usr/bin/env python3
+"""
+Reminder Tool for Leviathan Super-Brain
+=======================================
+First-class `remind(user, when, text)` agents can call directly instead
+of wrapping hand-rolled state. Reminders become one-shot cron entries;
+the dispatch daemon in the server routes each one back through the
+user's preferred gateway at the right time. Supports list/cancel.
+
+`when` accepts a UTC ISO timestamp or a relative form like "in 20m",
+"in 2h", "in 3d".
+
+Author: Leviathan DevOps
+"""
+
+import os
+import re
+import logging
+from datetime import datetime, timedelta, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DEFAULT_GATEWAY = os.environ.get("REMINDER_DEFAULT_GATEWAY", "discord")
+
+log = logging.getLogger("reminder_tool")
+
+_RELATIVE_RE = re.compile(r"^in\s+(\d+)\s*(m|min|minutes?|h|hours?|d|days?)$", re.IGNORECASE)
+_UNIT_SECONDS = {"m": 60, "h": 3600, "d": 86400}
+
+
+def parse_when(when: str) -> str:
+    """Resolve `when` to a UTC ISO timestamp, or raise ValueError."""
+    when = (when or "").strip()
+    match = _RELATIVE_RE.match(when)
+    if match:
+        amount, unit = int(match.group(1)), match.group(2)[0].lower()
+        fire_at = datetime.now(timezone.utc) + timedelta(seconds=amount * _UNIT_SECONDS[unit])
+        return fire_at.isoformat()
+    try:
+        parsed = datetime.fromisoformat(when.replace("Z", "+00:00"))
+    except ValueError:
+        raise ValueError(f"Cannot parse 'when': {when!r} (use ISO timestamp or 'in 20m')")
+    if parsed.tzinfo is None:
+        parsed = parsed.replace(tzinfo=timezone.utc)
+    if parsed <= datetime.now(timezone.utc):
+        raise ValueError(f"'when' is in the past: {when}")
+    return parsed.isoformat()
+
+
+def make_handler(cron_store):
+    """Build the tool handler bound to a CronStore."""
+
+    def handler(args: dict, context: dict) -> dict:
+        action = args.get("action", "remind")
+
+        if action == "remind":
+            user = args.get("user", "")
+            text = args.get("text", "")
+            if not user or not text:
+                return {"error": "Missing 'user' or 'text' argument"}
+            try:
+                fire_at = parse_when(args.get("when", ""))
+            except ValueError as e:
+                return {"error": str(e)}
+            entry = cron_store.add_one_shot(user, fire_at, payload={
+                "type": "reminder",
+                "text": text,
+                "gateway": args.get("gateway", DEFAULT_GATEWAY),
+                "created_by": context.get("agent_id"),
+            })
+            return {"reminder_id": entry["entry_id"], "user": user,
+                    "fire_at": fire_at, "text": text}
+
+        if action == "list":
+            user = args.get("user", "")
+            if not user:
+                return {"error": "Missing 'user' argument"}
+            entries = [e for e in cron_store.list_entries(owner=user)
+                       if e["payload"].get("type") == "reminder"]
+            return {"user": user, "count": len(entries),
+                    "reminders": [{"reminder_id": e["entry_id"],
+                                   "fire_at": e["fire_at"],
+                                   "text": e["payload"].get("text")}
+                                  for e in entries]}
+
+        if action == "cancel":
+            reminder_id = args.get("reminder_id", "")
+            if not reminder_id:
+                return {"error": "Missing 'reminder_id' argument"}
+            result = cron_store.cancel(reminder_id, owner=args.get("user"))
+            if "error" in result:
+                return result
+            return {"reminder_id": reminder_id, "status": "cancelled"}
+
+        return {"error": f"Unknown action: {action}"}
+
+    return handler
+
+
+def register(tool_registry, cron_store):
+    """Declare the reminders tool and attach its handler."""
+    tool_registry.declare(
+        "reminders",
+        description="Create, list and cancel user reminders "
+                    "(remind/list/cancel) backed by the cron store",
+        schema={"type": "object",
+                "properties": {"action": {"type": "string",
+                                          "enum": ["remind", "list", "cancel"]},
+                               "user": {"type": "string"},
+                               "when": {"type": "string"},
+                               "text": {"type": "string"},
+                               "gateway": {"type": "string"},
+                               "reminder_id": {"type": "string"}}},
+        capability="calendar.remind",
+        timeout_seconds=10,
+    )
+    tool_registry.register_handler("reminders", make_handler(cron_store))
+
+
+__all__ = ["register", "make_handler", "parse_when"]
@@ -0,0 +1,314 @@
+#!/usr/bin/env python3
+"""
+Shell Tool for Leviathan Super-Brain
+====================================
+Policy-controlled shell execution so ops agents can run scripts without
+getting arbitrary code execution. Commands are parsed to argv and run
+without a shell — composition (`;`, `&&`, `|`, `$(...)`, backticks) is
+rejected outright rather than interpreted, so an allowlisted prefix
+cannot smuggle in a second command. Per-agent policy:
+  - command allowlist (fnmatch on the first token, e.g. "git", "./scripts/*")
+  - deny patterns (regex matched against the whole command line)
+  - working-directory scoping (cwd must stay under the policy root)
+  - timeout cap
+
+The environment is scrubbed to a minimal safe set before execution, and
+every invocation — command, cwd, exit code, duration — lands in the
+shell_audit table.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import subprocess
+import shlex
+import json
+import os
+import re
+import fnmatch
+import logging
+from datetime import datetime, timezone
+
+from resource_monitor import child_rusage, rss_limiter
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+SHELL_MAX_TIMEOUT_SECONDS = int(os.environ.get("SHELL_MAX_TIMEOUT_SECONDS", "120"))
+SHELL_MAX_OUTPUT_BYTES = int(os.environ.get("SHELL_MAX_OUTPUT_BYTES", "65536"))
+
+# Only these env vars survive scrubbing; everything else (tokens, keys,
+# webhook URLs) is withheld from child processes.
+SAFE_ENV_KEYS = ("PATH", "HOME", "LANG", "LC_ALL", "TZ", "TERM")
+
+# Deny patterns applied to every agent, on top of per-agent policy.
+GLOBAL_DENY_PATTERNS = (
+    r"\brm\s+-rf\s+/",
+    r"\bmkfs\b",
+    r":\(\)\s*\{",          # fork bomb
+    r"\bdd\s+.*of=/dev/",
+    r"\bcurl\b.*\|\s*(ba)?sh",
+    r"\bwget\b.*\|\s*(ba)?sh",
+)
+
+log = logging.getLogger("shell_tool")
+
+# Characters that only mean something to a shell. Commands execute
+# argv-style (shell=False), so any of these is an attempt at composition,
+# substitution or redirection — denied, never interpreted.
+_SHELL_METACHAR_RE = re.compile(r"[;&|<>`$(){}\n]")
+
+
+class ShellPolicyStore:
+    """Per-agent shell policies + audit log, SQLite-backed."""
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS shell_policies (
+                    agent_id TEXT PRIMARY KEY,
+                    allowed_commands TEXT NOT NULL,
+                    deny_patterns TEXT,
+                    cwd_root TEXT,
+                    timeout_seconds INTEGER NOT NULL DEFAULT 30,
+                    updated_at TEXT NOT NULL
+                )
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS shell_audit (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    agent_id TEXT NOT NULL,
+                    command TEXT NOT NULL,
+                    cwd TEXT,
+                    exit_code INTEGER,
+                    duration_ms INTEGER,
+                    denied_reason TEXT,
+                    executed_at TEXT NOT NULL
+                )
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _now() -> str:
+        return datetime.now(timezone.utc).isoformat()
+
+    def set_policy(self, agent_id: str, allowed_commands: list,
+                   deny_patterns: list = None, cwd_root: str = None,
+                   timeout_seconds: int = 30) -> dict:
+        timeout_seconds = min(int(timeout_seconds), SHELL_MAX_TIMEOUT_SECONDS)
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT OR REPLACE INTO shell_policies
+                   (agent_id, allowed_commands, deny_patterns, cwd_root, timeout_seconds, updated_at)
+                   VALUES (?, ?, ?, ?, ?, ?)""",
+                (agent_id, json.dumps(allowed_commands),
+                 json.dumps(deny_patterns or []), cwd_root, timeout_seconds, self._now()),
+            )
+            conn.commit()
+            log.info(f"[SHELL] Policy set for {agent_id}: {len(allowed_commands)} allowed commands")
+            return self.get_policy(agent_id)
+        finally:
+            conn.close()
+
+    def get_policy(self, agent_id: str) -> dict:
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            row = conn.execute(
+                "SELECT * FROM shell_policies WHERE agent_id = ?", (agent_id,)
+            ).fetchone()
+            if not row:
+                return {}
+            policy = dict(row)
+            policy["allowed_commands"] = json.loads(policy["allowed_commands"])
+            policy["deny_patterns"] = json.loads(policy["deny_patterns"] or "[]")
+            return policy
+        finally:
+            conn.close()
+
+    def audit(self, agent_id: str, command: str, cwd: str = None,
+              exit_code: int = None, duration_ms: int = None,
+              denied_reason: str = None):
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT INTO shell_audit
+                   (agent_id, command, cwd, exit_code, duration_ms, denied_reason, executed_at)
+                   VALUES (?, ?, ?, ?, ?, ?, ?)""",
+                (agent_id, command, cwd, exit_code, duration_ms, denied_reason, self._now()),
+            )
+            conn.commit()
+        finally:
+            conn.close()
+
+    def recent_audit(self, agent_id: str = None, limit: int = 100) -> list:
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            if agent_id:
+                rows = conn.execute(
+                    "SELECT * FROM shell_audit WHERE agent_id = ? ORDER BY id DESC LIMIT ?",
+                    (agent_id, limit),
+                ).fetchall()
+            else:
+                rows = conn.execute(
+                    "SELECT * FROM shell_audit ORDER BY id DESC LIMIT ?", (limit,)
+                ).fetchall()
+            return [dict(r) for r in rows]
+        finally:
+            conn.close()
+
+
+def check_policy(policy: dict, command: str, cwd: str = None) -> str:
+    """Return a denial reason, or '' if the command passes policy."""
+    if not policy:
+        return "No shell policy configured for this agent"
+
+    if _SHELL_METACHAR_RE.search(command):
+        return ("Shell metacharacters (;, &&, |, $(), `...`, redirection) "
+                "are not allowed — commands run argv-style, not via a shell")
+
+    for pattern in GLOBAL_DENY_PATTERNS:
+        if re.search(pattern, command):
+            return f"Command matches global deny pattern: {pattern}"
+    for pattern in policy.get("deny_patterns", []):
+        try:
+            if re.search(pattern, command):
+                return f"Command matches deny pattern: {pattern}"
+        except re.error:
+            continue
+
+    try:
+        first_token = shlex.split(command)[0]
+    except (ValueError, IndexError):
+        return "Cannot parse command"
+    if not any(fnmatch.fnmatch(first_token, p) for p in policy.get("allowed_commands", [])):
+        return f"Command '{first_token}' not in allowlist"
+
+    cwd_root = policy.get("cwd_root")
+    if cwd_root:
+        resolved = os.path.realpath(cwd or cwd_root)
+        if resolved != os.path.realpath(cwd_root) and \
+                not resolved.startswith(os.path.realpath(cwd_root) + os.sep):
+            return f"cwd '{cwd}' escapes policy root '{cwd_root}'"
+    return ""
+
+
+def scrub_env() -> dict:
+    return {k: v for k, v in os.environ.items() if k in SAFE_ENV_KEYS}
+
+
+def make_handler(policy_store: ShellPolicyStore, resource_monitor=None,
+                 quota_manager=None):
+    """Build the tool handler bound to a ShellPolicyStore. With a
+    ResourceMonitor attached, each child's CPU/RSS is attributed to the
+    calling agent and the ResourceQuota resource limits are enforced."""
+
+    def handler(args: dict, context: dict) -> dict:
+        agent_id = context.get("agent_id", "unknown")
+        command = args.get("command", "")
+        if not command:
+            return {"error": "Missing 'command' argument"}
+
+        policy = policy_store.get_policy(agent_id)
+        cwd = args.get("cwd") or policy.get("cwd_root") or "/tmp"
+
+        denied = check_policy(policy, command, cwd)
+        if denied:
+            policy_store.audit(agent_id, command, cwd=cwd, denied_reason=denied)
+            log.warning(f"[SHELL] Denied for {agent_id}: {denied}")
+            return {"error": denied, "code": "shell_denied"}
+
+        preexec = None
+        if resource_monitor and quota_manager:
+            quota = quota_manager.get_quota(agent_id)
+            cpu_check = resource_monitor.check_cpu_quota(agent_id, quota)
+            if not cpu_check["allowed"]:
+                policy_store.audit(agent_id, command, cwd=cwd,
+                                   denied_reason="cpu_quota")
+                return {"error": f"Daily CPU quota exhausted "
+                                 f"({cpu_check['spent']:.1f}s / "
+                                 f"{cpu_check['limit']}s)",
+                        "code": "resource_quota"}
+            if quota.max_tool_rss_kb:
+                preexec = rss_limiter(quota.max_tool_rss_kb)
+
+        timeout = min(int(args.get("timeout_seconds", policy.get("timeout_seconds", 30))),
+                      SHELL_MAX_TIMEOUT_SECONDS)
+        started = datetime.now(timezone.utc)
+        usage_before = child_rusage() if resource_monitor else None
+        argv = shlex.split(command)
+        try:
+            proc = subprocess.run(
+                argv, cwd=cwd, env=scrub_env(),
+                capture_output=True, timeout=timeout, preexec_fn=preexec,
+            )
+            exit_code = proc.returncode
+            stdout = proc.stdout[:SHELL_MAX_OUTPUT_BYTES].decode(errors="replace")
+            stderr = proc.stderr[:SHELL_MAX_OUTPUT_BYTES].decode(errors="replace")
+        except FileNotFoundError:
+            policy_store.audit(agent_id, command, cwd=cwd, exit_code=-1,
+                               denied_reason="not found")
+            return {"error": f"Command not found: {argv[0]}", "code": "shell_error"}
+        except subprocess.TimeoutExpired:
+            duration_ms = int((datetime.now(timezone.utc) - started).total_seconds() * 1000)
+            policy_store.audit(agent_id, command, cwd=cwd, exit_code=-1,
+                               duration_ms=duration_ms, denied_reason="timeout")
+            if usage_before:
+                _attribute(agent_id, usage_before, duration_ms)
+            return {"error": f"Command timed out after {timeout}s", "code": "shell_timeout"}
+
+        duration_ms = int((datetime.now(timezone.utc) - started).total_seconds() * 1000)
+        policy_store.audit(agent_id, command, cwd=cwd, exit_code=exit_code,
+                           duration_ms=duration_ms)
+        if usage_before:
+            _attribute(agent_id, usage_before, duration_ms)
+        return {"exit_code": exit_code, "stdout": stdout, "stderr": stderr,
+                "duration_ms": duration_ms, "cwd": cwd}
+
+    def _attribute(agent_id, usage_before, duration_ms):
+        cpu_before, _ = usage_before
+        cpu_after, rss_kb = child_rusage()
+        resource_monitor.record_sample(
+            agent_id, cpu_seconds=cpu_after - cpu_before,
+            peak_rss_kb=rss_kb, source="shell", duration_ms=duration_ms)
+
+    return handler
+
+
+def register(tool_registry, policy_store: ShellPolicyStore,
+             resource_monitor=None, quota_manager=None):
+    """Declare the shell tool and attach its handler."""
+    tool_registry.declare(
+        "shell",
+        description="Run a shell command under the agent's shell policy "
+                    "(allowlist, deny patterns, cwd scoping, timeout; audited)",
+        schema={"type": "object",
+                "properties": {"command": {"type": "string"},
+                               "cwd": {"type": "string"},
+                               "timeout_seconds": {"type": "integer"}},
+                "required": ["command"]},
+        capability="system.shell",
+        timeout_seconds=SHELL_MAX_TIMEOUT_SECONDS,
+    )
+    tool_registry.register_handler(
+        "shell", make_handler(policy_store, resource_monitor=resource_monitor,
+                              quota_manager=quota_manager))
+
+
+__all__ = ["ShellPolicyStore", "register", "make_handler", "check_policy", "scrub_env"]
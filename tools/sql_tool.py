@@ -0,0 +1,279 @@
+#!/usr/bin/env python3
+"""
+SQL Query Tool for Leviathan Super-Brain
+========================================
+Read-only SQL against operator-registered database connections. Each
+connection is registered by name with a driver (sqlite/postgres/mysql),
+a DSN or path, a table allowlist and a row limit; agents with the
+capability run parameterized SELECTs and get tabular results back.
+Every query is audited with row counts and duration.
+
+Postgres/MySQL need their drivers (psycopg2 / pymysql) installed — the
+imports are lazy so SQLite works out of the box. Every driver enforces
+read-only at the session level (sqlite mode=ro, postgres readonly
+session, mysql SET SESSION TRANSACTION READ ONLY); registering a
+read-only database account on top is still recommended.
+
+Env:
+  SQL_TOOL_MAX_ROWS — hard cap on rows per query (default 500)
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import re
+import logging
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+SQL_TOOL_MAX_ROWS = int(os.environ.get("SQL_TOOL_MAX_ROWS", "500"))
+
+VALID_DRIVERS = ("sqlite", "postgres", "mysql")
+
+log = logging.getLogger("sql_tool")
+
+# Statements must be a single SELECT (or WITH … SELECT); anything that
+# smells like DML/DDL or statement stacking is refused.
+_FORBIDDEN_RE = re.compile(
+    r"\b(INSERT|UPDATE|DELETE|DROP|ALTER|CREATE|TRUNCATE|REPLACE|GRANT|REVOKE|"
+    r"ATTACH|DETACH|PRAGMA|VACUUM|EXEC|CALL)\b",
+    re.IGNORECASE,
+)
+# SELECT-prefixed write vectors: MySQL's INTO OUTFILE/DUMPFILE writes
+# server-side files, SELECT ... INTO @var/INTO table is still a write.
+_SELECT_INTO_RE = re.compile(r"\bINTO\s+(OUTFILE|DUMPFILE|\S+)", re.IGNORECASE)
+_TABLE_REF_RE = re.compile(r"\b(?:FROM|JOIN)\s+[\"'`]?(\w+)[\"'`]?", re.IGNORECASE)
+
+
+def validate_query(query: str, table_allowlist: list) -> str:
+    """Return a refusal reason, or '' if the query is an allowed SELECT."""
+    stripped = re.sub(r"--[^\n]*|/\*.*?\*/", " ", query, flags=re.DOTALL).strip()
+    if not stripped:
+        return "Empty query"
+    if ";" in stripped.rstrip(";"):
+        return "Multiple statements are not allowed"
+    first_word = stripped.split(None, 1)[0].upper()
+    if first_word not in ("SELECT", "WITH"):
+        return "Only SELECT queries are allowed"
+    if _FORBIDDEN_RE.search(stripped):
+        return "Query contains a forbidden keyword"
+    if _SELECT_INTO_RE.search(stripped):
+        return "SELECT ... INTO is not allowed"
+    if table_allowlist:
+        referenced = {t.lower() for t in _TABLE_REF_RE.findall(stripped)}
+        allowed = {t.lower() for t in table_allowlist}
+        disallowed = referenced - allowed
+        if disallowed:
+            return f"Tables not in allowlist: {', '.join(sorted(disallowed))}"
+    return ""
+
+
+class SqlConnectionStore:
+    """Registered database connections + query audit, SQLite-backed."""
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS sql_connections (
+                    name TEXT PRIMARY KEY,
+                    driver TEXT NOT NULL,
+                    dsn TEXT NOT NULL,
+                    table_allowlist TEXT,
+                    max_rows INTEGER NOT NULL DEFAULT 500,
+                    registered_at TEXT NOT NULL
+                )
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS sql_audit (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    agent_id TEXT NOT NULL,
+                    connection_name TEXT NOT NULL,
+                    query TEXT NOT NULL,
+                    row_count INTEGER,
+                    duration_ms INTEGER,
+                    denied_reason TEXT,
+                    executed_at TEXT NOT NULL
+                )
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _now() -> str:
+        return datetime.now(timezone.utc).isoformat()
+
+    def register_connection(self, name: str, driver: str, dsn: str,
+                            table_allowlist: list = None, max_rows: int = None) -> dict:
+        if driver not in VALID_DRIVERS:
+            return {"error": f"Unknown driver: {driver} (use {', '.join(VALID_DRIVERS)})"}
+        max_rows = min(int(max_rows or SQL_TOOL_MAX_ROWS), SQL_TOOL_MAX_ROWS)
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT OR REPLACE INTO sql_connections
+                   (name, driver, dsn, table_allowlist, max_rows, registered_at)
+                   VALUES (?, ?, ?, ?, ?, ?)""",
+                (name, driver, dsn, json.dumps(table_allowlist or []), max_rows, self._now()),
+            )
+            conn.commit()
+            log.info(f"[SQL] Registered connection '{name}' ({driver})")
+            return self.get_connection(name)
+        finally:
+            conn.close()
+
+    def get_connection(self, name: str) -> dict:
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            row = conn.execute(
+                "SELECT * FROM sql_connections WHERE name = ?", (name,)
+            ).fetchone()
+            if not row:
+                return {"error": f"Unknown connection: {name}"}
+            record = dict(row)
+            record["table_allowlist"] = json.loads(record["table_allowlist"] or "[]")
+            return record
+        finally:
+            conn.close()
+
+    def list_connections(self) -> list:
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            records = []
+            for row in conn.execute("SELECT * FROM sql_connections ORDER BY name").fetchall():
+                record = dict(row)
+                record["table_allowlist"] = json.loads(record["table_allowlist"] or "[]")
+                # DSNs can embed credentials — don't echo them back
+                record["dsn"] = "***"
+                records.append(record)
+            return records
+        finally:
+            conn.close()
+
+    def audit(self, agent_id: str, connection_name: str, query: str,
+              row_count: int = None, duration_ms: int = None,
+              denied_reason: str = None):
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT INTO sql_audit
+                   (agent_id, connection_name, query, row_count, duration_ms, denied_reason, executed_at)
+                   VALUES (?, ?, ?, ?, ?, ?, ?)""",
+                (agent_id, connection_name, query[:2000], row_count,
+                 duration_ms, denied_reason, self._now()),
+            )
+            conn.commit()
+        finally:
+            conn.close()
+
+
+def _open_target(record: dict):
+    """Open the registered database read-only where the driver allows it."""
+    driver = record["driver"]
+    if driver == "sqlite":
+        return sqlite3.connect(f"file:{record['dsn']}?mode=ro", uri=True, timeout=10)
+    if driver == "postgres":
+        import psycopg2  # optional dependency
+        conn = psycopg2.connect(record["dsn"])
+        conn.set_session(readonly=True)
+        return conn
+    if driver == "mysql":
+        import pymysql  # optional dependency
+        conn = pymysql.connect(**json.loads(record["dsn"]))
+        # Keyword filtering is not enforcement — make the session itself
+        # read-only so nothing that slips past the regex can write.
+        with conn.cursor() as cursor:
+            cursor.execute("SET SESSION TRANSACTION READ ONLY")
+        return conn
+    raise ValueError(f"Unknown driver: {driver}")
+
+
+def make_handler(connection_store: SqlConnectionStore):
+    """Build the tool handler bound to a SqlConnectionStore."""
+
+    def handler(args: dict, context: dict) -> dict:
+        agent_id = context.get("agent_id", "unknown")
+        connection_name = args.get("connection", "")
+        query = args.get("query", "")
+        if not connection_name or not query:
+            return {"error": "Missing 'connection' or 'query' argument"}
+
+        record = connection_store.get_connection(connection_name)
+        if "error" in record:
+            return record
+
+        denied = validate_query(query, record["table_allowlist"])
+        if denied:
+            connection_store.audit(agent_id, connection_name, query, denied_reason=denied)
+            log.warning(f"[SQL] Denied for {agent_id} on '{connection_name}': {denied}")
+            return {"error": denied, "code": "sql_denied"}
+
+        params = args.get("params", [])
+        max_rows = record["max_rows"]
+        started = datetime.now(timezone.utc)
+        try:
+            target = _open_target(record)
+            try:
+                cursor = target.cursor()
+                cursor.execute(query, params)
+                columns = [d[0] for d in cursor.description] if cursor.description else []
+                rows = cursor.fetchmany(max_rows + 1)
+                truncated = len(rows) > max_rows
+                rows = rows[:max_rows]
+            finally:
+                target.close()
+        except ImportError as e:
+            return {"error": f"Driver not installed for '{record['driver']}': {e}"}
+        except Exception as e:
+            duration_ms = int((datetime.now(timezone.utc) - started).total_seconds() * 1000)
+            connection_store.audit(agent_id, connection_name, query,
+                                   duration_ms=duration_ms, denied_reason=f"query error: {e}")
+            return {"error": f"Query failed: {e}", "code": "sql_failed"}
+
+        duration_ms = int((datetime.now(timezone.utc) - started).total_seconds() * 1000)
+        connection_store.audit(agent_id, connection_name, query,
+                               row_count=len(rows), duration_ms=duration_ms)
+        return {"connection": connection_name, "columns": columns,
+                "rows": [list(r) for r in rows], "row_count": len(rows),
+                "truncated": truncated, "duration_ms": duration_ms}
+
+    return handler
+
+
+def register(tool_registry, connection_store: SqlConnectionStore):
+    """Declare the sql_query tool and attach its handler."""
+    tool_registry.declare(
+        "sql_query",
+        description="Run a parameterized read-only SELECT against a "
+                    "registered database connection (allowlisted tables, "
+                    "row limits; audited)",
+        schema={"type": "object",
+                "properties": {"connection": {"type": "string"},
+                               "query": {"type": "string"},
+                               "params": {"type": "array"}},
+                "required": ["connection", "query"]},
+        capability="data.sql_read",
+        timeout_seconds=30,
+    )
+    tool_registry.register_handler("sql_query", make_handler(connection_store))
+
+
+__all__ = ["SqlConnectionStore", "register", "make_handler", "validate_query"]
@@ -0,0 +1,188 @@
+#!/usr/bin/env python3
+"""
+Turn Transcript Store for Leviathan Super-Brain
+===============================================
+Full transcripts of agent turns persisted for debugging and audits:
+system prompt hash (not the prompt itself — those can be huge and
+sensitive), message list, every tool call with its result, the final
+reply, timings and cost. Queryable by turn, agent, or conversation,
+with retention controls so the table doesn't grow forever.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import uuid
+import hashlib
+import logging
+from datetime import datetime, timedelta, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+TRANSCRIPT_RETENTION_DAYS = int(os.environ.get("TRANSCRIPT_RETENTION_DAYS", "30"))
+
+log = logging.getLogger("transcripts")
+
+
+class TranscriptStore:
+    """
+    SQLite-backed store of turn transcripts.
+
+    Table: turn_transcripts — one row per completed turn. Tool calls and
+    results are stored as a JSON list of {tool, input, output, duration_ms}.
+    """
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS turn_transcripts (
+                    turn_id TEXT PRIMARY KEY,
+                    agent_id TEXT NOT NULL,
+                    conversation_id TEXT,
+                    system_prompt_hash TEXT,
+                    messages TEXT,
+                    tool_calls TEXT,
+                    final_reply TEXT,
+                    model TEXT,
+                    input_tokens INTEGER DEFAULT 0,
+                    output_tokens INTEGER DEFAULT 0,
+                    cost_usd REAL DEFAULT 0.0,
+                    started_at TEXT,
+                    finished_at TEXT,
+                    duration_ms REAL,
+                    created_at TEXT NOT NULL
+                )
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_transcripts_agent
+                ON turn_transcripts(agent_id, created_at)
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_transcripts_conversation
+                ON turn_transcripts(conversation_id)
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _now() -> str:
+        return datetime.now(timezone.utc).isoformat()
+
+    @staticmethod
+    def hash_system_prompt(system_prompt: str) -> str:
+        """Stable hash so identical prompts can be correlated across turns."""
+        return hashlib.sha256((system_prompt or "").encode()).hexdigest()[:16]
+
+    def record_turn(self, agent_id: str, messages: list, final_reply: str,
+                    system_prompt: str = None, tool_calls: list = None,
+                    conversation_id: str = None, model: str = None,
+                    input_tokens: int = 0, output_tokens: int = 0,
+                    cost_usd: float = 0.0, started_at: str = None,
+                    finished_at: str = None) -> dict:
+        """Persist one completed turn. Returns {turn_id, ...}."""
+        turn_id = uuid.uuid4().hex[:16]
+        now = self._now()
+        duration_ms = None
+        if started_at and finished_at:
+            try:
+                duration_ms = (
+                    datetime.fromisoformat(finished_at) - datetime.fromisoformat(started_at)
+                ).total_seconds() * 1000
+            except ValueError:
+                pass
+
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT INTO turn_transcripts
+                   (turn_id, agent_id, conversation_id, system_prompt_hash, messages,
+                    tool_calls, final_reply, model, input_tokens, output_tokens,
+                    cost_usd, started_at, finished_at, duration_ms, created_at)
+                   VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)""",
+                (turn_id, agent_id, conversation_id,
+                 self.hash_system_prompt(system_prompt) if system_prompt else None,
+                 json.dumps(messages or []), json.dumps(tool_calls or []),
+                 final_reply, model, input_tokens, output_tokens, cost_usd,
+                 started_at, finished_at, duration_ms, now),
+            )
+            conn.commit()
+            return {"turn_id": turn_id, "created_at": now, "duration_ms": duration_ms}
+        finally:
+            conn.close()
+
+    def get_transcript(self, turn_id: str) -> dict:
+        """Full transcript for one turn, with tool IO decoded."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            row = conn.execute(
+                "SELECT * FROM turn_transcripts WHERE turn_id = ?", (turn_id,)
+            ).fetchone()
+            if not row:
+                return {"error": f"Unknown turn: {turn_id}"}
+            transcript = dict(row)
+            transcript["messages"] = json.loads(transcript["messages"] or "[]")
+            transcript["tool_calls"] = json.loads(transcript["tool_calls"] or "[]")
+            return transcript
+        finally:
+            conn.close()
+
+    def query(self, agent_id: str = None, conversation_id: str = None,
+              since: str = None, limit: int = 50) -> list:
+        """Transcript summaries (no message bodies) matching the filters."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            query = """SELECT turn_id, agent_id, conversation_id, model,
+                              input_tokens, output_tokens, cost_usd,
+                              duration_ms, created_at
+                       FROM turn_transcripts WHERE 1=1"""
+            params = []
+            if agent_id:
+                query += " AND agent_id = ?"
+                params.append(agent_id)
+            if conversation_id:
+                query += " AND conversation_id = ?"
+                params.append(conversation_id)
+            if since:
+                query += " AND created_at >= ?"
+                params.append(since)
+            query += " ORDER BY created_at DESC LIMIT ?"
+            params.append(limit)
+            return [dict(r) for r in conn.execute(query, params).fetchall()]
+        finally:
+            conn.close()
+
+    def prune_expired(self, retention_days: int = TRANSCRIPT_RETENTION_DAYS) -> int:
+        """Delete transcripts past the retention window. Returns count removed."""
+        cutoff = (datetime.now(timezone.utc) - timedelta(days=retention_days)).isoformat()
+        conn = self._connect()
+        try:
+            removed = conn.execute(
+                "DELETE FROM turn_transcripts WHERE created_at < ?", (cutoff,)
+            ).rowcount
+            conn.commit()
+            if removed:
+                log.info(f"[PRUNE] Removed {removed} transcripts older than {retention_days}d")
+            return removed
+        finally:
+            conn.close()
+
+
+__all__ = ["TranscriptStore"]
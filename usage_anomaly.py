@@ -0,0 +1,144 @@
+#!/usr/bin/env python3
+"""
+Usage Anomaly Detection for Leviathan Super-Brain
+=================================================
+Catches runaway spend while it's still an hourly blip, not a monthly
+invoice: each agent's current-hour spend is compared against its own
+trailing baseline (served from the hourly rollups), and a deviation
+beyond ANOMALY_FACTOR raises a usage.anomaly event — optionally pausing
+the agent's schedules on the spot. Agents with no meaningful spend are
+ignored (ANOMALY_MIN_SPEND_USD) so idle fleets don't alert on noise,
+and a per-agent cooldown keeps one incident to one alert.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import os
+import time
+import logging
+from datetime import datetime, timedelta, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+
+# Current-hour spend beyond factor × trailing hourly average is anomalous
+ANOMALY_FACTOR = float(os.environ.get("ANOMALY_FACTOR", "5.0"))
+# Hours of history the baseline averages over (current hour excluded)
+ANOMALY_BASELINE_HOURS = int(os.environ.get("ANOMALY_BASELINE_HOURS", "24"))
+# Ignore agents below this current-hour spend — noise, not runaways
+ANOMALY_MIN_SPEND_USD = float(os.environ.get("ANOMALY_MIN_SPEND_USD", "1.0"))
+# Pause an anomalous agent's schedules automatically
+ANOMALY_AUTO_PAUSE = os.environ.get("ANOMALY_AUTO_PAUSE", "false").lower() == "true"
+# One alert per agent per cooldown window
+ANOMALY_ALERT_COOLDOWN_SECONDS = int(os.environ.get("ANOMALY_ALERT_COOLDOWN_SECONDS", "3600"))
+
+log = logging.getLogger("usage_anomaly")
+
+
+class UsageAnomalyDetector:
+    """Per-agent hourly spend vs trailing baseline, from the rollups."""
+
+    def __init__(self, db_path: str = DB_PATH, event_bus=None,
+                 schedule_pause=None):
+        self.db_path = db_path
+        self.event_bus = event_bus
+        self.schedule_pause = schedule_pause
+        self._last_alert = {}  # agent_id → monotonic time of last alert
+        self.findings = []  # most recent check_all() result
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def check_all(self) -> list:
+        """
+        One detection pass over every agent with current-hour spend.
+        Returns the anomalies found (and remembers them for the status
+        endpoint); baseline-less agents (first hour of life) can't be
+        judged and are skipped.
+        """
+        now = datetime.now(timezone.utc)
+        current_hour = now.strftime("%Y-%m-%dT%H")
+        baseline_start = (now - timedelta(hours=ANOMALY_BASELINE_HOURS)
+                          ).strftime("%Y-%m-%dT%H")
+        conn = self._connect()
+        try:
+            current = dict(conn.execute(
+                """SELECT agent_id, SUM(cost_usd) FROM usage_rollup_hourly
+                   WHERE bucket = ? GROUP BY agent_id""",
+                (current_hour,),
+            ).fetchall())
+            baseline = {row[0]: (row[1] or 0.0, row[2]) for row in conn.execute(
+                """SELECT agent_id, SUM(cost_usd), COUNT(DISTINCT bucket)
+                   FROM usage_rollup_hourly
+                   WHERE bucket >= ? AND bucket < ?
+                   GROUP BY agent_id""",
+                (baseline_start, current_hour),
+            ).fetchall()}
+        except sqlite3.OperationalError:
+            return []  # data directory predates the rollups
+        finally:
+            conn.close()
+
+        anomalies = []
+        for agent_id, spend in current.items():
+            spend = spend or 0.0
+            if spend < ANOMALY_MIN_SPEND_USD:
+                continue
+            base_total, base_hours = baseline.get(agent_id, (0.0, 0))
+            if base_hours == 0:
+                continue  # no history to deviate from
+            # Average over the whole window, not just active hours — an
+            # agent that normally sleeps 23h/day should look anomalous
+            # when it suddenly burns all hour
+            base_avg = base_total / ANOMALY_BASELINE_HOURS
+            if base_avg <= 0 or spend <= ANOMALY_FACTOR * base_avg:
+                continue
+            anomalies.append(self._raise(agent_id, spend, base_avg))
+        self.findings = anomalies
+        return anomalies
+
+    def _raise(self, agent_id: str, spend: float, base_avg: float) -> dict:
+        finding = {
+            "agent_id": agent_id,
+            "hourly_spend_usd": round(spend, 4),
+            "baseline_hourly_usd": round(base_avg, 4),
+            "deviation_factor": round(spend / base_avg, 1),
+            "threshold_factor": ANOMALY_FACTOR,
+            "detected_at": datetime.now(timezone.utc).isoformat(),
+            "auto_paused": False,
+        }
+        now = time.monotonic()
+        if now - self._last_alert.get(agent_id, float("-inf")) \
+                < ANOMALY_ALERT_COOLDOWN_SECONDS:
+            finding["alert_suppressed"] = True
+            return finding
+        self._last_alert[agent_id] = now
+
+        log.warning(f"[ANOMALY] {agent_id} spending "
+                    f"${spend:.2f}/h vs ${base_avg:.2f}/h baseline "
+                    f"({finding['deviation_factor']}x, threshold {ANOMALY_FACTOR}x)")
+        if ANOMALY_AUTO_PAUSE and self.schedule_pause is not None:
+            self.schedule_pause.pause_schedules(
+                agent_id, paused_by="anomaly-detector",
+                reason=f"Hourly spend {finding['deviation_factor']}x baseline")
+            finding["auto_paused"] = True
+        if self.event_bus is not None:
+            self.event_bus.publish("usage.anomaly", finding)
+        return finding
+
+    def status(self) -> dict:
+        return {
+            "factor": ANOMALY_FACTOR,
+            "baseline_hours": ANOMALY_BASELINE_HOURS,
+            "min_spend_usd": ANOMALY_MIN_SPEND_USD,
+            "auto_pause": ANOMALY_AUTO_PAUSE,
+            "last_findings": self.findings,
+        }
+
+
+__all__ = ["UsageAnomalyDetector"]
@@ -0,0 +1,1252 @@
+#!/usr/bin/env python3
+"""
+Usage Store for Leviathan Super-Brain
+=====================================
+Durable per-call LLM usage records: who spent what, on which model, for
+which conversation. The in-memory TokenBudget counters answer "can we
+proceed right now"; the UsageStore answers "where did the money go" after
+the fact, and keeps cost estimates next to actuals so estimation drift is
+visible.
+
+Features:
+  1. record() — persist one usage record (tokens, cost, estimate vs actual)
+  2. estimate_turn_cost() — preview cost from prompt size + model pricing
+  3. Cost math delegated to the shared pricing module
+  4. Simple aggregate queries for reporting endpoints
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import csv
+import json
+import os
+import queue
+import random
+import hashlib
+import logging
+from datetime import datetime, timezone
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+
+# The rate table lives in the pricing module now; these re-exports keep
+# existing importers (workflow_budget, reports) working unchanged.
+from pricing import PRICING as COST_PER_M, DEFAULT_RATES, compute_cost as pricing_cost
+
+CHARS_PER_TOKEN = 4  # rough estimate, same heuristic as the semantic cache
+
+# Fraction of usage events persisted as raw rows (1.0 = all). Below 1.0
+# the rollups still absorb every event — cost accounting stays exact —
+# but only a sample of raw rows is written, cutting write volume on
+# very high-volume deployments. Raw-event views (pagination, streaming,
+# per-conversation rollups) then show ~rate of the traffic.
+USAGE_SAMPLE_RATE = float(os.environ.get("USAGE_SAMPLE_RATE", "1.0"))
+
+# Salt for anonymized exports. Set a deployment-specific value so hashed
+# agent IDs are stable across exports but can't be brute-forced trivially.
+ANON_SALT = os.environ.get("USAGE_ANON_SALT", "leviathan-usage-anon")
+
+# Fields stripped entirely from anonymized exports (identifying, not needed
+# for cost benchmarks).
+ANON_STRIPPED_FIELDS = ("tenant_id", "user_id", "conversation_id", "session_id",
+                        "purpose", "approval_id", "tags")
+
+log = logging.getLogger("usage_store")
+
+
+def estimate_tokens(text: str) -> int:
+    """Rough token estimate from character count."""
+    return max(1, len(text) // CHARS_PER_TOKEN)
+
+
+def estimate_turn_cost(model: str, prompt: str, max_output_tokens: int = 2048) -> dict:
+    """
+    Estimate USD cost of a turn from prompt size and model pricing,
+    assuming the model uses its full output allowance (worst case).
+    """
+    rates = COST_PER_M.get(model, DEFAULT_RATES)
+    input_tokens = estimate_tokens(prompt)
+    cost = (input_tokens * rates['input'] + max_output_tokens * rates['output']) / 1_000_000
+    return {
+        "model": model,
+        "input_tokens_est": input_tokens,
+        "output_tokens_est": max_output_tokens,
+        "estimated_cost_usd": round(cost, 6),
+        "known_model": model in COST_PER_M,
+    }
+
+
+class UsageStore:
+    """
+    SQLite-backed store of per-call usage records.
+
+    Table: usage_records — one row per LLM call with attribution fields
+    (agent/tenant/user/conversation) and estimate-vs-actual cost columns.
+    """
+
+    def __init__(self, db_path: str = DB_PATH, sample_rate: float = USAGE_SAMPLE_RATE):
+        self.db_path = db_path
+        self.sample_rate = min(max(sample_rate, 0.0), 1.0)
+        self.sampled_out = 0  # events absorbed by rollups only
+        self.observers = []  # callables invoked with each record() result
+        self._subscriptions = {}  # live queues from subscribe_usage()
+        self.ensure_schema()
+        if self.sample_rate < 1.0:
+            log.info(f"[USAGE] Sampling mode: {self.sample_rate:.0%} of events "
+                     f"persisted raw, rollups stay exact")
+
+    def add_observer(self, fn):
+        """Register a callback fired after every record() (metrics, alerts).
+        Observer failures are logged, never propagated to the caller."""
+        self.observers.append(fn)
+
+    def subscribe_usage(self, max_queued: int = 1000) -> "queue.Queue":
+        """
+        Broadcast subscription for live dashboards: returns a Queue that
+        receives every record as it is written, without polling the
+        store. A consumer that falls behind max_queued records loses the
+        newest events rather than blocking record() — live views should
+        drop, not backpressure the kernel. Always pair with
+        unsubscribe_usage() or the feed leaks.
+        """
+        live = queue.Queue(maxsize=max_queued)
+
+        def feed(record):
+            try:
+                live.put_nowait(record)
+            except queue.Full:
+                pass  # slow consumer; the durable row is still there
+
+        self._subscriptions[live] = feed
+        self.observers.append(feed)
+        log.info(f"[USAGE] Live subscriber attached "
+                 f"({len(self._subscriptions)} active)")
+        return live
+
+    def unsubscribe_usage(self, live: "queue.Queue"):
+        """Detach a subscribe_usage() queue from the broadcast."""
+        feed = self._subscriptions.pop(live, None)
+        if feed in self.observers:
+            self.observers.remove(feed)
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        """Create usage tables if they don't exist."""
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS usage_records (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    agent_id TEXT NOT NULL,
+                    tenant_id TEXT,
+                    user_id TEXT,
+                    conversation_id TEXT,
+                    model TEXT NOT NULL,
+                    provider TEXT,
+                    purpose TEXT,
+                    input_tokens INTEGER NOT NULL DEFAULT 0,
+                    output_tokens INTEGER NOT NULL DEFAULT 0,
+                    cost_usd REAL NOT NULL DEFAULT 0.0,
+                    estimated_cost_usd REAL,
+                    approval_id TEXT,
+                    created_at TEXT NOT NULL
+                )
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_usage_agent_time
+                ON usage_records(agent_id, created_at)
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_usage_conversation
+                ON usage_records(conversation_id)
+            """)
+            # Additive migration: gateway session attribution, distinct from
+            # conversation_id (one session can span several conversations)
+            try:
+                conn.execute("ALTER TABLE usage_records ADD COLUMN session_id TEXT")
+                conn.execute("""
+                    CREATE INDEX IF NOT EXISTS idx_usage_session
+                    ON usage_records(session_id)
+                """)
+            except sqlite3.OperationalError:
+                pass  # column already exists
+            # Additive migration: prompt-cache hits bill at a cheaper rate
+            try:
+                conn.execute("ALTER TABLE usage_records "
+                             "ADD COLUMN cached_input_tokens INTEGER NOT NULL DEFAULT 0")
+            except sqlite3.OperationalError:
+                pass  # column already exists
+            # Additive migration: cache writes bill at a surcharge rate,
+            # separate from the (cheaper) cache reads above
+            try:
+                conn.execute("ALTER TABLE usage_records "
+                             "ADD COLUMN cache_write_tokens INTEGER NOT NULL DEFAULT 0")
+            except sqlite3.OperationalError:
+                pass  # column already exists
+            # Additive migration: streamed generations write a provisional
+            # row first (finalized=0) and close it out when the stream ends
+            try:
+                conn.execute("ALTER TABLE usage_records "
+                             "ADD COLUMN finalized INTEGER NOT NULL DEFAULT 1")
+            except sqlite3.OperationalError:
+                pass  # column already exists
+            # Additive migration: when a fallback model served the call,
+            # the primary it stood in for — degraded-provider visibility
+            try:
+                conn.execute("ALTER TABLE usage_records "
+                             "ADD COLUMN fallback_from TEXT")
+            except sqlite3.OperationalError:
+                pass  # column already exists
+            # Additive migration: free-form attribution tags (JSON dict —
+            # campaign, feature, customer, ...) so finance can split
+            # spend along business dimensions, not just agent/model
+            try:
+                conn.execute("ALTER TABLE usage_records "
+                             "ADD COLUMN tags TEXT")
+            except sqlite3.OperationalError:
+                pass  # column already exists
+            # Pre-aggregated rollups so summary queries don't scan the raw
+            # table — maintained incrementally by record(), rebuildable
+            # from the raw records at any time
+            for table, bucket_len in (("usage_rollup_hourly", 13),
+                                      ("usage_rollup_daily", 10)):
+                conn.execute(f"""
+                    CREATE TABLE IF NOT EXISTS {table} (
+                        bucket TEXT NOT NULL,
+                        agent_id TEXT NOT NULL,
+                        calls INTEGER NOT NULL DEFAULT 0,
+                        input_tokens INTEGER NOT NULL DEFAULT 0,
+                        output_tokens INTEGER NOT NULL DEFAULT 0,
+                        cost_usd REAL NOT NULL DEFAULT 0.0,
+                        PRIMARY KEY (bucket, agent_id)
+                    )
+                """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS tool_usage_events (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    tool TEXT NOT NULL,
+                    agent_id TEXT,
+                    duration_ms REAL NOT NULL DEFAULT 0,
+                    cost_usd REAL NOT NULL DEFAULT 0.0,
+                    ok INTEGER NOT NULL DEFAULT 1,
+                    created_at TEXT NOT NULL
+                )
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_tool_usage_tool_time
+                ON tool_usage_events(tool, created_at)
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _now() -> str:
+        return datetime.now(timezone.utc).isoformat()
+
+    def compute_cost(self, model: str, input_tokens: int, output_tokens: int,
+                     cached_input_tokens: int = 0,
+                     cache_write_tokens: int = 0) -> float:
+        """USD cost for actual token counts at pricing-table rates."""
+        return pricing_cost(model, input_tokens, output_tokens,
+                            cached_input_tokens, cache_write_tokens)
+
+    def record(self, agent_id: str, model: str, input_tokens: int, output_tokens: int,
+               tenant_id: str = None, user_id: str = None, conversation_id: str = None,
+               session_id: str = None, provider: str = None, purpose: str = None,
+               cost_usd: float = None, estimated_cost_usd: float = None,
+               approval_id: str = None, cached_input_tokens: int = 0,
+               cache_write_tokens: int = 0, fallback_from: str = None,
+               tags: dict = None) -> dict:
+        """
+        Persist one usage record. If cost_usd is not given it is computed
+        from the pricing table (cached_input_tokens billing at the cached
+        rate). estimated_cost_usd (from the pre-turn preview) is stored
+        next to the actual so drift can be audited. `tags` is a flat
+        dict of attribution labels set by the caller (trigger, workflow,
+        gateway) — e.g. {"campaign": "q3-launch"}.
+
+        With sample_rate < 1.0 only that fraction of events gets a raw
+        row; the rest land in the rollups alone and come back with
+        id=None and sampled_out=True.
+        """
+        if cost_usd is None:
+            cost_usd = self.compute_cost(model, input_tokens, output_tokens,
+                                         cached_input_tokens, cache_write_tokens)
+        now = self._now()
+        persist_raw = (self.sample_rate >= 1.0
+                       or random.random() < self.sample_rate)
+        if not persist_raw:
+            # Sampled out: the rollups absorb the event (exact cost
+            # accounting), only the raw row is skipped
+            conn = self._connect()
+            try:
+                self._bump_rollups(conn, agent_id, now, input_tokens,
+                                   output_tokens, cost_usd)
+                conn.commit()
+            finally:
+                conn.close()
+            self.sampled_out += 1
+            record = {
+                "id": None,
+                "agent_id": agent_id,
+                "model": model,
+                "input_tokens": input_tokens,
+                "output_tokens": output_tokens,
+                "cost_usd": round(cost_usd, 6),
+                "estimated_cost_usd": estimated_cost_usd,
+                "created_at": now,
+                "sampled_out": True,
+            }
+            for observer in self.observers:
+                try:
+                    observer(record)
+                except Exception as e:
+                    log.warning(f"[USAGE] Observer {observer} failed: {e}")
+            return record
+        conn = self._connect()
+        try:
+            cursor = conn.execute(
+                """INSERT INTO usage_records
+                   (agent_id, tenant_id, user_id, conversation_id, session_id, model, provider,
+                    purpose, input_tokens, output_tokens, cached_input_tokens,
+                    cache_write_tokens, cost_usd,
+                    estimated_cost_usd, approval_id, fallback_from, tags, created_at)
+                   VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)""",
+                (agent_id, tenant_id, user_id, conversation_id, session_id, model, provider,
+                 purpose, input_tokens, output_tokens, cached_input_tokens or 0,
+                 cache_write_tokens or 0, cost_usd,
+                 estimated_cost_usd, approval_id, fallback_from,
+                 json.dumps(tags) if tags else None, now),
+            )
+            self._bump_rollups(conn, agent_id, now, input_tokens, output_tokens, cost_usd)
+            conn.commit()
+            record = {
+                "id": cursor.lastrowid,
+                "agent_id": agent_id,
+                "model": model,
+                "input_tokens": input_tokens,
+                "output_tokens": output_tokens,
+                "cost_usd": round(cost_usd, 6),
+                "estimated_cost_usd": estimated_cost_usd,
+                "created_at": now,
+            }
+            if fallback_from:
+                record["fallback_from"] = fallback_from
+            if tags:
+                record["tags"] = tags
+            if estimated_cost_usd is not None and estimated_cost_usd > 0:
+                record["estimate_drift_pct"] = round(
+                    (cost_usd - estimated_cost_usd) / estimated_cost_usd * 100, 1
+                )
+        finally:
+            conn.close()
+        for observer in self.observers:
+            try:
+                observer(record)
+            except Exception as e:
+                log.warning(f"[USAGE] Observer {observer} failed: {e}")
+        return record
+
+    @staticmethod
+    def _bump_rollups(conn, agent_id: str, created_at: str,
+                      input_tokens: int, output_tokens: int, cost_usd: float):
+        """Incrementally maintain the hourly/daily rollups inside the
+        record() transaction — the rollups never lag the raw table."""
+        for table, bucket_len in (("usage_rollup_hourly", 13),
+                                  ("usage_rollup_daily", 10)):
+            conn.execute(
+                f"""INSERT INTO {table}
+                    (bucket, agent_id, calls, input_tokens, output_tokens, cost_usd)
+                    VALUES (?, ?, 1, ?, ?, ?)
+                    ON CONFLICT (bucket, agent_id) DO UPDATE SET
+                        calls = calls + 1,
+                        input_tokens = input_tokens + excluded.input_tokens,
+                        output_tokens = output_tokens + excluded.output_tokens,
+                        cost_usd = cost_usd + excluded.cost_usd""",
+                (created_at[:bucket_len], agent_id,
+                 input_tokens, output_tokens, cost_usd),
+            )
+
+    @staticmethod
+    def _bump_rollup_deltas(conn, agent_id: str, created_at: str,
+                            input_tokens: int, output_tokens: int,
+                            cost_usd: float):
+        """Apply token/cost deltas to the rollups without counting a new
+        call — used when a streamed generation grows an existing row."""
+        for table, bucket_len in (("usage_rollup_hourly", 13),
+                                  ("usage_rollup_daily", 10)):
+            conn.execute(
+                f"""INSERT INTO {table}
+                    (bucket, agent_id, calls, input_tokens, output_tokens, cost_usd)
+                    VALUES (?, ?, 0, ?, ?, ?)
+                    ON CONFLICT (bucket, agent_id) DO UPDATE SET
+                        input_tokens = input_tokens + excluded.input_tokens,
+                        output_tokens = output_tokens + excluded.output_tokens,
+                        cost_usd = cost_usd + excluded.cost_usd""",
+                (created_at[:bucket_len], agent_id,
+                 input_tokens, output_tokens, cost_usd),
+            )
+
+    def begin_stream(self, agent_id: str, model: str, input_tokens: int,
+                     tenant_id: str = None, user_id: str = None,
+                     conversation_id: str = None, session_id: str = None,
+                     provider: str = None, purpose: str = None,
+                     estimated_cost_usd: float = None,
+                     approval_id: str = None) -> dict:
+        """
+        Open a provisional record for a streamed generation. The input
+        side is known up front and lands in the rollups immediately, so
+        budget checks see the call the moment it starts instead of only
+        after a 50k-token response finishes. Grow the record with
+        update_stream() as chunks arrive and close it with
+        finalize_stream().
+        """
+        cost_usd = self.compute_cost(model, input_tokens, 0)
+        now = self._now()
+        conn = self._connect()
+        try:
+            cursor = conn.execute(
+                """INSERT INTO usage_records
+                   (agent_id, tenant_id, user_id, conversation_id, session_id,
+                    model, provider, purpose, input_tokens, output_tokens,
+                    cost_usd, estimated_cost_usd, approval_id, created_at,
+                    finalized)
+                   VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, 0, ?, ?, ?, ?, 0)""",
+                (agent_id, tenant_id, user_id, conversation_id, session_id,
+                 model, provider, purpose, input_tokens, cost_usd,
+                 estimated_cost_usd, approval_id, now),
+            )
+            self._bump_rollups(conn, agent_id, now, input_tokens, 0, cost_usd)
+            conn.commit()
+            return {"stream_id": cursor.lastrowid, "agent_id": agent_id,
+                    "model": model, "input_tokens": input_tokens,
+                    "created_at": now}
+        finally:
+            conn.close()
+
+    def update_stream(self, stream_id: int, output_tokens: int) -> dict:
+        """
+        Report the cumulative output token count for an open stream. The
+        record and the rollups absorb only the delta since the last
+        update, so calling this per chunk is cheap and idempotent on
+        repeated counts. Updates after finalize are refused.
+        """
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                """SELECT agent_id, model, output_tokens, cost_usd, created_at,
+                          finalized
+                   FROM usage_records WHERE id = ?""",
+                (stream_id,),
+            ).fetchone()
+            if not row:
+                return {"error": f"Unknown stream: {stream_id}"}
+            agent_id, model, recorded, old_cost, created_at, finalized = row
+            if finalized:
+                return {"error": f"Stream {stream_id} already finalized"}
+            delta = output_tokens - recorded
+            if delta <= 0:
+                return {"stream_id": stream_id, "output_tokens": recorded,
+                        "cost_usd": old_cost}
+            delta_cost = self.compute_cost(model, 0, delta)
+            conn.execute(
+                """UPDATE usage_records
+                   SET output_tokens = output_tokens + ?, cost_usd = cost_usd + ?
+                   WHERE id = ?""",
+                (delta, delta_cost, stream_id),
+            )
+            self._bump_rollup_deltas(conn, agent_id, created_at, 0, delta, delta_cost)
+            conn.commit()
+            return {"stream_id": stream_id, "output_tokens": output_tokens,
+                    "cost_usd": round(old_cost + delta_cost, 6)}
+        finally:
+            conn.close()
+
+    def finalize_stream(self, stream_id: int, output_tokens: int = None,
+                        input_tokens: int = None) -> dict:
+        """
+        Close a streamed record with the provider's final counts (pass
+        None to keep what update_stream accumulated). Any difference from
+        the partials lands as a last delta, the row flips to finalized
+        and observers fire once — the stream looks like a single
+        record() to metrics and exporters.
+        """
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                """SELECT agent_id, model, input_tokens, output_tokens,
+                          cost_usd, estimated_cost_usd, created_at, finalized
+                   FROM usage_records WHERE id = ?""",
+                (stream_id,),
+            ).fetchone()
+            if not row:
+                return {"error": f"Unknown stream: {stream_id}"}
+            (agent_id, model, rec_in, rec_out, old_cost,
+             estimated_cost_usd, created_at, finalized) = row
+            if finalized:
+                return {"error": f"Stream {stream_id} already finalized"}
+            final_in = rec_in if input_tokens is None else input_tokens
+            final_out = rec_out if output_tokens is None else output_tokens
+            final_cost = self.compute_cost(model, final_in, final_out)
+            conn.execute(
+                """UPDATE usage_records
+                   SET input_tokens = ?, output_tokens = ?, cost_usd = ?,
+                       finalized = 1
+                   WHERE id = ?""",
+                (final_in, final_out, final_cost, stream_id),
+            )
+            self._bump_rollup_deltas(conn, agent_id, created_at,
+                                     final_in - rec_in, final_out - rec_out,
+                                     final_cost - old_cost)
+            conn.commit()
+        finally:
+            conn.close()
+        record = {
+            "id": stream_id,
+            "agent_id": agent_id,
+            "model": model,
+            "input_tokens": final_in,
+            "output_tokens": final_out,
+            "cost_usd": round(final_cost, 6),
+            "estimated_cost_usd": estimated_cost_usd,
+            "created_at": created_at,
+        }
+        if estimated_cost_usd is not None and estimated_cost_usd > 0:
+            record["estimate_drift_pct"] = round(
+                (final_cost - estimated_cost_usd) / estimated_cost_usd * 100, 1
+            )
+        for observer in self.observers:
+            try:
+                observer(record)
+            except Exception as e:
+                log.warning(f"[USAGE] Observer {observer} failed: {e}")
+        return record
+
+    def sampling_status(self) -> dict:
+        """Current sampling mode and how many events it has absorbed.
+        The expected relative error on raw-event views at rate p with n
+        sampled events is roughly 1/sqrt(n*p); rollup-served numbers
+        carry no error at any rate."""
+        return {
+            "sample_rate": self.sample_rate,
+            "sampling_active": self.sample_rate < 1.0,
+            "sampled_out": self.sampled_out,
+        }
+
+    def rebuild_rollups(self) -> dict:
+        """
+        Recompute both rollup tables from the raw records — backfill for
+        data directories that predate the rollups, or repair after manual
+        surgery on usage_records. With sampling active this rebuilds
+        only the sampled fraction — don't run it below sample_rate 1.0
+        unless the rollups are actually corrupt.
+        """
+        conn = self._connect()
+        try:
+            counts = {}
+            for table, bucket_len in (("usage_rollup_hourly", 13),
+                                      ("usage_rollup_daily", 10)):
+                conn.execute(f"DELETE FROM {table}")
+                cursor = conn.execute(
+                    f"""INSERT INTO {table}
+                        (bucket, agent_id, calls, input_tokens, output_tokens, cost_usd)
+                        SELECT substr(created_at, 1, {bucket_len}), agent_id,
+                               COUNT(*), SUM(input_tokens), SUM(output_tokens),
+                               SUM(cost_usd)
+                        FROM usage_records
+                        GROUP BY substr(created_at, 1, {bucket_len}), agent_id"""
+                )
+                counts[table] = cursor.rowcount
+            conn.commit()
+            log.info(f"[USAGE] Rollups rebuilt: {counts}")
+            return {"rebuilt": counts}
+        finally:
+            conn.close()
+
+    def query_daily_breakdown(self, agent_id: str = None, since: str = None,
+                              granularity: str = "daily", limit: int = 90) -> list:
+        """
+        Per-bucket spend/token breakdown served from the pre-aggregated
+        rollups (never a raw-table scan), newest bucket first. With an
+        agent_id the rows are that agent's; otherwise buckets sum the
+        whole fleet. granularity: 'daily' (default) or 'hourly'.
+        """
+        table = ("usage_rollup_hourly" if granularity == "hourly"
+                 else "usage_rollup_daily")
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            query = f"""SELECT bucket, SUM(calls) AS calls,
+                               SUM(input_tokens) AS input_tokens,
+                               SUM(output_tokens) AS output_tokens,
+                               SUM(cost_usd) AS cost_usd
+                        FROM {table} WHERE 1=1"""
+            params = []
+            if agent_id:
+                query += " AND agent_id = ?"
+                params.append(agent_id)
+            if since:
+                query += " AND bucket >= ?"
+                params.append(since[:13 if granularity == "hourly" else 10])
+            query += " GROUP BY bucket ORDER BY bucket DESC LIMIT ?"
+            params.append(limit)
+            rows = [dict(r) for r in conn.execute(query, params).fetchall()]
+            for row in rows:
+                row["cost_usd"] = round(row["cost_usd"] or 0, 6)
+            return rows
+        finally:
+            conn.close()
+
+    def record_tool_event(self, tool: str, agent_id: str = None,
+                          duration_ms: float = 0, cost_usd: float = 0.0,
+                          ok: bool = True) -> dict:
+        """
+        Persist one tool execution event. cost_usd is the token cost
+        attributed to this tool call (the turn that carried its result),
+        0.0 when the caller can't attribute — duration still counts.
+        """
+        now = self._now()
+        conn = self._connect()
+        try:
+            cursor = conn.execute(
+                """INSERT INTO tool_usage_events
+                   (tool, agent_id, duration_ms, cost_usd, ok, created_at)
+                   VALUES (?, ?, ?, ?, ?, ?)""",
+                (tool, agent_id, round(float(duration_ms), 1),
+                 round(float(cost_usd), 6), 1 if ok else 0, now),
+            )
+            conn.commit()
+            return {"id": cursor.lastrowid, "tool": tool, "created_at": now}
+        finally:
+            conn.close()
+
+    def query_by_tool(self, since: str = None, until: str = None,
+                      agent_id: str = None) -> list:
+        """
+        Per-tool rollups — calls, failure count, duration (total/avg) and
+        attributed cost, most expensive first — so "which tools drive
+        spend" has a direct answer.
+        """
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            query = """SELECT tool, COUNT(*) AS calls,
+                              SUM(CASE WHEN ok = 0 THEN 1 ELSE 0 END) AS failures,
+                              SUM(duration_ms) AS total_duration_ms,
+                              AVG(duration_ms) AS avg_duration_ms,
+                              SUM(cost_usd) AS cost_usd
+                       FROM tool_usage_events WHERE 1=1"""
+            params = []
+            if since:
+                query += " AND created_at >= ?"
+                params.append(since)
+            if until:
+                query += " AND created_at < ?"
+                params.append(until)
+            if agent_id:
+                query += " AND agent_id = ?"
+                params.append(agent_id)
+            query += " GROUP BY tool ORDER BY cost_usd DESC, total_duration_ms DESC"
+            rollups = [dict(r) for r in conn.execute(query, params).fetchall()]
+            for rollup in rollups:
+                rollup["avg_duration_ms"] = round(rollup["avg_duration_ms"] or 0, 1)
+                rollup["cost_usd"] = round(rollup["cost_usd"] or 0, 6)
+            return rollups
+        finally:
+            conn.close()
+
+    def agent_totals(self, agent_id: str = None, since: str = None) -> list:
+        """Aggregate spend/tokens per agent, optionally filtered."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            query = """SELECT agent_id, COUNT(*) AS calls,
+                              SUM(input_tokens) AS input_tokens,
+                              SUM(output_tokens) AS output_tokens,
+                              SUM(cached_input_tokens) AS cache_read_tokens,
+                              SUM(cache_write_tokens) AS cache_write_tokens,
+                              SUM(cost_usd) AS cost_usd
+                       FROM usage_records WHERE 1=1"""
+            params = []
+            if agent_id:
+                query += " AND agent_id = ?"
+                params.append(agent_id)
+            if since:
+                query += " AND created_at >= ?"
+                params.append(since)
+            query += " GROUP BY agent_id ORDER BY cost_usd DESC"
+            return [dict(r) for r in conn.execute(query, params).fetchall()]
+        finally:
+            conn.close()
+
+    def query_by_provider(self, since: str = None, until: str = None) -> list:
+        """
+        Cost/token totals grouped by provider (anthropic, openai, groq…)
+        for invoice reconciliation, highest spend first. Records without
+        a provider land in a 'unknown' bucket so the totals still add up.
+        """
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            query = """SELECT COALESCE(provider, 'unknown') AS provider,
+                              COUNT(*) AS calls,
+                              COUNT(DISTINCT model) AS models,
+                              SUM(input_tokens) AS input_tokens,
+                              SUM(output_tokens) AS output_tokens,
+                              SUM(cached_input_tokens) AS cache_read_tokens,
+                              SUM(cache_write_tokens) AS cache_write_tokens,
+                              SUM(cost_usd) AS cost_usd
+                       FROM usage_records WHERE 1=1"""
+            params = []
+            if since:
+                query += " AND created_at >= ?"
+                params.append(since)
+            if until:
+                query += " AND created_at < ?"
+                params.append(until)
+            query += " GROUP BY COALESCE(provider, 'unknown') ORDER BY cost_usd DESC"
+            rows = [dict(r) for r in conn.execute(query, params).fetchall()]
+            for row in rows:
+                row["cost_usd"] = round(row["cost_usd"] or 0, 6)
+            return rows
+        finally:
+            conn.close()
+
+    def query_agent_daily(self, agent_id: str, day: str = None) -> dict:
+        """One agent's calls/tokens/cost for a day ('YYYY-MM-DD', default
+        today), served from the daily rollup."""
+        day = day or datetime.now(timezone.utc).strftime("%Y-%m-%d")
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                """SELECT SUM(calls), SUM(input_tokens), SUM(output_tokens),
+                          SUM(cost_usd)
+                   FROM usage_rollup_daily WHERE agent_id = ? AND bucket = ?""",
+                (agent_id, day),
+            ).fetchone()
+        finally:
+            conn.close()
+        return {"agent_id": agent_id, "day": day, "calls": row[0] or 0,
+                "tokens": (row[1] or 0) + (row[2] or 0),
+                "cost_usd": round(row[3] or 0.0, 6)}
+
+    def query_agent_model_daily(self, agent_id: str, model: str,
+                                day: str = None) -> dict:
+        """One agent's calls/tokens/cost on one model for a day. The
+        rollups aren't keyed by model, so this aggregates the raw table —
+        exact at the default sample rate, approximate under sampling."""
+        day = day or datetime.now(timezone.utc).strftime("%Y-%m-%d")
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                """SELECT COUNT(*), SUM(input_tokens), SUM(output_tokens),
+                          SUM(cost_usd)
+                   FROM usage_records
+                   WHERE agent_id = ? AND model = ? AND created_at LIKE ?""",
+                (agent_id, model, day + "%"),
+            ).fetchone()
+        finally:
+            conn.close()
+        return {"agent_id": agent_id, "model": model, "day": day,
+                "calls": row[0] or 0,
+                "tokens": (row[1] or 0) + (row[2] or 0),
+                "cost_usd": round(row[3] or 0.0, 6)}
+
+    def query_agent_monthly(self, agent_id: str, month: str = None) -> dict:
+        """One agent's calls/tokens/cost for a calendar month ('YYYY-MM',
+        default current), summed over the daily rollup."""
+        month = month or datetime.now(timezone.utc).strftime("%Y-%m")
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                """SELECT SUM(calls), SUM(input_tokens), SUM(output_tokens),
+                          SUM(cost_usd)
+                   FROM usage_rollup_daily WHERE agent_id = ? AND bucket LIKE ?""",
+                (agent_id, month + "%"),
+            ).fetchone()
+        finally:
+            conn.close()
+        return {"agent_id": agent_id, "month": month, "calls": row[0] or 0,
+                "tokens": (row[1] or 0) + (row[2] or 0),
+                "cost_usd": round(row[3] or 0.0, 6)}
+
+    def query_group_summary(self, agent_ids: list, since: str = None,
+                            until: str = None) -> dict:
+        """
+        Combined totals for a set of agents (a team, a label selection, a
+        shared budget pool) in one SQL pass — per-agent rows plus the
+        group rollup, never N separate queries.
+        """
+        if not agent_ids:
+            return {"agents": 0, "calls": 0, "input_tokens": 0,
+                    "output_tokens": 0, "cost_usd": 0.0, "per_agent": []}
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            placeholders = ",".join("?" * len(agent_ids))
+            query = f"""SELECT agent_id, COUNT(*) AS calls,
+                               SUM(input_tokens) AS input_tokens,
+                               SUM(output_tokens) AS output_tokens,
+                               SUM(cost_usd) AS cost_usd
+                        FROM usage_records
+                        WHERE agent_id IN ({placeholders})"""
+            params = list(agent_ids)
+            if since:
+                query += " AND created_at >= ?"
+                params.append(since)
+            if until:
+                query += " AND created_at < ?"
+                params.append(until)
+            query += " GROUP BY agent_id ORDER BY cost_usd DESC"
+            per_agent = [dict(r) for r in conn.execute(query, params).fetchall()]
+        finally:
+            conn.close()
+        return {
+            "agents": len(agent_ids),
+            "calls": sum(r["calls"] or 0 for r in per_agent),
+            "input_tokens": sum(r["input_tokens"] or 0 for r in per_agent),
+            "output_tokens": sum(r["output_tokens"] or 0 for r in per_agent),
+            "cost_usd": round(sum(r["cost_usd"] or 0 for r in per_agent), 6),
+            "per_agent": per_agent,
+        }
+
+    def query_global_monthly(self, month: str = None) -> dict:
+        """Kernel-wide spend for a calendar month ('YYYY-MM', default
+        current). Served from the daily rollup, not a raw-table scan —
+        run rebuild_rollups() once on data that predates the rollups."""
+        month = month or datetime.now(timezone.utc).strftime("%Y-%m")
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                """SELECT SUM(calls) AS calls, SUM(cost_usd) AS cost_usd
+                   FROM usage_rollup_daily WHERE bucket LIKE ?""",
+                (month + "%",),
+            ).fetchone()
+            return {"month": month, "calls": row[0] or 0, "cost_usd": row[1] or 0.0}
+        finally:
+            conn.close()
+
+    def query_summary_by_tenant(self, since: str = None,
+                                until: str = None) -> list:
+        """
+        Spend/token totals grouped by tenant — the chargeback view for
+        hosted deployments, highest spend first. Records without a
+        tenant land in an 'untenanted' bucket (agent-owned traffic) so
+        the column still sums to the invoice.
+        """
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            query = """SELECT COALESCE(tenant_id, 'untenanted') AS tenant_id,
+                              COUNT(*) AS calls,
+                              COUNT(DISTINCT agent_id) AS agents,
+                              SUM(input_tokens) AS input_tokens,
+                              SUM(output_tokens) AS output_tokens,
+                              SUM(cost_usd) AS cost_usd
+                       FROM usage_records WHERE 1=1"""
+            params = []
+            if since:
+                query += " AND created_at >= ?"
+                params.append(since)
+            if until:
+                query += " AND created_at < ?"
+                params.append(until)
+            query += " GROUP BY COALESCE(tenant_id, 'untenanted') ORDER BY cost_usd DESC"
+            rows = [dict(r) for r in conn.execute(query, params).fetchall()]
+            for row in rows:
+                row["cost_usd"] = round(row["cost_usd"] or 0, 6)
+            return rows
+        finally:
+            conn.close()
+
+    def query_summary_by_tag(self, key: str, since: str = None,
+                             until: str = None) -> list:
+        """
+        Spend/token totals grouped by one attribution tag's values —
+        'split spend by campaign', highest spend first. Records without
+        the tag land in an 'untagged' bucket so the column still sums
+        to total spend. Served from the raw table (tags aren't rolled
+        up), so under sampling the split is approximate.
+        """
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            value = "COALESCE(json_extract(tags, '$.' || ?), 'untagged')"
+            query = f"""SELECT {value} AS value,
+                               COUNT(*) AS calls,
+                               COUNT(DISTINCT agent_id) AS agents,
+                               SUM(input_tokens) AS input_tokens,
+                               SUM(output_tokens) AS output_tokens,
+                               SUM(cost_usd) AS cost_usd
+                        FROM usage_records WHERE 1=1"""
+            params = [key]
+            if since:
+                query += " AND created_at >= ?"
+                params.append(since)
+            if until:
+                query += " AND created_at < ?"
+                params.append(until)
+            query += f" GROUP BY {value} ORDER BY cost_usd DESC"
+            params.append(key)
+            rows = [dict(r) for r in conn.execute(query, params).fetchall()]
+            for row in rows:
+                row["cost_usd"] = round(row["cost_usd"] or 0, 6)
+            return rows
+        finally:
+            conn.close()
+
+    def purge_tenant(self, tenant_id: str, before: str = None) -> dict:
+        """
+        Tenant-scoped cleanup: delete one tenant's raw usage records
+        (optionally only those before a cutoff) — offboarding and data
+        retention requests. The agent-keyed rollups keep the historical
+        totals; run rebuild_rollups() afterwards if they must forget
+        the tenant too.
+        """
+        conn = self._connect()
+        try:
+            query = "DELETE FROM usage_records WHERE tenant_id = ?"
+            params = [tenant_id]
+            if before:
+                query += " AND created_at < ?"
+                params.append(before)
+            deleted = conn.execute(query, params).rowcount
+            conn.commit()
+        finally:
+            conn.close()
+        log.info(f"[USAGE] Purged {deleted} records for tenant {tenant_id}"
+                 + (f" before {before}" if before else ""))
+        return {"tenant_id": tenant_id, "deleted": deleted, "before": before}
+
+    def query_tenant_monthly(self, tenant_id: str, month: str = None) -> dict:
+        """One tenant's spend for a calendar month ('YYYY-MM', default current)."""
+        month = month or datetime.now(timezone.utc).strftime("%Y-%m")
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                """SELECT COUNT(*) AS calls, SUM(cost_usd) AS cost_usd
+                   FROM usage_records WHERE tenant_id = ? AND created_at LIKE ?""",
+                (tenant_id, month + "%"),
+            ).fetchone()
+            return {"tenant_id": tenant_id, "month": month,
+                    "calls": row[0], "cost_usd": row[1] or 0.0}
+        finally:
+            conn.close()
+
+
+    def query_by_conversation(self, since: str = None, until: str = None,
+                              limit: int = 100) -> list:
+        """
+        Per-conversation rollups: cost, turns, tokens, tool calls and
+        wall-clock duration, most expensive first. Tool counts come from
+        the turn transcript table when transcripts were recorded.
+        """
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            query = """SELECT conversation_id,
+                              COUNT(*) AS turns,
+                              SUM(input_tokens) AS input_tokens,
+                              SUM(output_tokens) AS output_tokens,
+                              SUM(cost_usd) AS cost_usd,
+                              MIN(created_at) AS first_turn_at,
+                              MAX(created_at) AS last_turn_at
+                       FROM usage_records
+                       WHERE conversation_id IS NOT NULL"""
+            params = []
+            if since:
+                query += " AND created_at >= ?"
+                params.append(since)
+            if until:
+                query += " AND created_at < ?"
+                params.append(until)
+            query += " GROUP BY conversation_id ORDER BY cost_usd DESC LIMIT ?"
+            params.append(limit)
+            rollups = [dict(r) for r in conn.execute(query, params).fetchall()]
+
+            for rollup in rollups:
+                try:
+                    start = datetime.fromisoformat(rollup["first_turn_at"])
+                    end = datetime.fromisoformat(rollup["last_turn_at"])
+                    rollup["duration_seconds"] = round((end - start).total_seconds(), 1)
+                except (ValueError, TypeError):
+                    rollup["duration_seconds"] = None
+                # Tool call counts from transcripts, if recorded for this conversation
+                try:
+                    row = conn.execute(
+                        """SELECT SUM(json_array_length(tool_calls)) FROM turn_transcripts
+                           WHERE conversation_id = ?""",
+                        (rollup["conversation_id"],),
+                    ).fetchone()
+                    rollup["tool_calls"] = row[0] or 0
+                except sqlite3.OperationalError:
+                    rollup["tool_calls"] = None
+            return rollups
+        finally:
+            conn.close()
+
+    def query_by_session(self, session_id: str) -> dict:
+        """
+        Everything one gateway session spent: per-record detail plus a
+        rollup, so "which session burned my budget" has a direct answer.
+        """
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            records = [dict(r) for r in conn.execute(
+                """SELECT id, agent_id, conversation_id, model, purpose,
+                          input_tokens, output_tokens, cost_usd, created_at
+                   FROM usage_records WHERE session_id = ?
+                   ORDER BY created_at""",
+                (session_id,),
+            ).fetchall()]
+        finally:
+            conn.close()
+        return {
+            "session_id": session_id,
+            "calls": len(records),
+            "input_tokens": sum(r["input_tokens"] or 0 for r in records),
+            "output_tokens": sum(r["output_tokens"] or 0 for r in records),
+            "cost_usd": round(sum(r["cost_usd"] or 0 for r in records), 6),
+            "records": records,
+        }
+
+    def top_sessions(self, since: str = None, limit: int = 20) -> list:
+        """Most expensive sessions first — the session-level twin of
+        query_by_conversation."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            query = """SELECT session_id, COUNT(*) AS calls,
+                              SUM(input_tokens) AS input_tokens,
+                              SUM(output_tokens) AS output_tokens,
+                              SUM(cost_usd) AS cost_usd,
+                              MIN(created_at) AS first_call_at,
+                              MAX(created_at) AS last_call_at
+                       FROM usage_records WHERE session_id IS NOT NULL"""
+            params = []
+            if since:
+                query += " AND created_at >= ?"
+                params.append(since)
+            query += " GROUP BY session_id ORDER BY cost_usd DESC LIMIT ?"
+            params.append(limit)
+            return [dict(r) for r in conn.execute(query, params).fetchall()]
+        finally:
+            conn.close()
+
+    @staticmethod
+    def anonymize_agent_id(agent_id: str) -> str:
+        """Stable salted hash of an agent ID for anonymized exports."""
+        return hashlib.sha256(f"{ANON_SALT}:{agent_id}".encode()).hexdigest()[:16]
+
+    def export_records(self, since: str = None, until: str = None,
+                       anonymize: bool = False, limit: int = 10000) -> list:
+        """
+        Export raw usage records for a time range. With anonymize=True,
+        agent IDs are replaced by salted hashes and tenant/user/conversation
+        identifiers and attribution tags (which can name customers) are
+        stripped, so the export is safe to share with vendors or publish
+        as benchmark data.
+        """
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            query = "SELECT * FROM usage_records WHERE 1=1"
+            params = []
+            if since:
+                query += " AND created_at >= ?"
+                params.append(since)
+            if until:
+                query += " AND created_at < ?"
+                params.append(until)
+            query += " ORDER BY created_at LIMIT ?"
+            params.append(limit)
+            records = [dict(r) for r in conn.execute(query, params).fetchall()]
+        finally:
+            conn.close()
+
+        if anonymize:
+            for record in records:
+                record["agent_id"] = self.anonymize_agent_id(record["agent_id"])
+                for field in ANON_STRIPPED_FIELDS:
+                    record.pop(field, None)
+        return records
+
+    def query_events(self, agent_id: str = None, model: str = None,
+                     since: str = None, until: str = None,
+                     cursor: int = 0, limit: int = 100) -> dict:
+        """
+        Raw usage events with agent/model/time-range filters and cursor
+        pagination — the listing counterpart to the aggregate queries.
+        The cursor is the last row id of the previous page (rows come
+        back oldest first); next_cursor is None on the final page.
+        """
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            query = "SELECT * FROM usage_records WHERE id > ?"
+            params = [int(cursor or 0)]
+            if agent_id:
+                query += " AND agent_id = ?"
+                params.append(agent_id)
+            if model:
+                query += " AND model = ?"
+                params.append(model)
+            if since:
+                query += " AND created_at >= ?"
+                params.append(since)
+            if until:
+                query += " AND created_at < ?"
+                params.append(until)
+            # Fetch one extra row to learn whether another page exists
+            query += " ORDER BY id LIMIT ?"
+            params.append(limit + 1)
+            rows = [dict(r) for r in conn.execute(query, params).fetchall()]
+        finally:
+            conn.close()
+        has_more = len(rows) > limit
+        events = rows[:limit]
+        return {
+            "events": events,
+            "count": len(events),
+            "next_cursor": events[-1]["id"] if has_more and events else None,
+        }
+
+    def _export_cursor(self, conn, since: str, until: str):
+        """Streaming cursor over a time range, oldest first."""
+        query = "SELECT * FROM usage_records WHERE 1=1"
+        params = []
+        if since:
+            query += " AND created_at >= ?"
+            params.append(since)
+        if until:
+            query += " AND created_at < ?"
+            params.append(until)
+        query += " ORDER BY created_at"
+        return conn.execute(query, params)
+
+    def query_events_stream(self, since: str = None, until: str = None,
+                            agent_id: str = None, chunk_size: int = 1000):
+        """
+        Generator over raw usage records, oldest first, fetched in
+        chunks of `chunk_size` — memory stays bounded no matter how many
+        millions of rows the range covers. The connection is held open
+        until the generator is exhausted or closed, so drain it promptly.
+        """
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            query = "SELECT * FROM usage_records WHERE 1=1"
+            params = []
+            if since:
+                query += " AND created_at >= ?"
+                params.append(since)
+            if until:
+                query += " AND created_at < ?"
+                params.append(until)
+            if agent_id:
+                query += " AND agent_id = ?"
+                params.append(agent_id)
+            query += " ORDER BY created_at"
+            cursor = conn.execute(query, params)
+            while True:
+                chunk = cursor.fetchmany(chunk_size)
+                if not chunk:
+                    break
+                for row in chunk:
+                    yield dict(row)
+        finally:
+            conn.close()
+
+    def export_to_file(self, path: str, fmt: str = "csv", since: str = None,
+                       until: str = None, chunk_size: int = 1000) -> dict:
+        """
+        Stream usage records for a time range into a CSV or Parquet file
+        for finance reconciliation and offline analysis. Rows are read in
+        chunks of `chunk_size`, so a year of events never sits in memory.
+        Parquet needs pyarrow on the box; the error says so if it isn't.
+        """
+        if fmt not in ("csv", "parquet"):
+            return {"error": f"Unsupported format: {fmt} (csv or parquet)"}
+        if fmt == "parquet":
+            try:
+                import pyarrow as pa
+                import pyarrow.parquet as pq
+            except ImportError:
+                return {"error": "Parquet export requires pyarrow "
+                                 "(pip install pyarrow)"}
+
+        os.makedirs(os.path.dirname(path) or ".", exist_ok=True)
+        conn = self._connect()
+        rows_written = 0
+        try:
+            conn.row_factory = sqlite3.Row
+            cursor = self._export_cursor(conn, since, until)
+            columns = [d[0] for d in cursor.description]
+
+            if fmt == "csv":
+                with open(path, "w", newline="") as f:
+                    writer = csv.writer(f)
+                    writer.writerow(columns)
+                    while True:
+                        chunk = cursor.fetchmany(chunk_size)
+                        if not chunk:
+                            break
+                        writer.writerows([tuple(r) for r in chunk])
+                        rows_written += len(chunk)
+            else:
+                pq_writer = None
+                try:
+                    while True:
+                        chunk = cursor.fetchmany(chunk_size)
+                        if not chunk:
+                            break
+                        table = pa.Table.from_pylist([dict(r) for r in chunk])
+                        if pq_writer is None:
+                            pq_writer = pq.ParquetWriter(path, table.schema)
+                        pq_writer.write_table(table)
+                        rows_written += len(chunk)
+                    if pq_writer is None:
+                        # No rows — still produce a valid (empty) file
+                        table = pa.Table.from_pylist([], schema=pa.schema(
+                            [(c, pa.string()) for c in columns]))
+                        pq.write_table(table, path)
+                finally:
+                    if pq_writer is not None:
+                        pq_writer.close()
+        finally:
+            conn.close()
+        log.info(f"[USAGE] Exported {rows_written} records to {path} ({fmt})")
+        return {"path": path, "format": fmt, "rows": rows_written,
+                "since": since, "until": until}
+
+    def export_report(self, since: str = None, anonymize: bool = False) -> dict:
+        """Per-agent cost report, optionally with hashed agent IDs."""
+        totals = self.agent_totals(since=since)
+        if anonymize:
+            for row in totals:
+                row["agent_id"] = self.anonymize_agent_id(row["agent_id"])
+        top_conversations = self.query_by_conversation(since=since, limit=10)
+        if anonymize:
+            for rollup in top_conversations:
+                rollup["conversation_id"] = self.anonymize_agent_id(rollup["conversation_id"])
+        return {
+            "generated_at": self._now(),
+            "since": since,
+            "anonymized": anonymize,
+            "total_cost_usd": round(sum(r["cost_usd"] or 0 for r in totals), 6),
+            "agents": totals,
+            "top_conversations": top_conversations,
+        }
+
+
+__all__ = ["UsageStore", "estimate_turn_cost", "estimate_tokens", "COST_PER_M"]
@@ -0,0 +1,222 @@
+#!/usr/bin/env python3
+"""
+Outbound Webhook Notifier for Leviathan Super-Brain
+===================================================
+Pipes kernel lifecycle events (agent crashed, budget exhausted, approval
+pending, workflow failed, ...) from the event bus into HTTP endpoints —
+PagerDuty, Slack incoming webhooks, anything that takes JSON. Features:
+  - per-endpoint topic filters (fnmatch patterns, e.g. "budget.*")
+  - optional payload template merged over the event body
+  - HMAC-SHA256 signing (X-Leviathan-Signature header)
+  - bounded retries with backoff per delivery
+
+The notifier holds one wildcard subscription on the shared bus and fans
+deliveries out per endpoint, so adding an endpoint never adds bus load.
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import time
+import uuid
+import hmac
+import hashlib
+import fnmatch
+import logging
+from datetime import datetime, timezone
+
+import requests
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+WEBHOOK_TIMEOUT_SECONDS = int(os.environ.get("WEBHOOK_TIMEOUT_SECONDS", "10"))
+WEBHOOK_MAX_ATTEMPTS = int(os.environ.get("WEBHOOK_MAX_ATTEMPTS", "3"))
+WEBHOOK_RETRY_BACKOFF_SECONDS = float(os.environ.get("WEBHOOK_RETRY_BACKOFF_SECONDS", "2"))
+
+log = logging.getLogger("webhook_notifier")
+
+
+class WebhookNotifier:
+    """Event-bus → HTTP bridge with signing, retries and topic filters."""
+
+    def __init__(self, db_path: str = DB_PATH, outbox=None):
+        self.db_path = db_path
+        # With a WebhookOutbox attached, deliveries go through the shared
+        # outbox (rotated keys, replay protection, attempt history)
+        # instead of the plain signing below
+        self.outbox = outbox
+        self.delivered = 0
+        self.failed = 0
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS webhook_endpoints (
+                    endpoint_id TEXT PRIMARY KEY,
+                    url TEXT NOT NULL,
+                    secret TEXT,
+                    topics TEXT NOT NULL,
+                    template TEXT,
+                    active INTEGER NOT NULL DEFAULT 1,
+                    created_at TEXT NOT NULL,
+                    last_delivery_at TEXT,
+                    last_error TEXT
+                )
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    # ── Endpoint management ──
+
+    def add_endpoint(self, url: str, topics: list, secret: str = None,
+                     template: dict = None) -> dict:
+        endpoint_id = f"hook-{uuid.uuid4().hex[:12]}"
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT INTO webhook_endpoints
+                   (endpoint_id, url, secret, topics, template, active, created_at)
+                   VALUES (?, ?, ?, ?, ?, 1, ?)""",
+                (endpoint_id, url, secret, json.dumps(topics),
+                 json.dumps(template) if template else None,
+                 datetime.now(timezone.utc).isoformat()),
+            )
+            conn.commit()
+            log.info(f"[WEBHOOK] Registered {endpoint_id} for topics {topics}")
+            return {"endpoint_id": endpoint_id, "url": url, "topics": topics}
+        finally:
+            conn.close()
+
+    def remove_endpoint(self, endpoint_id: str) -> bool:
+        conn = self._connect()
+        try:
+            removed = conn.execute(
+                "DELETE FROM webhook_endpoints WHERE endpoint_id = ?", (endpoint_id,)
+            ).rowcount
+            conn.commit()
+            return removed > 0
+        finally:
+            conn.close()
+
+    def list_endpoints(self) -> list:
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            endpoints = []
+            for row in conn.execute(
+                "SELECT * FROM webhook_endpoints ORDER BY created_at"
+            ).fetchall():
+                endpoint = dict(row)
+                endpoint["topics"] = json.loads(endpoint["topics"])
+                endpoint["template"] = json.loads(endpoint["template"]) if endpoint["template"] else None
+                endpoint["secret"] = "***" if endpoint["secret"] else None
+                endpoints.append(endpoint)
+            return endpoints
+        finally:
+            conn.close()
+
+    # ── Delivery ──
+
+    @staticmethod
+    def _sign(secret: str, body: bytes) -> str:
+        return hmac.new(secret.encode(), body, hashlib.sha256).hexdigest()
+
+    def _deliver(self, endpoint: dict, body: dict) -> bool:
+        if self.outbox is not None:
+            result = self.outbox.send(endpoint["endpoint_id"], endpoint["url"],
+                                      body, secret=endpoint["secret"])
+            ok = result["status"] == "delivered"
+            self._record_outcome(
+                endpoint["endpoint_id"],
+                None if ok else result["attempts"][-1]["outcome"])
+            if ok:
+                self.delivered += 1
+            else:
+                self.failed += 1
+            return ok
+        payload = json.dumps(body).encode()
+        headers = {"Content-Type": "application/json"}
+        if endpoint["secret"]:
+            headers["X-Leviathan-Signature"] = self._sign(endpoint["secret"], payload)
+
+        last_error = None
+        for attempt in range(1, WEBHOOK_MAX_ATTEMPTS + 1):
+            try:
+                resp = requests.post(endpoint["url"], data=payload,
+                                     headers=headers, timeout=WEBHOOK_TIMEOUT_SECONDS)
+                if resp.status_code < 300:
+                    self._record_outcome(endpoint["endpoint_id"], None)
+                    self.delivered += 1
+                    return True
+                last_error = f"HTTP {resp.status_code}"
+            except Exception as e:
+                last_error = str(e)
+            if attempt < WEBHOOK_MAX_ATTEMPTS:
+                time.sleep(WEBHOOK_RETRY_BACKOFF_SECONDS * attempt)
+
+        self._record_outcome(endpoint["endpoint_id"], last_error)
+        self.failed += 1
+        log.warning(f"[WEBHOOK] Delivery to {endpoint['endpoint_id']} failed "
+                    f"after {WEBHOOK_MAX_ATTEMPTS} attempts: {last_error}")
+        return False
+
+    def _record_outcome(self, endpoint_id: str, error: str):
+        conn = self._connect()
+        try:
+            conn.execute(
+                "UPDATE webhook_endpoints SET last_delivery_at = ?, last_error = ? "
+                "WHERE endpoint_id = ?",
+                (datetime.now(timezone.utc).isoformat(), error, endpoint_id),
+            )
+            conn.commit()
+        finally:
+            conn.close()
+
+    def handle_event(self, event: dict):
+        """Bus subscriber handler: fan the event out to matching endpoints."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            endpoints = [dict(r) for r in conn.execute(
+                "SELECT * FROM webhook_endpoints WHERE active = 1"
+            ).fetchall()]
+        finally:
+            conn.close()
+
+        for endpoint in endpoints:
+            topics = json.loads(endpoint["topics"])
+            if not any(fnmatch.fnmatch(event["topic"], t) for t in topics):
+                continue
+            body = {
+                "event": event["topic"],
+                "sequence": event["sequence"],
+                "published_at": event["published_at"],
+                "payload": event["payload"],
+            }
+            if endpoint["template"]:
+                body = {**json.loads(endpoint["template"]), **body}
+            self._deliver(endpoint, body)
+
+    def attach(self, bus):
+        """Subscribe to the shared event bus (one wildcard subscription)."""
+        bus.subscribe("*", "webhook-notifier", self.handle_event)
+
+    def stats(self) -> dict:
+        return {"delivered": self.delivered, "failed": self.failed,
+                "endpoints": len(self.list_endpoints())}
+
+
+__all__ = ["WebhookNotifier"]
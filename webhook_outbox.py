@@ -0,0 +1,332 @@
+#!/usr/bin/env python3
+"""
+Webhook Outbox for Leviathan Super-Brain
+========================================
+The shared delivery path for every outbound webhook (event notifier,
+billing sink, approval callbacks): one place that signs, retries, and
+remembers. On top of the plain HMAC signing the notifier already did,
+the outbox adds:
+
+  - per-endpoint signing keys with rotation-with-overlap: a rotated-out
+    key keeps verifying for WEBHOOK_KEY_OVERLAP_SECONDS, and every
+    delivery carries X-Leviathan-Key-Id so receivers know which key
+    signed it — rotation without a deploy-coordinated flag day
+  - replay protection: a strictly monotonic per-endpoint timestamp and
+    a random nonce are part of the signed material
+    (sign(ts + "." + nonce + "." + body)), so a captured delivery can't
+    be replayed or re-ordered past the receiver's high-water mark
+  - a queryable delivery history: every delivery row records each
+    attempt's outcome, the key used and the nonce sent
+
+Author: Leviathan DevOps
+"""
+
+import sqlite3
+import json
+import os
+import time
+import uuid
+import hmac
+import hashlib
+import secrets
+import logging
+from datetime import datetime, timezone
+
+import requests
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DB_PATH = os.environ.get("SUPER_BRAIN_DB_PATH", "/data/hydra-brain.db")
+OUTBOX_TIMEOUT_SECONDS = int(os.environ.get("OUTBOX_TIMEOUT_SECONDS", "10"))
+OUTBOX_MAX_ATTEMPTS = int(os.environ.get("OUTBOX_MAX_ATTEMPTS", "3"))
+OUTBOX_RETRY_BACKOFF_SECONDS = float(
+    os.environ.get("OUTBOX_RETRY_BACKOFF_SECONDS", "2"))
+# A rotated-out key keeps verifying for this long
+WEBHOOK_KEY_OVERLAP_SECONDS = int(
+    os.environ.get("WEBHOOK_KEY_OVERLAP_SECONDS", "3600"))
+# Delivery history kept per endpoint (oldest pruned past this)
+OUTBOX_HISTORY_LIMIT = int(os.environ.get("OUTBOX_HISTORY_LIMIT", "500"))
+
+log = logging.getLogger("webhook_outbox")
+
+
+class WebhookOutbox:
+    """Signed, replay-protected webhook delivery with attempt history."""
+
+    def __init__(self, db_path: str = DB_PATH):
+        self.db_path = db_path
+        self.delivered = 0
+        self.failed = 0
+        self.ensure_schema()
+
+    def _connect(self) -> sqlite3.Connection:
+        conn = sqlite3.connect(self.db_path, timeout=10)
+        conn.execute("PRAGMA journal_mode=WAL;")
+        conn.execute("PRAGMA busy_timeout=5000;")
+        return conn
+
+    def ensure_schema(self):
+        conn = self._connect()
+        try:
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS outbox_keys (
+                    key_id TEXT PRIMARY KEY,
+                    endpoint_id TEXT NOT NULL,
+                    secret TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    retired_at TEXT
+                )
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_outbox_keys_endpoint
+                ON outbox_keys(endpoint_id, retired_at)
+            """)
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS outbox_deliveries (
+                    delivery_id TEXT PRIMARY KEY,
+                    endpoint_id TEXT NOT NULL,
+                    url TEXT NOT NULL,
+                    status TEXT NOT NULL,
+                    key_id TEXT,
+                    nonce TEXT NOT NULL,
+                    signed_timestamp INTEGER NOT NULL,
+                    attempts TEXT NOT NULL,
+                    created_at TEXT NOT NULL
+                )
+            """)
+            conn.execute("""
+                CREATE INDEX IF NOT EXISTS idx_outbox_deliveries_endpoint
+                ON outbox_deliveries(endpoint_id, created_at)
+            """)
+            # Per-endpoint monotonic timestamp high-water mark — two
+            # deliveries in the same millisecond still sign distinct,
+            # strictly increasing timestamps
+            conn.execute("""
+                CREATE TABLE IF NOT EXISTS outbox_timestamps (
+                    endpoint_id TEXT PRIMARY KEY,
+                    last_timestamp INTEGER NOT NULL
+                )
+            """)
+            conn.commit()
+        finally:
+            conn.close()
+
+    @staticmethod
+    def _now() -> str:
+        return datetime.now(timezone.utc).isoformat()
+
+    # ── Signing keys ──
+
+    def rotate_key(self, endpoint_id: str, secret: str = None) -> dict:
+        """
+        Make a new signing key current for an endpoint. The previous
+        current key is retired but stays inside the overlap window, so
+        receivers that haven't picked up the new key yet keep verifying.
+        Returns the new key (the one time the secret is shown).
+        """
+        secret = secret or secrets.token_hex(32)
+        key_id = f"whk-{uuid.uuid4().hex[:12]}"
+        now = self._now()
+        conn = self._connect()
+        try:
+            conn.execute(
+                "UPDATE outbox_keys SET retired_at = ? "
+                "WHERE endpoint_id = ? AND retired_at IS NULL",
+                (now, endpoint_id),
+            )
+            conn.execute(
+                """INSERT INTO outbox_keys
+                   (key_id, endpoint_id, secret, created_at)
+                   VALUES (?, ?, ?, ?)""",
+                (key_id, endpoint_id, secret, now),
+            )
+            conn.commit()
+        finally:
+            conn.close()
+        log.info(f"[OUTBOX] Key rotated for {endpoint_id}: {key_id} "
+                 f"(previous key verifies {WEBHOOK_KEY_OVERLAP_SECONDS}s more)")
+        return {"endpoint_id": endpoint_id, "key_id": key_id,
+                "secret": secret,
+                "overlap_seconds": WEBHOOK_KEY_OVERLAP_SECONDS}
+
+    def current_key(self, endpoint_id: str) -> dict:
+        """The active signing key for an endpoint, or None."""
+        conn = self._connect()
+        try:
+            row = conn.execute(
+                """SELECT key_id, secret FROM outbox_keys
+                   WHERE endpoint_id = ? AND retired_at IS NULL
+                   ORDER BY created_at DESC LIMIT 1""",
+                (endpoint_id,),
+            ).fetchone()
+            return {"key_id": row[0], "secret": row[1]} if row else None
+        finally:
+            conn.close()
+
+    def valid_keys(self, endpoint_id: str) -> list:
+        """Current key plus retired keys still inside the overlap window
+        — what a receiver should accept."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            rows = [dict(r) for r in conn.execute(
+                "SELECT * FROM outbox_keys WHERE endpoint_id = ?",
+                (endpoint_id,)).fetchall()]
+        finally:
+            conn.close()
+        valid = []
+        now = datetime.now(timezone.utc)
+        for row in rows:
+            if row["retired_at"] is None:
+                valid.append(row)
+                continue
+            retired = datetime.fromisoformat(row["retired_at"])
+            if (now - retired).total_seconds() <= WEBHOOK_KEY_OVERLAP_SECONDS:
+                valid.append(row)
+        return valid
+
+    # ── Delivery ──
+
+    @staticmethod
+    def _sign(secret: str, timestamp: int, nonce: str, body: bytes) -> str:
+        material = f"{timestamp}.{nonce}.".encode() + body
+        return hmac.new(secret.encode(), material, hashlib.sha256).hexdigest()
+
+    def _next_timestamp(self, conn, endpoint_id: str) -> int:
+        """Strictly monotonic per-endpoint milliseconds — the receiver's
+        replay check only has to remember one number."""
+        now_ms = int(time.time() * 1000)
+        row = conn.execute(
+            "SELECT last_timestamp FROM outbox_timestamps WHERE endpoint_id = ?",
+            (endpoint_id,),
+        ).fetchone()
+        ts = max(now_ms, (row[0] + 1) if row else 0)
+        conn.execute(
+            """INSERT INTO outbox_timestamps (endpoint_id, last_timestamp)
+               VALUES (?, ?)
+               ON CONFLICT (endpoint_id) DO UPDATE SET
+                   last_timestamp = excluded.last_timestamp""",
+            (endpoint_id, ts),
+        )
+        return ts
+
+    def send(self, endpoint_id: str, url: str, body: dict,
+             secret: str = None) -> dict:
+        """
+        Deliver one webhook with retries. Signing uses the endpoint's
+        current rotated key when one exists, else the caller-provided
+        static secret, else the delivery goes unsigned. Every attempt
+        lands in the delivery history.
+        """
+        payload = json.dumps(body).encode()
+        nonce = secrets.token_hex(12)
+        key = self.current_key(endpoint_id)
+        key_id = key["key_id"] if key else ("static" if secret else None)
+        signing_secret = key["secret"] if key else secret
+
+        conn = self._connect()
+        try:
+            timestamp = self._next_timestamp(conn, endpoint_id)
+            conn.commit()
+        finally:
+            conn.close()
+
+        headers = {"Content-Type": "application/json",
+                   "X-Leviathan-Timestamp": str(timestamp),
+                   "X-Leviathan-Nonce": nonce}
+        if signing_secret:
+            headers["X-Leviathan-Key-Id"] = key_id
+            headers["X-Leviathan-Signature"] = self._sign(
+                signing_secret, timestamp, nonce, payload)
+
+        delivery_id = f"dlv-{uuid.uuid4().hex[:12]}"
+        attempts = []
+        status = "failed"
+        for attempt in range(1, OUTBOX_MAX_ATTEMPTS + 1):
+            started = self._now()
+            try:
+                resp = requests.post(url, data=payload, headers=headers,
+                                     timeout=OUTBOX_TIMEOUT_SECONDS)
+                outcome = f"HTTP {resp.status_code}"
+                ok = resp.status_code < 300
+            except Exception as e:
+                outcome = str(e)
+                ok = False
+            attempts.append({"attempt": attempt, "at": started,
+                             "outcome": outcome, "ok": ok})
+            if ok:
+                status = "delivered"
+                break
+            if attempt < OUTBOX_MAX_ATTEMPTS:
+                time.sleep(OUTBOX_RETRY_BACKOFF_SECONDS * attempt)
+
+        conn = self._connect()
+        try:
+            conn.execute(
+                """INSERT INTO outbox_deliveries
+                   (delivery_id, endpoint_id, url, status, key_id, nonce,
+                    signed_timestamp, attempts, created_at)
+                   VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)""",
+                (delivery_id, endpoint_id, url, status, key_id, nonce,
+                 timestamp, json.dumps(attempts), self._now()),
+            )
+            # Opportunistic prune, oldest first
+            conn.execute(
+                """DELETE FROM outbox_deliveries WHERE endpoint_id = ?
+                   AND delivery_id NOT IN (
+                       SELECT delivery_id FROM outbox_deliveries
+                       WHERE endpoint_id = ?
+                       ORDER BY created_at DESC LIMIT ?)""",
+                (endpoint_id, endpoint_id, OUTBOX_HISTORY_LIMIT),
+            )
+            conn.commit()
+        finally:
+            conn.close()
+
+        if status == "delivered":
+            self.delivered += 1
+        else:
+            self.failed += 1
+            log.warning(f"[OUTBOX] {delivery_id} to {endpoint_id} failed "
+                        f"after {len(attempts)} attempt(s): "
+                        f"{attempts[-1]['outcome']}")
+        return {"delivery_id": delivery_id, "status": status,
+                "attempts": attempts, "key_id": key_id, "nonce": nonce,
+                "signed_timestamp": timestamp}
+
+    # ── History ──
+
+    def deliveries(self, endpoint_id: str = None, limit: int = 100) -> list:
+        """Delivery attempt history, newest first."""
+        conn = self._connect()
+        try:
+            conn.row_factory = sqlite3.Row
+            query = "SELECT * FROM outbox_deliveries WHERE 1=1"
+            params = []
+            if endpoint_id:
+                query += " AND endpoint_id = ?"
+                params.append(endpoint_id)
+            query += " ORDER BY created_at DESC LIMIT ?"
+            params.append(limit)
+            rows = [dict(r) for r in conn.execute(query, params).fetchall()]
+            for row in rows:
+                row["attempts"] = json.loads(row["attempts"])
+            return rows
+        finally:
+            conn.close()
+
+    def status(self) -> dict:
+        conn = self._connect()
+        try:
+            endpoints = conn.execute(
+                "SELECT COUNT(DISTINCT endpoint_id) FROM outbox_keys "
+                "WHERE retired_at IS NULL").fetchone()[0]
+        finally:
+            conn.close()
+        return {"delivered": self.delivered, "failed": self.failed,
+                "endpoints_with_keys": endpoints,
+                "key_overlap_seconds": WEBHOOK_KEY_OVERLAP_SECONDS}
+
+
+__all__ = ["WebhookOutbox", "WEBHOOK_KEY_OVERLAP_SECONDS"]
@@ -0,0 +1,558 @@
+#!/usr/bin/env python3
+"""
+Leviathan Setup Wizard
+======================
+Guided first-run setup for the Super Brain stack: collects API keys,
+database path and gateway settings, validates them, and writes an
+env-format config file the servers read at boot.
+
+Runs two ways:
+  Interactive:  python3 wizard.py
+  Headless:     python3 wizard.py --non-interactive --answers answers.json
+                python3 wizard.py --non-interactive --set DEEPSEEK_API_KEY=sk-...
+
+Headless mode takes an answers file (JSON or TOML) and/or --set flags,
+applies the same validation as the interactive flow, and produces the
+same config output — so provisioning automation and Docker images can
+run setup unattended without a TTY.
+
+Onboarding flows verify credentials before writing them:
+  add-gateway:   python3 wizard.py add-gateway whatsapp|telegram|smtp
+  add-provider:  python3 wizard.py add-provider deepseek|openrouter
+
+Author: Leviathan DevOps
+"""
+
+import argparse
+import json
+import os
+import smtplib
+import sys
+
+import requests
+
+DEFAULT_OUTPUT = os.environ.get("LEVIATHAN_CONFIG_PATH", "leviathan.env")
+
+
+# ──────────────────────────────────────────────
+# Question definitions
+# ──────────────────────────────────────────────
+
+def _non_empty(value):
+    return bool(value.strip()) or "value must not be empty"
+
+
+def _valid_path(value):
+    parent = os.path.dirname(value) or "."
+    return os.path.isdir(parent) or f"parent directory does not exist: {parent}"
+
+
+def _valid_port(value):
+    try:
+        port = int(value)
+    except ValueError:
+        return "port must be an integer"
+    return (1 <= port <= 65535) or "port must be 1-65535"
+
+
+# (key, prompt, default, required, validator)
+QUESTIONS = [
+    ("DEEPSEEK_API_KEY", "DeepSeek API key", "", True, _non_empty),
+    ("OPENROUTER_API_KEY", "OpenRouter API key", "", True, _non_empty),
+    ("SUPER_BRAIN_API_KEY", "Super Brain API key (Bearer auth)", "super-brain-key-2026", True, _non_empty),
+    ("SUPER_BRAIN_DB_PATH", "SQLite database path", "/data/hydra-brain.db", True, _valid_path),
+    ("PORT", "HTTP port", "8080", True, _valid_port),
+    ("GITHUB_PAT", "GitHub PAT (optional, for forensic audits)", "", False, None),
+    ("DISCORD_BOT_TOKEN", "Discord bot token (optional)", "", False, None),
+    ("WHATSAPP_TOKEN", "WhatsApp Cloud API token (optional)", "", False, None),
+    ("WHATSAPP_PHONE_ID", "WhatsApp phone number ID (optional)", "", False, None),
+]
+
+
+# ──────────────────────────────────────────────
+# Answer collection & validation
+# ──────────────────────────────────────────────
+
+def validate_answers(answers: dict) -> list:
+    """
+    Validate a complete answer set against QUESTIONS.
+    Returns a list of error strings (empty list = valid).
+    """
+    errors = []
+    known_keys = {q[0] for q in QUESTIONS}
+    for key in answers:
+        if key not in known_keys:
+            errors.append(f"unknown config key: {key}")
+    for key, prompt, default, required, validator in QUESTIONS:
+        value = str(answers.get(key, default or ""))
+        if required and not value.strip():
+            errors.append(f"{key} is required ({prompt})")
+            continue
+        if validator and value.strip():
+            result = validator(value)
+            if result is not True:
+                errors.append(f"{key}: {result}")
+    return errors
+
+
+def apply_defaults(answers: dict) -> dict:
+    """Fill unanswered optional questions with their defaults."""
+    config = {}
+    for key, _prompt, default, _required, _validator in QUESTIONS:
+        config[key] = str(answers.get(key, default or ""))
+    return config
+
+
+def run_interactive() -> dict:
+    """Prompt for each question on the TTY, re-asking until valid."""
+    print("═" * 50)
+    print("  Leviathan Setup Wizard")
+    print("═" * 50)
+    answers = {}
+    for key, prompt, default, required, validator in QUESTIONS:
+        while True:
+            suffix = f" [{default}]" if default else (" (optional)" if not required else "")
+            value = input(f"{prompt}{suffix}: ").strip() or default
+            if required and not value:
+                print("  ✗ This field is required.")
+                continue
+            if validator and value:
+                result = validator(value)
+                if result is not True:
+                    print(f"  ✗ {result}")
+                    continue
+            answers[key] = value
+            break
+    return apply_defaults(answers)
+
+
+def load_answers_file(path: str) -> dict:
+    """Load an answers file. JSON by extension, TOML via tomllib otherwise."""
+    with open(path, "rb") as f:
+        if path.endswith(".json"):
+            return json.load(f)
+        try:
+            import tomllib
+        except ImportError:
+            raise SystemExit("TOML answers files require Python 3.11+ (tomllib)")
+        return tomllib.load(f)
+
+
+def run_headless(answers: dict) -> dict:
+    """Validate a pre-supplied answer set; exit non-zero on any error."""
+    errors = validate_answers(answers)
+    if errors:
+        for error in errors:
+            print(f"✗ {error}", file=sys.stderr)
+        raise SystemExit(1)
+    return apply_defaults(answers)
+
+
+# ──────────────────────────────────────────────
+# Output
+# ──────────────────────────────────────────────
+
+def write_config(config: dict, output_path: str):
+    """Write env-format config, skipping empty optional values."""
+    lines = ["# Generated by the Leviathan setup wizard"]
+    for key, value in config.items():
+        if value:
+            lines.append(f"{key}={value}")
+    with open(output_path, "w") as f:
+        f.write("\n".join(lines) + "\n")
+    os.chmod(output_path, 0o600)  # contains secrets
+    print(f"✓ Config written to {output_path}")
+
+
+# ──────────────────────────────────────────────
+# Gateway & provider onboarding flows
+# ──────────────────────────────────────────────
+
+def verify_whatsapp(token: str, phone_id: str) -> dict:
+    """Verify a WhatsApp Cloud API pairing by fetching the phone number record."""
+    try:
+        resp = requests.get(
+            f"https://graph.facebook.com/v19.0/{phone_id}",
+            headers={"Authorization": f"Bearer {token}"}, timeout=15,
+        )
+        if resp.status_code == 200:
+            display = resp.json().get("display_phone_number", "unknown")
+            return {"ok": True, "detail": f"paired to {display}"}
+        if resp.status_code in (401, 403):
+            return {"ok": False, "detail": "token rejected — regenerate it in Meta Business settings"}
+        return {"ok": False, "detail": f"API returned {resp.status_code} — check the phone number ID"}
+    except requests.RequestException as e:
+        return {"ok": False, "detail": f"network error: {e}"}
+
+
+def verify_telegram(bot_token: str) -> dict:
+    """Validate a Telegram bot token via getMe."""
+    try:
+        resp = requests.get(f"https://api.telegram.org/bot{bot_token}/getMe", timeout=15)
+        data = resp.json() if resp.status_code == 200 else {}
+        if data.get("ok"):
+            return {"ok": True, "detail": f"bot @{data['result'].get('username', '?')}"}
+        return {"ok": False, "detail": "token rejected — check it with @BotFather"}
+    except requests.RequestException as e:
+        return {"ok": False, "detail": f"network error: {e}"}
+
+
+def verify_smtp(host: str, port: str, username: str, password: str, test_to: str = None) -> dict:
+    """Log in to the SMTP server; optionally send a test message."""
+    try:
+        with smtplib.SMTP(host, int(port), timeout=20) as smtp:
+            smtp.starttls()
+            smtp.login(username, password)
+            if test_to:
+                smtp.sendmail(
+                    username, [test_to],
+                    f"From: {username}\r\nTo: {test_to}\r\nSubject: Leviathan SMTP test\r\n\r\n"
+                    "SMTP gateway onboarding test-send succeeded.",
+                )
+                return {"ok": True, "detail": f"login ok, test mail sent to {test_to}"}
+            return {"ok": True, "detail": "login ok (no test-send requested)"}
+    except smtplib.SMTPAuthenticationError:
+        return {"ok": False, "detail": "authentication failed — check username/password (app password?)"}
+    except Exception as e:
+        return {"ok": False, "detail": f"connection failed: {e}"}
+
+
+def verify_provider_key(provider: str, api_key: str) -> dict:
+    """Validate an LLM provider key with the cheapest available call."""
+    endpoints = {
+        "deepseek": ("https://api.deepseek.com/v1/models", "Bearer"),
+        "openrouter": ("https://openrouter.ai/api/v1/models", "Bearer"),
+    }
+    if provider not in endpoints:
+        return {"ok": False, "detail": f"unknown provider: {provider} (use deepseek/openrouter)"}
+    url, scheme = endpoints[provider]
+    try:
+        resp = requests.get(url, headers={"Authorization": f"{scheme} {api_key}"}, timeout=15)
+        if resp.status_code == 200:
+            return {"ok": True, "detail": "key accepted (models list fetched)"}
+        if resp.status_code in (401, 403):
+            return {"ok": False, "detail": "key rejected — check for typos or revocation"}
+        return {"ok": False, "detail": f"provider returned {resp.status_code}"}
+    except requests.RequestException as e:
+        return {"ok": False, "detail": f"network error: {e}"}
+
+
+# Per-flow question lists: (config key, prompt)
+GATEWAY_FLOWS = {
+    "whatsapp": [("WHATSAPP_TOKEN", "WhatsApp Cloud API token"),
+                 ("WHATSAPP_PHONE_ID", "WhatsApp phone number ID")],
+    "telegram": [("TELEGRAM_BOT_TOKEN", "Telegram bot token (from @BotFather)")],
+    "smtp": [("SMTP_HOST", "SMTP host"), ("SMTP_PORT", "SMTP port"),
+             ("SMTP_USERNAME", "SMTP username"), ("SMTP_PASSWORD", "SMTP password"),
+             ("SMTP_TEST_TO", "Send a test mail to (optional)")],
+}
+
+PROVIDER_FLOWS = {
+    "deepseek": [("DEEPSEEK_API_KEY", "DeepSeek API key")],
+    "openrouter": [("OPENROUTER_API_KEY", "OpenRouter API key")],
+}
+
+
+def _collect_flow_answers(flow_questions: list, answers: dict, interactive: bool) -> dict:
+    """Collect flow answers from pre-supplied values or TTY prompts."""
+    collected = {}
+    for key, prompt in flow_questions:
+        value = answers.get(key, "")
+        if not value and interactive:
+            value = input(f"{prompt}: ").strip()
+        collected[key] = value
+    return collected
+
+
+def _append_config(values: dict, output_path: str):
+    """Append verified keys to the config file (replacing existing lines)."""
+    existing = []
+    if os.path.exists(output_path):
+        with open(output_path) as f:
+            existing = [line.rstrip("\n") for line in f
+                        if line.split("=", 1)[0] not in values]
+    with open(output_path, "w") as f:
+        f.write("\n".join(existing + [f"{k}={v}" for k, v in values.items() if v]) + "\n")
+    os.chmod(output_path, 0o600)
+
+
+def run_gateway_flow(gateway: str, answers: dict, output_path: str, interactive: bool = True):
+    """Guided gateway onboarding: collect, verify, then write config."""
+    if gateway not in GATEWAY_FLOWS:
+        raise SystemExit(f"Unknown gateway: {gateway} (use {'/'.join(GATEWAY_FLOWS)})")
+    values = _collect_flow_answers(GATEWAY_FLOWS[gateway], answers, interactive)
+
+    if gateway == "whatsapp":
+        result = verify_whatsapp(values["WHATSAPP_TOKEN"], values["WHATSAPP_PHONE_ID"])
+    elif gateway == "telegram":
+        result = verify_telegram(values["TELEGRAM_BOT_TOKEN"])
+    else:
+        result = verify_smtp(values["SMTP_HOST"], values["SMTP_PORT"],
+                             values["SMTP_USERNAME"], values["SMTP_PASSWORD"],
+                             values.get("SMTP_TEST_TO") or None)
+
+    if not result["ok"]:
+        raise SystemExit(f"✗ {gateway} verification failed: {result['detail']}")
+    values.pop("SMTP_TEST_TO", None)  # not config, just for the test-send
+    _append_config(values, output_path)
+    print(f"✓ {gateway} gateway verified ({result['detail']}) and written to {output_path}")
+
+
+def run_provider_flow(provider: str, answers: dict, output_path: str, interactive: bool = True):
+    """Guided LLM provider onboarding: key validation via a cheap call."""
+    if provider not in PROVIDER_FLOWS:
+        raise SystemExit(f"Unknown provider: {provider} (use {'/'.join(PROVIDER_FLOWS)})")
+    values = _collect_flow_answers(PROVIDER_FLOWS[provider], answers, interactive)
+    key_name = PROVIDER_FLOWS[provider][0][0]
+
+    result = verify_provider_key(provider, values[key_name])
+    if not result["ok"]:
+        raise SystemExit(f"✗ {provider} verification failed: {result['detail']}")
+    _append_config(values, output_path)
+    print(f"✓ {provider} provider verified ({result['detail']}) and written to {output_path}")
+
+
+# ──────────────────────────────────────────────
+# Import from other agent frameworks
+# ──────────────────────────────────────────────
+
+# Field mappings: source field → manifest field
+OPENAI_ASSISTANT_MAP = {
+    "name": "name",
+    "instructions": "system_prompt",
+    "model": "model",
+    "description": "description",
+}
+
+CREWAI_AGENT_MAP = {
+    "role": "name",
+    "goal": "description",
+    "backstory": "system_prompt",
+    "llm": "model",
+}
+
+
+def _load_import_file(path: str) -> dict:
+    """Load a JSON or YAML export file."""
+    with open(path) as f:
+        if path.endswith((".yaml", ".yml")):
+            try:
+                import yaml
+            except ImportError:
+                raise SystemExit("YAML imports require PyYAML (pip install pyyaml)")
+            return yaml.safe_load(f)
+        return json.load(f)
+
+
+def convert_agent_definition(source: dict, fmt: str) -> dict:
+    """
+    Convert one foreign agent definition into an OpenFang-style manifest.
+    Returns {"manifest": ..., "mapped": [...], "unmapped": [...]}.
+    """
+    mapping = OPENAI_ASSISTANT_MAP if fmt == "openai" else CREWAI_AGENT_MAP
+    manifest = {"skills": []}
+    mapped, unmapped = [], []
+
+    for src_field, dst_field in mapping.items():
+        if src_field in source:
+            manifest[dst_field] = source[src_field]
+            mapped.append(src_field)
+
+    # OpenAI tools / CrewAI tools become advertised skills (names only —
+    # schemas need manual attention)
+    for tool in source.get("tools", []):
+        if isinstance(tool, dict):
+            tool_name = tool.get("function", {}).get("name") or tool.get("type") or "unknown"
+        else:
+            tool_name = str(tool)
+        manifest["skills"].append({"name": tool_name})
+    if source.get("tools"):
+        mapped.append("tools")
+
+    for field in source:
+        if field not in mapped and field != "tools":
+            unmapped.append(field)
+
+    return {"manifest": manifest, "mapped": mapped, "unmapped": unmapped}
+
+
+def run_import_flow(path: str, fmt: str, output_dir: str = "manifests"):
+    """
+    Import agent definitions from another framework's export and write
+    OpenFang manifests plus a migration report of what mapped cleanly
+    and what needs manual attention.
+    """
+    if fmt not in ("openai", "crewai", "langchain"):
+        raise SystemExit(f"Unknown import format: {fmt} (use openai/crewai/langchain)")
+    if fmt == "langchain":
+        fmt = "crewai"  # same YAML shape for our purposes
+
+    data = _load_import_file(path)
+    # OpenAI exports wrap assistants in {"data": [...]}; CrewAI YAML is
+    # either a list or {"agents": [...]}; a bare dict is a single agent.
+    if isinstance(data, dict):
+        agents = data.get("data") or data.get("agents") or [data]
+    else:
+        agents = data
+
+    os.makedirs(output_dir, exist_ok=True)
+    report = []
+    for i, source in enumerate(agents):
+        result = convert_agent_definition(source, fmt)
+        name = result["manifest"].get("name") or f"imported-agent-{i}"
+        safe_name = "".join(c if c.isalnum() or c in "-_" else "-" for c in str(name).lower())
+        out_path = os.path.join(output_dir, f"{safe_name}.json")
+        with open(out_path, "w") as f:
+            json.dump(result["manifest"], f, indent=2)
+        report.append({"agent": name, "manifest": out_path,
+                       "mapped": result["mapped"], "unmapped": result["unmapped"]})
+
+    print(f"✓ Imported {len(report)} agent(s) from {path} ({fmt} format)")
+    for entry in report:
+        print(f"  • {entry['agent']} → {entry['manifest']}")
+        print(f"    mapped: {', '.join(entry['mapped']) or 'nothing'}")
+        if entry["unmapped"]:
+            print(f"    needs manual attention: {', '.join(str(f) for f in entry['unmapped'])}")
+    return report
+
+
+# ──────────────────────────────────────────────
+# New-agent scaffolding
+# ──────────────────────────────────────────────
+
+# Template → (description, system prompt, trigger stanza, quota defaults, skills)
+AGENT_TEMPLATES = {
+    "chat-assistant": {
+        "description": "Conversational assistant reachable over chat gateways",
+        "system_prompt": ("You are a helpful assistant. Answer concisely, "
+                          "ask for clarification when the request is ambiguous."),
+        "triggers": [{"type": "gateway_message", "gateways": ["discord"]}],
+        "quotas": {"max_tokens_per_day": 200000, "max_cost_usd_per_day": 2.0},
+        "skills": [{"name": "chat.reply"}],
+    },
+    "cron-reporter": {
+        "description": "Scheduled reporter that posts summaries on a cron schedule",
+        "system_prompt": ("You produce short, factual status reports from the data "
+                          "you are given. No speculation, no filler."),
+        "triggers": [{"type": "cron", "schedule": "0 9 * * *"}],
+        "quotas": {"max_tokens_per_day": 50000, "max_cost_usd_per_day": 0.5},
+        "skills": [{"name": "report.generate"}],
+    },
+    "webhook-processor": {
+        "description": "Processes inbound webhook payloads and routes results",
+        "system_prompt": ("You transform structured webhook payloads into actions. "
+                          "Validate inputs, reject anything malformed."),
+        "triggers": [{"type": "webhook", "path": "/hooks/incoming"}],
+        "quotas": {"max_tokens_per_day": 100000, "max_cost_usd_per_day": 1.0},
+        "skills": [{"name": "webhook.process"}],
+    },
+}
+
+
+def run_new_agent_flow(name: str, template: str, output_dir: str = "manifests"):
+    """
+    Scaffold a manifest from a template: default prompt, trigger stanza
+    and quota defaults, registered in a disabled state ready for review.
+    """
+    if template not in AGENT_TEMPLATES:
+        raise SystemExit(f"Unknown template: {template} (use {'/'.join(AGENT_TEMPLATES)})")
+    tpl = AGENT_TEMPLATES[template]
+
+    safe_name = "".join(c if c.isalnum() or c in "-_" else "-" for c in name.lower())
+    manifest = {
+        "name": name,
+        "template": template,
+        "description": tpl["description"],
+        "enabled": False,  # review before first run
+        "system_prompt": tpl["system_prompt"],
+        "triggers": tpl["triggers"],
+        "quotas": tpl["quotas"],
+        "skills": tpl["skills"],
+    }
+
+    os.makedirs(output_dir, exist_ok=True)
+    out_path = os.path.join(output_dir, f"{safe_name}.json")
+    if os.path.exists(out_path):
+        raise SystemExit(f"✗ Manifest already exists: {out_path}")
+    with open(out_path, "w") as f:
+        json.dump(manifest, f, indent=2)
+
+    print(f"✓ Scaffolded '{name}' from template '{template}' → {out_path}")
+    print("  The agent is disabled — review the manifest, then register it:")
+    print(f"  curl -X POST $API/agents/register -d @{out_path}")
+    return manifest
+
+
+def build_parser() -> argparse.ArgumentParser:
+    parser = argparse.ArgumentParser(description="Leviathan setup wizard")
+    parser.add_argument("command", nargs="?", default="setup",
+                        choices=["setup", "add-gateway", "add-provider", "import-config", "new-agent"],
+                        help="wizard flow to run (default: setup)")
+    parser.add_argument("target", nargs="?", default=None,
+                        help="gateway/provider name, export file, or new agent name")
+    parser.add_argument("--template", default="chat-assistant",
+                        choices=sorted(AGENT_TEMPLATES),
+                        help="template for new-agent (default: chat-assistant)")
+    parser.add_argument("--format", default="openai", dest="import_format",
+                        choices=["openai", "crewai", "langchain"],
+                        help="source format for import-config (default: openai)")
+    parser.add_argument("--manifest-dir", default="manifests",
+                        help="output directory for imported manifests")
+    parser.add_argument("--non-interactive", action="store_true",
+                        help="run without a TTY, using --answers/--set")
+    parser.add_argument("--answers", metavar="FILE",
+                        help="answers file (JSON or TOML) for headless mode")
+    parser.add_argument("--set", metavar="KEY=VALUE", action="append", default=[],
+                        dest="overrides", help="set a single answer (repeatable)")
+    parser.add_argument("--output", default=DEFAULT_OUTPUT,
+                        help=f"config output path (default: {DEFAULT_OUTPUT})")
+    return parser
+
+
+def main(argv=None):
+    args = build_parser().parse_args(argv)
+
+    answers = {}
+    if args.answers:
+        answers.update(load_answers_file(args.answers))
+    for override in args.overrides:
+        if "=" not in override:
+            raise SystemExit(f"--set expects KEY=VALUE, got: {override}")
+        key, value = override.split("=", 1)
+        answers[key.strip()] = value.strip()
+
+    headless = args.non_interactive or bool(args.answers) or bool(args.overrides)
+
+    if args.command == "add-gateway":
+        if not args.target:
+            raise SystemExit(f"Usage: wizard.py add-gateway {'/'.join(GATEWAY_FLOWS)}")
+        run_gateway_flow(args.target, answers, args.output, interactive=not headless)
+        return
+    if args.command == "add-provider":
+        if not args.target:
+            raise SystemExit(f"Usage: wizard.py add-provider {'/'.join(PROVIDER_FLOWS)}")
+        run_provider_flow(args.target, answers, args.output, interactive=not headless)
+        return
+    if args.command == "import-config":
+        if not args.target:
+            raise SystemExit("Usage: wizard.py import-config EXPORT_FILE --format openai|crewai|langchain")
+        run_import_flow(args.target, args.import_format, args.manifest_dir)
+        return
+    if args.command == "new-agent":
+        if not args.target:
+            raise SystemExit(f"Usage: wizard.py new-agent NAME --template {'|'.join(sorted(AGENT_TEMPLATES))}")
+        run_new_agent_flow(args.target, args.template, args.manifest_dir)
+        return
+
+    if headless:
+        config = run_headless(answers)
+    else:
+        if not sys.stdin.isatty():
+            raise SystemExit("No TTY available — use --non-interactive with --answers or --set")
+        config = run_interactive()
+
+    write_config(config, args.output)
+
+
+if __name__ == "__main__":
+    main()
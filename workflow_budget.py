@@ -0,0 +1,270 @@
+#!/usr/bin/env python3
+"""
+Workflow Step Budget Tracker for Leviathan Super-Brain
+======================================================
+Per-step (and per-run) token/cost budget allocation for multi-stage
+workflows like the Hydra build pipeline. One runaway research step must
+not consume the budget intended for the rest of the pipeline — each step
+gets its own allowance, checked before the call and debited after.
+
+Budgets come from the workflow definition:
+    tracker = TokenBudgetTracker(
+        run_budget={"max_cost_usd": 10.0},
+        step_budgets={"Brain (R1 verify)": {"max_tokens": 20000},
+                      "default": {"max_cost_usd": 2.0}},
+    )
+
+Env overrides:
+  WORKFLOW_RUN_CAP_USD       — default per-run cost cap (default 10.0)
+  WORKFLOW_STEP_BUDGETS_JSON — JSON dict of step → budget
+
+Crossing 90/95/100% of the run budget (or a step's allocation) publishes
+a structured BudgetAlert on the event bus — each threshold fires once per
+scope, so subscribers get edges, not a firehose. check_budget() adds a
+burn-rate forecast (time-to-exhaustion over the recent window) so a
+scheduler can throttle proactively instead of slamming into 95%.
+
+Author: Leviathan DevOps
+"""
+
+import os
+import json
+import time
+import logging
+import threading
+from collections import deque
+
+from usage_store import COST_PER_M, DEFAULT_RATES
+
+# ──────────────────────────────────────────────
+# Configuration
+# ──────────────────────────────────────────────
+DEFAULT_RUN_CAP_USD = float(os.environ.get("WORKFLOW_RUN_CAP_USD", "10.0"))
+
+try:
+    ENV_STEP_BUDGETS = json.loads(os.environ.get("WORKFLOW_STEP_BUDGETS_JSON", "{}"))
+except json.JSONDecodeError:
+    ENV_STEP_BUDGETS = {}
+
+log = logging.getLogger("workflow_budget")
+
+# Budget fractions that trigger a BudgetAlert event
+ALERT_THRESHOLDS = (0.90, 0.95, 1.00)
+
+# Burn-rate forecasting: window of recent spend the rate is measured
+# over, and the time-to-exhaustion below which throttling is advised
+FORECAST_WINDOW_SECONDS = float(os.environ.get("FORECAST_WINDOW_SECONDS", "300"))
+THROTTLE_HORIZON_SECONDS = float(os.environ.get("THROTTLE_HORIZON_SECONDS", "60"))
+
+
+class TokenBudgetTracker:
+    """
+    Thread-safe per-run budget tracker with step-level allocations.
+
+    A budget dict may set 'max_tokens' and/or 'max_cost_usd'; unset
+    dimensions are unlimited. Unknown steps fall back to the 'default'
+    step budget if one is defined.
+    """
+
+    def __init__(self, run_budget: dict = None, step_budgets: dict = None,
+                 event_bus=None, run_id: str = None):
+        self.run_budget = run_budget or {"max_cost_usd": DEFAULT_RUN_CAP_USD}
+        self.step_budgets = {**ENV_STEP_BUDGETS, **(step_budgets or {})}
+        self.event_bus = event_bus
+        self.run_id = run_id
+        self.lock = threading.Lock()
+        self.run_spent = {"tokens": 0, "cost_usd": 0.0}
+        self.step_spent = {}  # step → {"tokens": int, "cost_usd": float, "calls": int}
+        self.blocked_steps = []
+        self._alerted = set()  # (scope, threshold) pairs already fired
+        self._samples = {}  # scope → deque of (monotonic_ts, tokens, cost)
+
+    def _budget_for(self, step: str) -> dict:
+        return self.step_budgets.get(step, self.step_budgets.get("default", {}))
+
+    @staticmethod
+    def _over(spent: dict, budget: dict) -> str:
+        """Return the exceeded dimension name, or '' if within budget."""
+        max_tokens = budget.get("max_tokens")
+        if max_tokens is not None and spent["tokens"] >= max_tokens:
+            return f"tokens ({spent['tokens']}/{max_tokens})"
+        max_cost = budget.get("max_cost_usd")
+        if max_cost is not None and spent["cost_usd"] >= max_cost:
+            return f"cost (${spent['cost_usd']:.4f}/${max_cost:.2f})"
+        return ""
+
+    def can_run(self, step: str):
+        """
+        Check whether a step may run. Returns (allowed: bool, reason: str).
+        Checks the run budget first, then the step's own allocation.
+        """
+        with self.lock:
+            over_run = self._over(self.run_spent, self.run_budget)
+            if over_run:
+                return False, f"run budget exhausted: {over_run}"
+            spent = self.step_spent.get(step, {"tokens": 0, "cost_usd": 0.0})
+            over_step = self._over(spent, self._budget_for(step))
+            if over_step:
+                return False, f"step budget exhausted: {over_step}"
+            return True, ""
+
+    def _utilization(self, spent: dict, budget: dict) -> float:
+        """Worst-dimension budget utilization, 0.0 when unlimited."""
+        ratios = []
+        if budget.get("max_tokens"):
+            ratios.append(spent["tokens"] / budget["max_tokens"])
+        if budget.get("max_cost_usd"):
+            ratios.append(spent["cost_usd"] / budget["max_cost_usd"])
+        return max(ratios) if ratios else 0.0
+
+    def _pending_alerts(self, step: str) -> list:
+        """Newly crossed thresholds for the run and the step. Call under
+        self.lock — marks them fired so each alerts exactly once."""
+        alerts = []
+        scopes = [("run", self.run_spent, self.run_budget)]
+        if step in self.step_spent:
+            scopes.append((f"step:{step}", self.step_spent[step], self._budget_for(step)))
+        for scope, spent, budget in scopes:
+            used = self._utilization(spent, budget)
+            for threshold in ALERT_THRESHOLDS:
+                if used >= threshold and (scope, threshold) not in self._alerted:
+                    self._alerted.add((scope, threshold))
+                    alerts.append({
+                        "type": "BudgetAlert",
+                        "run_id": self.run_id,
+                        "scope": scope,
+                        "threshold_pct": int(threshold * 100),
+                        "used_pct": round(used * 100, 1),
+                        "spent_tokens": spent["tokens"],
+                        "spent_cost_usd": round(spent["cost_usd"], 6),
+                        "budget": dict(budget),
+                    })
+        return alerts
+
+    def _emit(self, alerts: list):
+        """Publish outside the lock — bus handlers run synchronously."""
+        for alert in alerts:
+            log.warning(f"[BUDGET] {alert['scope']} at {alert['used_pct']}% "
+                        f"of budget ({alert['threshold_pct']}% threshold)")
+            if self.event_bus is not None:
+                try:
+                    self.event_bus.publish("budget.alert", alert)
+                except Exception as e:
+                    log.error(f"[BUDGET] Alert publish failed: {e}")
+
+    def record(self, step: str, model: str, input_tokens: int, output_tokens: int) -> float:
+        """Debit a completed call against the step and run budgets. Returns cost."""
+        rates = COST_PER_M.get(model, DEFAULT_RATES)
+        cost = (input_tokens * rates["input"] + output_tokens * rates["output"]) / 1_000_000
+        tokens = input_tokens + output_tokens
+        with self.lock:
+            self.run_spent["tokens"] += tokens
+            self.run_spent["cost_usd"] += cost
+            spent = self.step_spent.setdefault(step, {"tokens": 0, "cost_usd": 0.0, "calls": 0})
+            spent["tokens"] += tokens
+            spent["cost_usd"] += cost
+            spent["calls"] += 1
+            now = time.monotonic()
+            for scope in ("run", f"step:{step}"):
+                self._samples.setdefault(scope, deque(maxlen=1000)).append(
+                    (now, tokens, cost))
+            alerts = self._pending_alerts(step)
+        self._emit(alerts)
+        return cost
+
+    def _burn_rate(self, scope: str) -> tuple:
+        """(tokens/s, cost/s) over the forecast window. Call under
+        self.lock. Zero rates when there's too little history to tell."""
+        samples = self._samples.get(scope)
+        if not samples or len(samples) < 2:
+            return 0.0, 0.0
+        now = time.monotonic()
+        cutoff = now - FORECAST_WINDOW_SECONDS
+        recent = [s for s in samples if s[0] >= cutoff]
+        if len(recent) < 2:
+            return 0.0, 0.0
+        span = max(now - recent[0][0], 1.0)
+        return (sum(s[1] for s in recent) / span,
+                sum(s[2] for s in recent) / span)
+
+    def forecast_exhaustion(self, step: str = None) -> dict:
+        """
+        Estimate time-to-exhaustion from the recent burn rate — for the
+        run budget, or a step's allocation when `step` is given. The
+        scheduler throttles on 'throttle_advised' instead of slamming
+        into the 95% alert.
+        """
+        with self.lock:
+            if step is None:
+                scope, spent, budget = "run", self.run_spent, self.run_budget
+            else:
+                scope = f"step:{step}"
+                spent = self.step_spent.get(step, {"tokens": 0, "cost_usd": 0.0})
+                budget = self._budget_for(step)
+            tokens_per_s, cost_per_s = self._burn_rate(scope)
+
+            horizons = []
+            if budget.get("max_tokens") and tokens_per_s > 0:
+                horizons.append((budget["max_tokens"] - spent["tokens"]) / tokens_per_s)
+            if budget.get("max_cost_usd") and cost_per_s > 0:
+                horizons.append((budget["max_cost_usd"] - spent["cost_usd"]) / cost_per_s)
+            seconds = round(max(0.0, min(horizons)), 1) if horizons else None
+            return {
+                "scope": scope,
+                "window_seconds": FORECAST_WINDOW_SECONDS,
+                "burn_tokens_per_second": round(tokens_per_s, 1),
+                "burn_cost_usd_per_second": round(cost_per_s, 6),
+                "seconds_to_exhaustion": seconds,
+                "throttle_advised": (seconds is not None
+                                     and seconds <= THROTTLE_HORIZON_SECONDS),
+            }
+
+    def check_budget(self, step: str) -> dict:
+        """
+        can_run plus the forecast in one structured result — what a
+        scheduler wants before dispatching a step: may it run now, and
+        how long until it can't.
+        """
+        allowed, reason = self.can_run(step)
+        result = {"allowed": allowed, "reason": reason,
+                  "forecast": self.forecast_exhaustion()}
+        step_forecast = self.forecast_exhaustion(step)
+        if step_forecast["seconds_to_exhaustion"] is not None:
+            result["step_forecast"] = step_forecast
+        if result["forecast"]["throttle_advised"] or \
+                result.get("step_forecast", {}).get("throttle_advised"):
+            result["throttle_advised"] = True
+        else:
+            result["throttle_advised"] = False
+        return result
+
+    def mark_blocked(self, step: str, reason: str):
+        """Record that a step was skipped for budget reasons (for the run report)."""
+        with self.lock:
+            self.blocked_steps.append({"step": step, "reason": reason})
+        log.warning(f"[BUDGET] Step '{step}' blocked: {reason}")
+
+    def status(self) -> dict:
+        with self.lock:
+            return {
+                "run_budget": self.run_budget,
+                "run_spent": {
+                    "tokens": self.run_spent["tokens"],
+                    "cost_usd": round(self.run_spent["cost_usd"], 6),
+                },
+                "steps": {
+                    step: {**spent, "cost_usd": round(spent["cost_usd"], 6),
+                           "budget": self._budget_for(step)}
+                    for step, spent in self.step_spent.items()
+                },
+                "blocked_steps": list(self.blocked_steps),
+            }
+
+    def status_with_forecast(self) -> dict:
+        """status() plus the run-level exhaustion forecast."""
+        status = self.status()
+        status["forecast"] = self.forecast_exhaustion()
+        return status
+
+
+__all__ = ["TokenBudgetTracker"]